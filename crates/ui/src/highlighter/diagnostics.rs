@@ -1,16 +1,18 @@
 use std::{
     cmp::Ordering,
     ops::{Deref, Range},
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
     usize,
 };
 
 use gpui::{px, App, HighlightStyle, Hsla, SharedString, UnderlineStyle};
 use ropey::Rope;
+use serde::Deserialize;
 use sum_tree::{Bias, SeekTarget, SumTree};
 
 use crate::{
     input::{Position, RopeExt as _},
-    ActiveTheme,
+    ActiveTheme, IconName,
 };
 
 pub type DiagnosticRelatedInformation = lsp_types::DiagnosticRelatedInformation;
@@ -77,7 +79,7 @@ impl From<lsp_types::Diagnostic> for Diagnostic {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
 pub enum DiagnosticSeverity {
     #[default]
     Hint,
@@ -99,6 +101,15 @@ impl From<lsp_types::DiagnosticSeverity> for DiagnosticSeverity {
 }
 
 impl DiagnosticSeverity {
+    /// Returns the icon to represent this severity in lists.
+    pub fn icon(&self) -> IconName {
+        match self {
+            Self::Error => IconName::CircleX,
+            Self::Warning => IconName::TriangleAlert,
+            Self::Info | Self::Hint => IconName::Info,
+        }
+    }
+
     pub(crate) fn bg(&self, cx: &App) -> Hsla {
         let theme = &cx.theme().highlight_theme;
 
@@ -244,6 +255,13 @@ impl SeekTarget<'_, DiagnosticSummary, DiagnosticSummary> for usize {
 pub struct DiagnosticSet {
     text: Rope,
     diagnostics: SumTree<DiagnosticEntry>,
+    version: usize,
+}
+
+/// Returns a new version for the [`DiagnosticSet`], unique in all sets.
+fn next_version() -> usize {
+    static VERSION: AtomicUsize = AtomicUsize::new(0);
+    VERSION.fetch_add(1, AtomicOrdering::Relaxed) + 1
 }
 
 impl DiagnosticSet {
@@ -251,9 +269,15 @@ impl DiagnosticSet {
         Self {
             text: text.clone(),
             diagnostics: SumTree::new(&()),
+            version: next_version(),
         }
     }
 
+    /// Returns the version of the diagnostics, it's changed when the diagnostics are changed.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
     pub fn reset(&mut self, text: &Rope) {
         self.text = text.clone();
         self.clear();
@@ -271,6 +295,7 @@ impl DiagnosticSet {
             },
             &(),
         );
+        self.version = next_version();
     }
 
     pub fn extend<D, I>(&mut self, diagnostics: D)
@@ -288,7 +313,10 @@ impl DiagnosticSet {
     }
 
     pub fn clear(&mut self) {
-        self.diagnostics = SumTree::new(&());
+        if !self.diagnostics.is_empty() {
+            self.diagnostics = SumTree::new(&());
+            self.version = next_version();
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -331,10 +359,54 @@ impl DiagnosticSet {
         styles
     }
 
-    #[allow(unused)]
     pub(crate) fn iter(&self) -> impl Iterator<Item = &DiagnosticEntry> {
         self.diagnostics.iter()
     }
+
+    /// Returns all diagnostics, in the order they were pushed.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.iter().map(|entry| &entry.diagnostic)
+    }
+
+    /// Returns the first diagnostic that starts after the `offset`,
+    /// wrapping around to the start of the text.
+    ///
+    /// If `severity` is Some, only diagnostics with that severity are considered.
+    pub(crate) fn next_entry(
+        &self,
+        offset: usize,
+        severity: Option<DiagnosticSeverity>,
+    ) -> Option<&DiagnosticEntry> {
+        let entries = || {
+            self.iter()
+                .filter(move |entry| severity.map_or(true, |s| entry.severity == s))
+        };
+
+        entries()
+            .filter(|entry| entry.range.start > offset)
+            .min_by_key(|entry| entry.range.start)
+            .or_else(|| entries().min_by_key(|entry| entry.range.start))
+    }
+
+    /// Returns the last diagnostic that starts before the `offset`,
+    /// wrapping around to the end of the text.
+    ///
+    /// If `severity` is Some, only diagnostics with that severity are considered.
+    pub(crate) fn previous_entry(
+        &self,
+        offset: usize,
+        severity: Option<DiagnosticSeverity>,
+    ) -> Option<&DiagnosticEntry> {
+        let entries = || {
+            self.iter()
+                .filter(move |entry| severity.map_or(true, |s| entry.severity == s))
+        };
+
+        entries()
+            .filter(|entry| entry.range.start < offset)
+            .max_by_key(|entry| entry.range.start)
+            .or_else(|| entries().max_by_key(|entry| entry.range.start))
+    }
 }
 
 #[cfg(test)]
@@ -389,7 +461,62 @@ mod tests {
         );
         assert_eq!(diagnostics.len(), 3);
 
+        let version = diagnostics.version();
         diagnostics.clear();
         assert_eq!(diagnostics.len(), 0);
+        assert_ne!(diagnostics.version(), version);
+
+        // Clear the empty diagnostics, e.g. reset on edit, is not a change.
+        let version = diagnostics.version();
+        diagnostics.reset(&text);
+        assert_eq!(diagnostics.version(), version);
+    }
+
+    #[test]
+    fn test_next_previous_entry() {
+        use ropey::Rope;
+
+        use super::{Diagnostic, DiagnosticSet, DiagnosticSeverity};
+
+        let text = Rope::from("let a = 1;\nlet b = 2;\nlet c = 3;");
+        let mut diagnostics = DiagnosticSet::new(&text);
+        diagnostics.push(
+            Diagnostic::new(Position::new(0, 4)..Position::new(0, 5), "unused a")
+                .with_severity(DiagnosticSeverity::Warning),
+        );
+        diagnostics.push(
+            Diagnostic::new(Position::new(1, 4)..Position::new(1, 5), "bad b")
+                .with_severity(DiagnosticSeverity::Error),
+        );
+        diagnostics.push(
+            Diagnostic::new(Position::new(2, 4)..Position::new(2, 5), "unused c")
+                .with_severity(DiagnosticSeverity::Warning),
+        );
+
+        let next = |offset, severity| {
+            diagnostics
+                .next_entry(offset, severity)
+                .map(|entry| entry.message.as_str())
+        };
+        let prev = |offset, severity| {
+            diagnostics
+                .previous_entry(offset, severity)
+                .map(|entry| entry.message.as_str())
+        };
+
+        assert_eq!(next(0, None), Some("unused a"));
+        assert_eq!(next(4, None), Some("bad b"));
+        assert_eq!(next(15, None), Some("unused c"));
+        // Wrap around to the first one.
+        assert_eq!(next(26, None), Some("unused a"));
+        assert_eq!(next(4, Some(DiagnosticSeverity::Warning)), Some("unused c"));
+        assert_eq!(next(0, Some(DiagnosticSeverity::Info)), None);
+
+        assert_eq!(prev(27, None), Some("unused c"));
+        assert_eq!(prev(26, None), Some("bad b"));
+        assert_eq!(prev(15, None), Some("unused a"));
+        // Wrap around to the last one.
+        assert_eq!(prev(4, None), Some("unused c"));
+        assert_eq!(prev(26, Some(DiagnosticSeverity::Error)), Some("bad b"));
     }
 }
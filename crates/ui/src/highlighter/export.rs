@@ -0,0 +1,324 @@
+use std::{collections::BTreeSet, fmt::Write as _, ops::Range};
+
+use gpui::{FontStyle, FontWeight, HighlightStyle, Hsla, SharedString};
+use ropey::Rope;
+
+use crate::{
+    Colorize as _,
+    highlighter::{HIGHLIGHT_NAMES, HighlightTheme, SyntaxHighlighter},
+};
+
+impl SyntaxHighlighter {
+    /// Create a highlighter for the `language` with the `text` parsed.
+    ///
+    /// This is used to highlight the code outside of an editor, no `Window` is required.
+    pub fn from_text(language: &str, text: &Rope) -> Self {
+        let mut highlighter = Self::new(language);
+        highlighter.update(None, text);
+        highlighter
+    }
+
+    /// Returns the styled runs of the whole text, the runs are ordered and not overlapped.
+    ///
+    /// The text without highlight has a default [`HighlightStyle`].
+    pub fn runs(&self, theme: &HighlightTheme) -> Vec<(Range<usize>, HighlightStyle)> {
+        self.styles(&(0..self.text.len()), theme)
+    }
+
+    /// Export the highlighted text as HTML with inline styles, the text is wrapped in `<pre><code>`.
+    ///
+    /// The editor background and foreground of the `theme` are used for the `<pre>`.
+    pub fn to_html(&self, theme: &HighlightTheme) -> String {
+        let mut pre_style = String::new();
+        if let Some(color) = theme.style.editor_background {
+            let _ = write!(pre_style, "background-color:{};", color.to_hex());
+        }
+        if let Some(color) = theme.style.editor_foreground {
+            let _ = write!(pre_style, "color:{};", color.to_hex());
+        }
+
+        let mut html = if pre_style.is_empty() {
+            "<pre><code>".to_string()
+        } else {
+            format!("<pre style=\"{}\"><code>", pre_style)
+        };
+        for (range, style) in self.runs(theme) {
+            let text = escape_html(&self.text.slice(range).to_string());
+            let css = style_to_css(&style);
+            if css.is_empty() {
+                html.push_str(&text);
+            } else {
+                let _ = write!(html, "<span style=\"{}\">{}</span>", css, text);
+            }
+        }
+        html.push_str("</code></pre>");
+        html
+    }
+
+    /// Export the highlighted text as HTML with CSS classes, the text is wrapped in `<pre><code>`.
+    ///
+    /// The class name is the `class_prefix` with the highlight name, e.g. `hl-keyword`, `hl-string-escape`.
+    /// Use [`HighlightTheme::to_css`] to generate the stylesheet.
+    pub fn to_html_with_classes(&self, class_prefix: &str) -> String {
+        let mut html = "<pre><code>".to_string();
+        for (range, name) in self.capture_runs() {
+            let text = escape_html(&self.text.slice(range).to_string());
+            match name {
+                Some(name) => {
+                    let _ = write!(
+                        html,
+                        "<span class=\"{}\">{}</span>",
+                        class_name(class_prefix, &name),
+                        text
+                    );
+                }
+                None => html.push_str(&text),
+            }
+        }
+        html.push_str("</code></pre>");
+        html
+    }
+
+    /// Export the highlighted text with ANSI 24-bit color escape sequences for the terminal.
+    ///
+    /// The styles are reset at the end of each line.
+    pub fn to_ansi(&self, theme: &HighlightTheme) -> String {
+        let mut output = String::new();
+        for (range, style) in self.runs(theme) {
+            let text = self.text.slice(range).to_string();
+            let sgr = style_to_sgr(&style);
+            for (ix, line) in text.split('\n').enumerate() {
+                if ix > 0 {
+                    output.push('\n');
+                }
+                if line.is_empty() {
+                    continue;
+                }
+                if sgr.is_empty() {
+                    output.push_str(line);
+                } else {
+                    let _ = write!(output, "\x1b[{}m{}\x1b[0m", sgr, line);
+                }
+            }
+        }
+        output
+    }
+
+    /// Returns the runs of the top-most highlight name for the whole text, not overlapped.
+    ///
+    /// The later capture is on the top of the earlier ones, the captures are swept by
+    /// the start and end offsets once.
+    fn capture_runs(&self) -> Vec<(Range<usize>, Option<SharedString>)> {
+        let len = self.text.len();
+        let captures = self.captures(&(0..len));
+
+        // The (offset, is_start, capture index) events, sorted by the offset.
+        let mut events = Vec::with_capacity(captures.len() * 2);
+        for (ix, (range, _)) in captures.iter().enumerate() {
+            let (start, end) = (range.start.min(len), range.end.min(len));
+            if start < end {
+                events.push((start, true, ix));
+                events.push((end, false, ix));
+            }
+        }
+        events.sort_unstable_by_key(|(offset, _, _)| *offset);
+
+        // The indices of the active captures, the last one is the top-most.
+        let mut active = BTreeSet::new();
+        let mut runs: Vec<(Range<usize>, Option<SharedString>)> = vec![];
+        let mut offset = 0;
+        let mut events = events.into_iter().peekable();
+        loop {
+            let next_offset = events.peek().map_or(len, |(offset, _, _)| *offset);
+            if next_offset > offset {
+                let range = offset..next_offset;
+                let name = active.last().map(|ix: &usize| captures[*ix].1.clone());
+                match runs.last_mut() {
+                    Some((last_range, last_name)) if *last_name == name => {
+                        last_range.end = range.end
+                    }
+                    _ => runs.push((range, name)),
+                }
+                offset = next_offset;
+            }
+
+            let Some((_, is_start, ix)) = events.next() else {
+                break;
+            };
+            if is_start {
+                active.insert(ix);
+            } else {
+                active.remove(&ix);
+            }
+        }
+        runs
+    }
+}
+
+impl HighlightTheme {
+    /// Generate the CSS stylesheet for the HTML exported by [`SyntaxHighlighter::to_html_with_classes`].
+    pub fn to_css(&self, class_prefix: &str) -> String {
+        let mut css = String::new();
+        if let Some(color) = self.style.editor_background {
+            let _ = writeln!(css, "pre {{ background-color: {}; }}", color.to_hex());
+        }
+        if let Some(color) = self.style.editor_foreground {
+            let _ = writeln!(css, "pre {{ color: {}; }}", color.to_hex());
+        }
+        for name in HIGHLIGHT_NAMES {
+            let Some(style) = self.style(name) else {
+                continue;
+            };
+            let rules = style_to_css(&style);
+            if !rules.is_empty() {
+                let _ = writeln!(css, ".{} {{ {} }}", class_name(class_prefix, name), rules);
+            }
+        }
+        css
+    }
+}
+
+fn class_name(class_prefix: &str, name: &str) -> String {
+    format!("{}{}", class_prefix, name.replace(['.', '_'], "-"))
+}
+
+/// Escape the special characters in HTML text or attribute value.
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn style_to_css(style: &HighlightStyle) -> String {
+    let mut css = String::new();
+    if let Some(color) = style.color {
+        let _ = write!(css, "color:{};", color.to_hex());
+    }
+    if let Some(color) = style.background_color {
+        let _ = write!(css, "background-color:{};", color.to_hex());
+    }
+    if let Some(weight) = style.font_weight {
+        let _ = write!(css, "font-weight:{};", weight.0 as u32);
+    }
+    if style.font_style == Some(FontStyle::Italic) {
+        css.push_str("font-style:italic;");
+    }
+    if style.underline.is_some() {
+        css.push_str("text-decoration:underline;");
+    } else if style.strikethrough.is_some() {
+        css.push_str("text-decoration:line-through;");
+    }
+    css
+}
+
+fn ansi_rgb(color: Hsla) -> String {
+    let rgb = color.to_rgb();
+    format!(
+        "{};{};{}",
+        (rgb.r * 255.).round() as u8,
+        (rgb.g * 255.).round() as u8,
+        (rgb.b * 255.).round() as u8
+    )
+}
+
+/// Returns the SGR parameters of the `style`, e.g. `1;38;2;255;0;0`.
+fn style_to_sgr(style: &HighlightStyle) -> String {
+    let mut params = vec![];
+    if style
+        .font_weight
+        .map_or(false, |weight| weight.0 >= FontWeight::SEMIBOLD.0)
+    {
+        params.push("1".to_string());
+    }
+    if style.font_style == Some(FontStyle::Italic) {
+        params.push("3".to_string());
+    }
+    if style.underline.is_some() {
+        params.push("4".to_string());
+    }
+    if style.strikethrough.is_some() {
+        params.push("9".to_string());
+    }
+    if let Some(color) = style.color {
+        params.push(format!("38;2;{}", ansi_rgb(color)));
+    }
+    if let Some(color) = style.background_color {
+        params.push(format!("48;2;{}", ansi_rgb(color)));
+    }
+    params.join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{FontWeight, HighlightStyle, hsla};
+
+    use super::{class_name, escape_html, style_to_css, style_to_sgr};
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_class_name() {
+        assert_eq!(class_name("hl-", "keyword"), "hl-keyword");
+        assert_eq!(class_name("hl-", "string.escape"), "hl-string-escape");
+        assert_eq!(class_name("", "link_text"), "link-text");
+    }
+
+    #[test]
+    fn test_style_to_css_and_sgr() {
+        let style = HighlightStyle {
+            color: Some(hsla(0., 1., 0.5, 1.)),
+            font_weight: Some(FontWeight::BOLD),
+            ..Default::default()
+        };
+        assert_eq!(style_to_css(&style), "color:#FF0000;font-weight:700;");
+        assert_eq!(style_to_sgr(&style), "1;38;2;255;0;0");
+        assert_eq!(style_to_sgr(&HighlightStyle::default()), "");
+    }
+
+    #[test]
+    fn test_export() {
+        use ropey::Rope;
+
+        use crate::highlighter::{HighlightTheme, SyntaxHighlighter};
+
+        let text = Rope::from_str("[\"<a>\", 1]");
+        let highlighter = SyntaxHighlighter::from_text("json", &text);
+        let theme = HighlightTheme::default_light();
+
+        let runs = highlighter.runs(&theme);
+        assert_eq!(runs.first().map(|(r, _)| r.start), Some(0));
+        assert_eq!(runs.last().map(|(r, _)| r.end), Some(text.len()));
+
+        // The capture runs cover the whole text in order.
+        let capture_runs = highlighter.capture_runs();
+        assert_eq!(capture_runs.first().map(|(r, _)| r.start), Some(0));
+        assert_eq!(capture_runs.last().map(|(r, _)| r.end), Some(text.len()));
+        assert!(
+            capture_runs
+                .windows(2)
+                .all(|w| w[0].0.end == w[1].0.start && w[0].1 != w[1].1)
+        );
+
+        let html = highlighter.to_html_with_classes("hl-");
+        assert!(html.starts_with("<pre><code>"));
+        assert!(html.contains("<span class=\"hl-string\">&quot;&lt;a&gt;&quot;</span>"));
+
+        let ansi = highlighter.to_ansi(&theme);
+        assert!(ansi.contains("\x1b[0m"));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::highlighter::{HighlightTheme, LanguageRegistry};
+use crate::highlighter::{loader::new_parser, HighlightTheme, LanguageRegistry};
 use crate::input::RopeExt;
 
 use anyhow::{anyhow, Context, Result};
@@ -6,8 +6,10 @@ use gpui::{HighlightStyle, SharedString};
 
 use ropey::{ChunkCursor, Rope};
 use std::{
+    cell::RefCell,
     collections::{BTreeSet, HashMap},
     ops::Range,
+    sync::Arc,
     usize,
 };
 use sum_tree::Bias;
@@ -35,10 +37,15 @@ pub struct SyntaxHighlighter {
     local_ref_capture_index: Option<u32>,
 
     /// The last parsed source text.
-    text: Rope,
+    pub(super) text: Rope,
     parser: Parser,
+    ts_language: tree_sitter::Language,
     /// The last parsed tree.
     tree: Option<Tree>,
+    /// Increased on each edit, used to discard the outdated background parse result.
+    revision: usize,
+    /// The locals matched in the last highlight, reused until the text or the tree is changed.
+    locals_cache: RefCell<Option<LocalsCache>>,
 }
 
 struct TextProvider<'a>(&'a Rope);
@@ -155,6 +162,42 @@ impl<'a> sum_tree::Dimension<'a, HighlightSummary> for Range<usize> {
     }
 }
 
+/// A local variable node matched by the `locals.scm` query.
+#[derive(Debug, Clone, PartialEq)]
+enum LocalNode {
+    /// The node is a `@local.definition`.
+    Definition,
+    /// The node is a `@local.reference`, with the highlight name of its definition.
+    Reference(SharedString),
+}
+
+/// A `@local.scope` on the scope stack of the locals pass.
+struct LocalScope {
+    end: usize,
+    /// False if the scope sets `(#set! local.scope-inherits false)`,
+    /// the references inside can't see the definitions of the parent scopes.
+    inherits: bool,
+    /// The indices of the definitions in this scope.
+    defs: Vec<usize>,
+}
+
+/// The locals of the [`SyntaxHighlighter::match_locals`], cached for the `revision`.
+struct LocalsCache {
+    revision: usize,
+    /// The byte range the locals were matched in.
+    range: Range<usize>,
+    locals: Arc<HashMap<Range<usize>, LocalNode>>,
+}
+
+struct LocalDef {
+    name: String,
+    /// The references before this offset are not resolved to this definition,
+    /// this is the end of the `@local.definition-value` if present, e.g. `let x = x + 1`.
+    value_end: usize,
+    /// The first highlight name of the definition node.
+    highlight: Option<SharedString>,
+}
+
 impl SyntaxHighlighter {
     /// Create a new SyntaxHighlighter for HTML.
     pub fn new(lang: &str) -> Self {
@@ -181,10 +224,7 @@ impl SyntaxHighlighter {
             ));
         };
 
-        let mut parser = Parser::new();
-        parser
-            .set_language(&config.language)
-            .context("parse set_language")?;
+        let mut parser = new_parser(&config.language)?;
 
         // Concatenate the query strings, keeping track of the start offset of each section.
         let mut query_source = String::new();
@@ -301,7 +341,10 @@ impl SyntaxHighlighter {
             local_ref_capture_index,
             text: Rope::new(),
             parser,
+            ts_language: config.language.clone(),
             tree: None,
+            revision: 0,
+            locals_cache: RefCell::new(None),
         })
     }
 
@@ -332,25 +375,53 @@ impl SyntaxHighlighter {
             .unwrap_or(self.parser.parse("", None).unwrap());
         old_tree.edit(&edit);
 
-        let new_tree = self.parser.parse_with_options(
-            &mut move |offset, _| {
-                if offset >= text.len() {
-                    ""
-                } else {
-                    let (chunk, chunk_byte_ix) = text.chunk(offset);
-                    &chunk[offset - chunk_byte_ix..]
-                }
-            },
-            Some(&old_tree),
-            None,
-        );
-
-        let Some(new_tree) = new_tree else {
+        let Some(new_tree) = parse_rope(&mut self.parser, text, Some(&old_tree)) else {
             return;
         };
 
         self.tree = Some(new_tree);
         self.text = text.clone();
+        self.revision += 1;
+    }
+
+    /// Apply the `edit` to the last parsed tree without parsing.
+    ///
+    /// The highlights are shifted by the edit until the parse of [`Self::parse_task`] is applied,
+    /// this is used to avoid blocking on parsing the large text.
+    pub fn edit(&mut self, edit: InputEdit, text: &Rope) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.edit(&edit);
+        }
+        self.text = text.clone();
+        self.revision += 1;
+    }
+
+    /// Returns the revision and a function to parse the current text,
+    /// the function can be run in a background thread.
+    ///
+    /// Use [`Self::set_parsed_tree`] to apply the parsed tree.
+    pub fn parse_task(&self) -> (usize, impl FnOnce() -> Option<Tree> + Send + 'static) {
+        let language = self.ts_language.clone();
+        let old_tree = self.tree.clone();
+        let text = self.text.clone();
+
+        (self.revision, move || {
+            let mut parser = new_parser(&language).ok()?;
+            parse_rope(&mut parser, &text, old_tree.as_ref())
+        })
+    }
+
+    /// Apply the `tree` parsed by [`Self::parse_task`].
+    ///
+    /// Returns false if the text has been changed since the `revision`, the tree is discarded.
+    pub fn set_parsed_tree(&mut self, revision: usize, tree: Tree) -> bool {
+        if revision != self.revision {
+            return false;
+        }
+
+        self.tree = Some(tree);
+        self.locals_cache.take();
+        true
     }
 
     /// Match the visible ranges of nodes in the Tree for highlighting.
@@ -365,6 +436,7 @@ impl SyntaxHighlighter {
         };
 
         let root_node = tree.root_node();
+        let locals = self.match_locals(tree, query, &range);
 
         let source = &self.text;
         let mut cursor = QueryCursor::new();
@@ -385,6 +457,11 @@ impl SyntaxHighlighter {
                 continue;
             }
 
+            // The captures of the injections and locals patterns are not highlights.
+            if query_match.pattern_index < self.highlights_pattern_index {
+                continue;
+            }
+
             for cap in query_match.captures {
                 let node = cap.node;
 
@@ -393,7 +470,17 @@ impl SyntaxHighlighter {
                 };
 
                 let node_range: Range<usize> = node.start_byte()..node.end_byte();
-                let highlight_name = SharedString::from(highlight_name.to_string());
+                let mut highlight_name = SharedString::from(highlight_name.to_string());
+
+                match locals.get(&node_range) {
+                    // Skip the patterns with `(#is-not? local)` for the local variables.
+                    Some(_) if self.non_local_variable_patterns[query_match.pattern_index] => {
+                        continue;
+                    }
+                    // The references inherit the highlight of their definition.
+                    Some(LocalNode::Reference(name)) => highlight_name = name.clone(),
+                    _ => {}
+                }
 
                 // Merge near range and same highlight name
                 let last_item = highlights.last();
@@ -429,6 +516,157 @@ impl SyntaxHighlighter {
         highlights
     }
 
+    /// Match the `locals.scm` patterns to find the local variables in the `range`,
+    /// returns the local variable nodes by their byte range.
+    ///
+    /// The definitions and scopes are tracked from the start of the top-level node containing
+    /// the `range`, so the definitions before the visible range are also resolved.
+    ///
+    /// The result is cached until the text or the tree is changed, it's reused if the `range` is
+    /// covered, e.g. the styles of the lines in the same frame.
+    fn match_locals(
+        &self,
+        tree: &Tree,
+        query: &Query,
+        range: &Range<usize>,
+    ) -> Arc<HashMap<Range<usize>, LocalNode>> {
+        let mut locals = HashMap::new();
+        if self.locals_pattern_index == self.highlights_pattern_index {
+            return Arc::new(locals);
+        }
+
+        let root_node = tree.root_node();
+        let start = root_node
+            .first_child_for_byte(range.start)
+            .map_or(range.start, |node| node.start_byte().min(range.start));
+
+        if let Some(cache) = self.locals_cache.borrow().as_ref() {
+            if cache.revision == self.revision
+                && cache.range.start <= start
+                && range.end <= cache.range.end
+            {
+                return cache.locals.clone();
+            }
+        }
+
+        let mut scopes = vec![LocalScope {
+            end: usize::MAX,
+            inherits: false,
+            defs: vec![],
+        }];
+        let mut defs: Vec<LocalDef> = vec![];
+        let mut def_indices: HashMap<Range<usize>, usize> = HashMap::new();
+        let mut refs: Vec<(Range<usize>, usize)> = vec![];
+
+        let mut cursor = QueryCursor::new();
+        cursor.set_byte_range(start..range.end);
+        let mut captures = cursor.captures(query, root_node, TextProvider(&self.text));
+        while let Some((query_match, index)) = captures.next() {
+            let pattern_index = query_match.pattern_index;
+            if pattern_index < self.locals_pattern_index {
+                continue;
+            }
+
+            let capture = query_match.captures[*index];
+            let node_range = capture.node.byte_range();
+            while scopes.len() > 1
+                && scopes
+                    .last()
+                    .map_or(false, |scope| node_range.start >= scope.end)
+            {
+                scopes.pop();
+            }
+
+            if pattern_index >= self.highlights_pattern_index {
+                // The captures are ordered by the pattern index for the same node,
+                // so the definition is found before its highlights.
+                if let Some(&ix) = def_indices.get(&node_range) {
+                    if defs[ix].highlight.is_none()
+                        && !self.non_local_variable_patterns[pattern_index]
+                    {
+                        defs[ix].highlight = query
+                            .capture_names()
+                            .get(capture.index as usize)
+                            .map(|name| SharedString::from(name.to_string()));
+                    }
+                }
+                continue;
+            }
+
+            let capture_index = Some(capture.index);
+            if capture_index == self.local_scope_capture_index {
+                let inherits = !query.property_settings(pattern_index).iter().any(|prop| {
+                    prop.key.as_ref() == "local.scope-inherits"
+                        && prop.value.as_deref() == Some("false")
+                });
+                scopes.push(LocalScope {
+                    end: node_range.end,
+                    inherits,
+                    defs: vec![],
+                });
+            } else if capture_index == self.local_def_capture_index {
+                let value_end = query_match
+                    .captures
+                    .iter()
+                    .find(|cap| Some(cap.index) == self.local_def_value_capture_index)
+                    .map_or(node_range.end, |cap| cap.node.end_byte());
+
+                def_indices.insert(node_range.clone(), defs.len());
+                if let Some(scope) = scopes.last_mut() {
+                    scope.defs.push(defs.len());
+                }
+                defs.push(LocalDef {
+                    name: self.text.slice(node_range.clone()).to_string(),
+                    value_end,
+                    highlight: None,
+                });
+            } else if capture_index == self.local_ref_capture_index
+                && !def_indices.contains_key(&node_range)
+            {
+                let name = self.text.slice(node_range.clone()).to_string();
+                for scope in scopes.iter().rev() {
+                    let def_ix = scope.defs.iter().rev().find(|&&ix| {
+                        defs[ix].name == name && node_range.start >= defs[ix].value_end
+                    });
+                    if let Some(&ix) = def_ix {
+                        refs.push((node_range.clone(), ix));
+                        break;
+                    }
+                    if !scope.inherits {
+                        break;
+                    }
+                }
+            }
+        }
+
+        for range in def_indices.into_keys() {
+            locals.insert(range, LocalNode::Definition);
+        }
+        for (range, ix) in refs {
+            if let Some(highlight) = defs[ix].highlight.clone() {
+                locals.insert(range, LocalNode::Reference(highlight));
+            }
+        }
+
+        let locals = Arc::new(locals);
+        self.locals_cache.replace(Some(LocalsCache {
+            revision: self.revision,
+            range: start..range.end,
+            locals: locals.clone(),
+        }));
+        locals
+    }
+
+    /// Returns the highlight captures (e.g. `comment`, `string`) of the nodes in the `range`.
+    ///
+    /// The ranges are byte offsets in the text, and they may overlap.
+    pub fn captures(&self, range: &Range<usize>) -> Vec<(Range<usize>, SharedString)> {
+        self.match_styles(range.clone())
+            .into_iter()
+            .map(|item| (item.range, item.name))
+            .collect()
+    }
+
     /// TODO: Use incremental parsing to handle the injection.
     fn handle_injection(
         &self,
@@ -454,10 +692,9 @@ impl SyntaxHighlighter {
         let Some(config) = LanguageRegistry::singleton().language(injection_language) else {
             return cache;
         };
-        let mut parser = Parser::new();
-        if parser.set_language(&config.language).is_err() {
+        let Ok(mut parser) = new_parser(&config.language) else {
             return cache;
-        }
+        };
 
         let source = content.as_bytes();
         let Some(tree) = parser.parse(source, None) else {
@@ -630,6 +867,22 @@ impl SyntaxHighlighter {
     }
 }
 
+/// Parse the `text` with the `old_tree` for incremental parsing.
+fn parse_rope(parser: &mut Parser, text: &Rope, old_tree: Option<&Tree>) -> Option<Tree> {
+    parser.parse_with_options(
+        &mut move |offset, _| {
+            if offset >= text.len() {
+                ""
+            } else {
+                let (chunk, chunk_byte_ix) = text.chunk(offset);
+                &chunk[offset - chunk_byte_ix..]
+            }
+        },
+        old_tree,
+        None,
+    )
+}
+
 /// To merge intersection ranges, let the subsequent range cover
 /// the previous overlapping range and split the previous range.
 ///
@@ -838,4 +1091,61 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_locals() {
+        let code = "fn foo(a: i32) -> i32 {\n    let b = a + 1;\n    let a = a * b;\n    a\n}";
+        let text = Rope::from_str(code);
+        let mut highlighter = SyntaxHighlighter::new("rust");
+        highlighter.update(None, &text);
+
+        let captures = highlighter.captures(&(0..code.len()));
+        let name_at = |offset: usize| {
+            captures
+                .iter()
+                .find(|(range, _)| range.start == offset)
+                .map(|(_, name)| name.to_string())
+        };
+
+        // The definition of the parameter
+        assert_eq!(name_at(7).as_deref(), Some("variable.parameter"));
+        // `a` in `let b = a + 1` refers to the parameter
+        let ix = code.find("a + 1").unwrap();
+        assert_eq!(name_at(ix).as_deref(), Some("variable.parameter"));
+        // `a` in `let a = a * b` refers to the parameter, not the new `a`
+        let ix = code.find("a * b").unwrap();
+        assert_eq!(name_at(ix).as_deref(), Some("variable.parameter"));
+        // The last `a` refers to the `let a`, which has no highlight
+        let ix = code.rfind('a').unwrap();
+        assert_eq!(name_at(ix), None);
+
+        // The cached locals are used for the ranges in the same revision.
+        assert_eq!(highlighter.captures(&(0..code.len())), captures);
+        let revision = highlighter.locals_cache.borrow().as_ref().unwrap().revision;
+        assert_eq!(revision, highlighter.revision);
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_locals_go() {
+        let code = "func foo(a int) int {\n\tb := a + 1\n\treturn b\n}";
+        let text = Rope::from_str(code);
+        let mut highlighter = SyntaxHighlighter::new("go");
+        highlighter.update(None, &text);
+
+        let captures = highlighter.captures(&(0..code.len()));
+        let name_at = |offset: usize| {
+            captures
+                .iter()
+                .find(|(range, _)| range.start == offset)
+                .map(|(_, name)| name.to_string())
+        };
+
+        assert_eq!(name_at(9).as_deref(), Some("variable.parameter"));
+        let ix = code.find("a + 1").unwrap();
+        assert_eq!(name_at(ix).as_deref(), Some("variable.parameter"));
+        let ix = code.rfind('b').unwrap();
+        assert_eq!(name_at(ix).as_deref(), Some("variable"));
+    }
 }
@@ -223,13 +223,13 @@ impl Language {
                 tree_sitter_rust::LANGUAGE,
                 include_str!("languages/rust/highlights.scm"),
                 include_str!("languages/rust/injections.scm"),
-                "",
+                include_str!("languages/rust/locals.scm"),
             ),
             Self::Go => (
                 tree_sitter_go::LANGUAGE,
                 include_str!("languages/go/highlights.scm"),
                 "",
-                "",
+                include_str!("languages/go/locals.scm"),
             ),
             Self::C => (
                 tree_sitter_c::LANGUAGE,
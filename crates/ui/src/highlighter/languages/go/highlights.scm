@@ -27,6 +27,11 @@
   (literal_element
     (identifier) @variable.member))
 
+(parameter_declaration
+  name: (identifier) @variable.parameter)
+(variadic_parameter_declaration
+  name: (identifier) @variable.parameter)
+
 (type_identifier) @type
 (field_identifier) @variable.member
 (identifier) @variable
@@ -0,0 +1,28 @@
+; Scopes
+
+[
+  (block)
+  (function_item)
+  (closure_expression)
+  (for_expression)
+  (match_arm)
+] @local.scope
+
+; Definitions
+
+(parameter
+  pattern: (identifier) @local.definition)
+
+(closure_parameters
+  (identifier) @local.definition)
+
+(let_declaration
+  pattern: (identifier) @local.definition
+  value: (_)? @local.definition-value)
+
+(for_expression
+  pattern: (identifier) @local.definition)
+
+; References
+
+(identifier) @local.reference
@@ -0,0 +1,311 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result, anyhow};
+use gpui::SharedString;
+use tree_sitter::{Language, Parser, Query};
+
+use crate::highlighter::{LanguageConfig, LanguageRegistry};
+
+/// The query files to load from the language directory, `highlights.scm` is required.
+const HIGHLIGHTS_FILE: &str = "highlights.scm";
+const INJECTIONS_FILE: &str = "injections.scm";
+const LOCALS_FILE: &str = "locals.scm";
+
+/// The kind of the grammar file found in the language directory.
+enum GrammarFile {
+    /// A shared library (`.so`, `.dylib` or `.dll`) exports the `tree_sitter_{name}` function.
+    Library(PathBuf),
+    /// A WASM grammar built by `tree-sitter build --wasm`.
+    Wasm(PathBuf),
+}
+
+impl LanguageRegistry {
+    /// Load the languages from the subdirectories of `dir` and register them,
+    /// see [`LanguageRegistry::load_language`] for the layout of each subdirectory.
+    ///
+    /// Returns the load result of each language (named by the subdirectory), so one broken
+    /// language does not prevent the others from loading.
+    ///
+    /// Returns error if the `dir` can't be read.
+    pub fn load_languages(
+        &self,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<(SharedString, Result<LanguageConfig>)>> {
+        let dir = dir.as_ref();
+        let mut results = vec![];
+        for entry in std::fs::read_dir(dir).with_context(|| format!("read dir {:?}", dir))? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            let name = SharedString::from(name.to_string());
+            let result = self.load_language(&name, &path);
+            if let Err(err) = &result {
+                tracing::warn!("failed to load language {:?}: {:?}", name, err);
+            }
+            results.push((name, result));
+        }
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(results)
+    }
+
+    /// Load a language from the `dir` and register it with the `name`.
+    ///
+    /// The `dir` contains:
+    ///
+    /// - A grammar, either a shared library (`.so`, `.dylib` or `.dll`, requires the
+    ///   `tree-sitter-dynamic` feature) exporting the `tree_sitter_{name}` function,
+    ///   or a `.wasm` file (requires the `tree-sitter-wasm` feature).
+    /// - `highlights.scm`, and the optional `injections.scm` and `locals.scm`.
+    ///
+    /// The `-` in the `name` is replaced with `_` for the function name, e.g. `tree_sitter_c_sharp`.
+    pub fn load_language(&self, name: &str, dir: impl AsRef<Path>) -> Result<LanguageConfig> {
+        let dir = dir.as_ref();
+        let language = match find_grammar_file(dir)? {
+            GrammarFile::Library(path) => load_library(name, &path)?,
+            GrammarFile::Wasm(path) => load_wasm(name, &path)?,
+        };
+
+        let highlights = read_query(&language, dir, HIGHLIGHTS_FILE, true)?;
+        let injections = read_query(&language, dir, INJECTIONS_FILE, false)?;
+        let locals = read_query(&language, dir, LOCALS_FILE, false)?;
+
+        let injection_languages = self.injection_languages(&language, &injections)?;
+
+        let config = LanguageConfig::new(
+            name,
+            language,
+            injection_languages,
+            &highlights,
+            &injections,
+            &locals,
+        );
+        self.register(name, &config);
+        Ok(config)
+    }
+
+    /// Returns the languages may be injected by the `injections` query, the highlights of them
+    /// are loaded when the highlighter is created.
+    ///
+    /// The languages set by `#set! injection.language` are collected, and if the language is
+    /// captured by `@injection.language` from the text (e.g. the info string of a code block),
+    /// all the languages registered now are included.
+    fn injection_languages(
+        &self,
+        language: &Language,
+        injections: &str,
+    ) -> Result<Vec<SharedString>> {
+        if injections.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let query = Query::new(language, injections).context("invalid injections query")?;
+        let mut languages = (0..query.pattern_count())
+            .flat_map(|ix| query.property_settings(ix))
+            .filter(|setting| setting.key.as_ref() == "injection.language")
+            .filter_map(|setting| setting.value.as_deref())
+            .map(|name| SharedString::from(name.to_string()))
+            .collect::<Vec<_>>();
+        if query
+            .capture_names()
+            .iter()
+            .any(|name| *name == "injection.language")
+        {
+            languages.extend(self.languages());
+        }
+        languages.sort();
+        languages.dedup();
+
+        Ok(languages)
+    }
+}
+
+/// Create a parser for the `language`, the WASM languages are supported with a WASM store.
+pub(super) fn new_parser(language: &Language) -> Result<Parser> {
+    let mut parser = Parser::new();
+
+    #[cfg(feature = "tree-sitter-wasm")]
+    if language.is_wasm() {
+        parser.set_wasm_store(wasm::new_store()?)?;
+    }
+
+    parser
+        .set_language(language)
+        .context("parse set_language")?;
+    Ok(parser)
+}
+
+fn find_grammar_file(dir: &Path) -> Result<GrammarFile> {
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("read dir {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext == std::env::consts::DLL_EXTENSION => {
+                return Ok(GrammarFile::Library(path));
+            }
+            Some("wasm") => return Ok(GrammarFile::Wasm(path)),
+            _ => {}
+        }
+    }
+
+    Err(anyhow!(
+        "no grammar (.{} or .wasm) found in {:?}",
+        std::env::consts::DLL_EXTENSION,
+        dir
+    ))
+}
+
+/// Read the query `file` in the `dir`, and check it is valid for the `language`.
+fn read_query(language: &Language, dir: &Path, file: &str, required: bool) -> Result<String> {
+    let path = dir.join(file);
+    if !required && !path.exists() {
+        return Ok(String::new());
+    }
+
+    let source = std::fs::read_to_string(&path).with_context(|| format!("read {:?}", path))?;
+    Query::new(language, &source).with_context(|| format!("invalid query {:?}", path))?;
+    Ok(source)
+}
+
+#[cfg_attr(not(feature = "tree-sitter-dynamic"), allow(dead_code))]
+fn symbol_name(name: &str) -> String {
+    format!("tree_sitter_{}", name.replace('-', "_"))
+}
+
+#[cfg(feature = "tree-sitter-dynamic")]
+fn load_library(name: &str, path: &Path) -> Result<Language> {
+    use std::sync::Mutex;
+
+    /// The loaded libraries must be alive as long as the languages are used.
+    static LIBRARIES: Mutex<Vec<libloading::Library>> = Mutex::new(Vec::new());
+
+    let symbol = symbol_name(name);
+    // SAFETY: The library is a tree-sitter grammar, the function with the `symbol` name
+    // returns a pointer to a static `TSLanguage`, and the library is never unloaded.
+    unsafe {
+        let library = libloading::Library::new(path).with_context(|| format!("load {:?}", path))?;
+        let language = {
+            let language_fn = library
+                .get::<unsafe extern "C" fn() -> *const tree_sitter::ffi::TSLanguage>(
+                    symbol.as_bytes(),
+                )
+                .with_context(|| format!("find symbol `{}` in {:?}", symbol, path))?;
+            Language::from_raw(language_fn())
+        };
+        LIBRARIES.lock().unwrap().push(library);
+        Ok(language)
+    }
+}
+
+#[cfg(not(feature = "tree-sitter-dynamic"))]
+fn load_library(_: &str, path: &Path) -> Result<Language> {
+    Err(anyhow!(
+        "can't load {:?}, the `tree-sitter-dynamic` feature is not enabled",
+        path
+    ))
+}
+
+#[cfg(feature = "tree-sitter-wasm")]
+mod wasm {
+    use std::sync::LazyLock;
+
+    use anyhow::Result;
+    use tree_sitter::{WasmStore, wasmtime::Engine};
+
+    /// All the WASM stores must be created from the same engine to share the languages.
+    static ENGINE: LazyLock<Engine> = LazyLock::new(Engine::default);
+
+    pub(super) fn new_store() -> Result<WasmStore> {
+        Ok(WasmStore::new(&ENGINE)?)
+    }
+}
+
+#[cfg(feature = "tree-sitter-wasm")]
+fn load_wasm(name: &str, path: &Path) -> Result<Language> {
+    let bytes = std::fs::read(path).with_context(|| format!("read {:?}", path))?;
+    let mut store = wasm::new_store()?;
+    Ok(store.load_language(name, &bytes)?)
+}
+
+#[cfg(not(feature = "tree-sitter-wasm"))]
+fn load_wasm(_: &str, path: &Path) -> Result<Language> {
+    Err(anyhow!(
+        "can't load {:?}, the `tree-sitter-wasm` feature is not enabled",
+        path
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::symbol_name;
+    use crate::highlighter::LanguageRegistry;
+
+    #[test]
+    fn test_symbol_name() {
+        assert_eq!(symbol_name("rust"), "tree_sitter_rust");
+        assert_eq!(symbol_name("c-sharp"), "tree_sitter_c_sharp");
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_injection_languages() {
+        let registry = LanguageRegistry::singleton();
+        let language = registry.language("rust").unwrap().language;
+
+        let languages = registry
+            .injection_languages(
+                &language,
+                r#"((line_comment) @injection.content (#set! injection.language "markdown"))
+                ((block_comment) @injection.content (#set! injection.language "markdown"))"#,
+            )
+            .unwrap();
+        assert_eq!(languages, vec!["markdown"]);
+
+        // The language captured from the text may be any registered language.
+        let languages = registry
+            .injection_languages(
+                &language,
+                "(macro_invocation (identifier) @injection.language (token_tree) @injection.content)",
+            )
+            .unwrap();
+        assert!(languages.iter().any(|name| name == "rust"));
+        assert!(languages.iter().any(|name| name == "json"));
+
+        assert!(
+            registry
+                .injection_languages(&language, "")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_load_languages_errors() {
+        let dir =
+            std::env::temp_dir().join(format!("gpui-component-languages-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("no-grammar")).unwrap();
+        std::fs::write(dir.join("no-grammar").join("highlights.scm"), "").unwrap();
+        std::fs::create_dir_all(dir.join("broken")).unwrap();
+        std::fs::write(dir.join("broken").join("broken.wasm"), "not wasm").unwrap();
+
+        let registry = LanguageRegistry::singleton();
+        let results = registry.load_languages(&dir).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "broken");
+        assert!(results[0].1.is_err());
+        assert_eq!(results[1].0, "no-grammar");
+        assert!(results[1].1.is_err());
+
+        assert!(registry.load_languages(dir.join("not-exists")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
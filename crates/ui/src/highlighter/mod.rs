@@ -1,9 +1,12 @@
 mod diagnostics;
+mod export;
 mod highlighter;
 mod languages;
+mod loader;
 mod registry;
 
 pub use diagnostics::*;
+pub(crate) use export::escape_html;
 pub use highlighter::*;
 pub use languages::*;
 pub use registry::*;
@@ -124,6 +124,16 @@ where
         &self.redos
     }
 
+    /// Get the mutable undo stack.
+    pub(crate) fn undos_mut(&mut self) -> &mut Vec<I> {
+        &mut self.undos
+    }
+
+    /// Get the mutable redo stack.
+    pub(crate) fn redos_mut(&mut self) -> &mut Vec<I> {
+        &mut self.redos
+    }
+
     /// Clear the undo and redo stacks.
     pub fn clear(&mut self) {
         self.undos.clear();
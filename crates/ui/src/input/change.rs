@@ -1,7 +1,8 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, ops::Range};
 
 use crate::{history::HistoryItem, input::Selection};
 
+/// A text edit of the [`super::InputState`], the ranges are byte offsets.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Change {
     pub(crate) old_range: Selection,
@@ -26,6 +27,26 @@ impl Change {
             version: 0,
         }
     }
+
+    /// The replaced range in the text before the change.
+    pub fn old_range(&self) -> Range<usize> {
+        self.old_range.into()
+    }
+
+    /// The replaced text.
+    pub fn old_text(&self) -> &str {
+        &self.old_text
+    }
+
+    /// The range of the inserted text in the text after the change.
+    pub fn new_range(&self) -> Range<usize> {
+        self.new_range.into()
+    }
+
+    /// The inserted text.
+    pub fn new_text(&self) -> &str {
+        &self.new_text
+    }
 }
 
 impl HistoryItem for Change {
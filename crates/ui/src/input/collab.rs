@@ -0,0 +1,424 @@
+use std::ops::Range;
+
+use gpui::{Context, Hsla, SharedString, Window};
+use ropey::Rope;
+use sum_tree::Bias;
+
+use crate::{
+    history::History,
+    input::{Change, InputEvent, InputState, RopeExt as _, Selection},
+};
+
+/// Emitted by [`InputState`] for each edit made locally, e.g. typing, paste, undo.
+///
+/// The edits applied by [`InputState::apply_remote_changes`] are not emitted,
+/// so this can be sent to the OT/CRDT transport of the collaborative editing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalEdit {
+    /// The change, the `old_range` is the byte range in the text before this edit.
+    pub change: Change,
+}
+
+/// The cursor and selection of a remote collaborator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteCursor {
+    /// The unique id of the collaborator.
+    pub id: SharedString,
+    /// The name of the collaborator, displayed in a label above the cursor.
+    pub name: SharedString,
+    /// The color of the cursor and selection.
+    pub color: Hsla,
+    /// The byte offset where the selection started, same as `head` if no selection.
+    pub anchor: usize,
+    /// The byte offset of the cursor.
+    pub head: usize,
+}
+
+impl RemoteCursor {
+    /// Create a remote cursor at the start of the text.
+    pub fn new(id: impl Into<SharedString>, name: impl Into<SharedString>, color: Hsla) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            color,
+            anchor: 0,
+            head: 0,
+        }
+    }
+
+    /// Set the selection from `anchor` to `head` (the cursor).
+    pub fn selection(mut self, anchor: usize, head: usize) -> Self {
+        self.anchor = anchor;
+        self.head = head;
+        self
+    }
+
+    /// Returns the selected range, ordered.
+    pub fn selected_range(&self) -> Range<usize> {
+        self.anchor.min(self.head)..self.anchor.max(self.head)
+    }
+}
+
+/// Map the `offset` in the text before the `range` was replaced by `new_len` bytes,
+/// to the offset in the text after.
+///
+/// The offsets inside the replaced range are moved to the end of the new text.
+fn rebase_offset(offset: usize, range: &Range<usize>, new_len: usize) -> usize {
+    if offset <= range.start {
+        offset
+    } else if offset >= range.end {
+        offset - range.len() + new_len
+    } else {
+        range.start + new_len
+    }
+}
+
+fn rebase_selection(selection: Selection, range: &Range<usize>, new_len: usize) -> Selection {
+    Selection::new(
+        rebase_offset(selection.start, range, new_len),
+        rebase_offset(selection.end, range, new_len),
+    )
+}
+
+/// Transform the remote edit, which replaced the `range` by `new_len` bytes, through a history item.
+///
+/// The `current` is the range of the item in the text the edit applied to,
+/// the `other` is the range on the other side of the item, e.g. the `old_range` for undo.
+///
+/// Returns the edit range in the coordinates of the `other` side,
+/// or `None` if the edit overlaps the `current` range.
+fn transform_edit(
+    range: &Range<usize>,
+    new_len: usize,
+    current: &mut Selection,
+    other: &mut Selection,
+) -> Option<Range<usize>> {
+    if range.end <= current.start {
+        // Before the item, shift the item.
+        for offset in [
+            &mut current.start,
+            &mut current.end,
+            &mut other.start,
+            &mut other.end,
+        ] {
+            *offset = *offset - range.len() + new_len;
+        }
+        Some(range.clone())
+    } else if range.start >= current.end {
+        // After the item, shift the edit.
+        let shift = |offset: usize| offset - current.len() + other.len();
+        Some(shift(range.start)..shift(range.end))
+    } else {
+        None
+    }
+}
+
+/// Rebase the undo and redo history onto the remote edit, which replaced the `range` by `new_len` bytes.
+///
+/// Each item is in the coordinates of the text when it's applied, so the edit is transformed
+/// through the items in the order of undo (or redo).
+/// The item overlapped with the edit and the items after it are dropped,
+/// because they can't be applied without reverting the remote edit.
+fn rebase_history(history: &mut History<Change>, range: &Range<usize>, new_len: usize) {
+    // Undo replaces the `new_range` by the `old_text`, from the last item.
+    let undos = history.undos_mut();
+    let mut edit = range.clone();
+    for ix in (0..undos.len()).rev() {
+        let item = &mut undos[ix];
+        match transform_edit(&edit, new_len, &mut item.new_range, &mut item.old_range) {
+            Some(range) => edit = range,
+            None => {
+                undos.drain(..=ix);
+                break;
+            }
+        }
+    }
+
+    // Redo replaces the `old_range` by the `new_text`, from the last item.
+    let redos = history.redos_mut();
+    let mut edit = range.clone();
+    for ix in (0..redos.len()).rev() {
+        let item = &mut redos[ix];
+        match transform_edit(&edit, new_len, &mut item.old_range, &mut item.new_range) {
+            Some(range) => edit = range,
+            None => {
+                redos.drain(..=ix);
+                break;
+            }
+        }
+    }
+}
+
+impl InputState {
+    /// Apply the changes received from the remote collaborators, in order.
+    ///
+    /// Each [`Change::old_range`] is the byte range in the text with the previous changes applied,
+    /// only the `old_range` and `new_text` are used.
+    ///
+    /// Unlike the local edits, the remote changes:
+    ///
+    /// - Are not pushed to the undo history, the history is rebased onto them instead,
+    ///   the local changes overlapped with a remote change can't be undone anymore.
+    /// - Do not emit [`LocalEdit`].
+    /// - Rebase the local selection and the [`RemoteCursor`]s to keep them on the same text.
+    pub fn apply_remote_changes(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut changed = false;
+        for change in changes {
+            let len = self.text.len();
+            let start = self.text.clip_offset(change.old_range.start.min(len), Bias::Left);
+            let end = self
+                .text
+                .clip_offset(change.old_range.end.min(len), Bias::Right)
+                .max(start);
+            let range = start..end;
+            let new_text = change.new_text.as_str();
+            if range.is_empty() && new_text.is_empty() {
+                continue;
+            }
+
+            let new_len = new_text.len();
+            self.text.replace(range.clone(), new_text);
+
+            self.selected_range = rebase_selection(self.selected_range, &range, new_len);
+            self.selected_word_range = self
+                .selected_word_range
+                .map(|selection| rebase_selection(selection, &range, new_len));
+            self.ime_marked_range = self
+                .ime_marked_range
+                .map(|selection| rebase_selection(selection, &range, new_len));
+            for cursor in self.remote_cursors.iter_mut() {
+                cursor.anchor = rebase_offset(cursor.anchor, &range, new_len);
+                cursor.head = rebase_offset(cursor.head, &range, new_len);
+            }
+            rebase_history(&mut self.history, &range, new_len);
+
+            self.update_text_wrapper(&range, new_text, cx);
+            self.update_highlighter(&range, new_text, true, cx);
+            changed = true;
+        }
+
+        if !changed {
+            return;
+        }
+
+        if let Some(diagnostics) = self.mode.diagnostics_mut() {
+            diagnostics.reset(&self.text)
+        }
+        self.lsp.update(&self.text, window, cx);
+        self.update_preferred_column();
+        self.update_search(cx);
+        self.mode.update_auto_grow(&self.text_wrapper);
+        cx.emit(InputEvent::Change);
+        cx.notify();
+    }
+
+    /// Returns the selected byte range, ordered.
+    ///
+    /// Use with [`InputState::cursor`] to send the local selection to the collaborators.
+    pub fn selected_range(&self) -> Range<usize> {
+        self.selected_range.into()
+    }
+
+    /// Add or update (by [`RemoteCursor::id`]) the cursor of a remote collaborator.
+    pub fn set_remote_cursor(&mut self, cursor: RemoteCursor, cx: &mut Context<Self>) {
+        let len = self.text.len();
+        let mut cursor = cursor;
+        cursor.anchor = self.text.clip_offset(cursor.anchor.min(len), Bias::Left);
+        cursor.head = self.text.clip_offset(cursor.head.min(len), Bias::Left);
+
+        if let Some(existing) = self.remote_cursors.iter_mut().find(|c| c.id == cursor.id) {
+            *existing = cursor;
+        } else {
+            self.remote_cursors.push(cursor);
+        }
+        cx.notify();
+    }
+
+    /// Remove the cursor of the remote collaborator with the `id`.
+    pub fn remove_remote_cursor(&mut self, id: &str, cx: &mut Context<Self>) {
+        self.remote_cursors.retain(|cursor| cursor.id.as_ref() != id);
+        cx.notify();
+    }
+
+    /// Returns the cursors of the remote collaborators.
+    pub fn remote_cursors(&self) -> &[RemoteCursor] {
+        &self.remote_cursors
+    }
+
+    /// Called after the `range` in the `old_text` replaced by the `new_text` locally.
+    ///
+    /// Rebase the remote cursors and emit the [`LocalEdit`].
+    pub(super) fn did_local_edit(
+        &mut self,
+        old_text: &Rope,
+        range: &Range<usize>,
+        new_text: &str,
+        cx: &mut Context<Self>,
+    ) {
+        let old = old_text.slice(range.clone()).to_string();
+        if old.is_empty() && new_text.is_empty() {
+            return;
+        }
+
+        for cursor in self.remote_cursors.iter_mut() {
+            cursor.anchor = rebase_offset(cursor.anchor, range, new_text.len());
+            cursor.head = rebase_offset(cursor.head, range, new_text.len());
+        }
+
+        let new_range = range.start..range.start + new_text.len();
+        cx.emit(LocalEdit {
+            change: Change::new(range.clone(), &old, new_range, new_text),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use gpui::{AppContext as _, EntityInputHandler as _, Hsla};
+
+    use super::{LocalEdit, RemoteCursor, rebase_offset};
+    use crate::input::{Change, InputState, Redo, Undo};
+
+    #[test]
+    fn test_rebase_offset() {
+        // Insert 3 bytes at 5
+        assert_eq!(rebase_offset(2, &(5..5), 3), 2);
+        assert_eq!(rebase_offset(5, &(5..5), 3), 5);
+        assert_eq!(rebase_offset(6, &(5..5), 3), 9);
+        // Delete 5..8
+        assert_eq!(rebase_offset(5, &(5..8), 0), 5);
+        assert_eq!(rebase_offset(6, &(5..8), 0), 5);
+        assert_eq!(rebase_offset(8, &(5..8), 0), 5);
+        assert_eq!(rebase_offset(10, &(5..8), 0), 7);
+        // Replace 5..8 with 1 byte
+        assert_eq!(rebase_offset(7, &(5..8), 1), 6);
+        assert_eq!(rebase_offset(9, &(5..8), 1), 7);
+    }
+
+    #[gpui::test]
+    fn test_remote_changes(cx: &mut gpui::TestAppContext) {
+        cx.update(|cx| crate::init(cx));
+        let cx = cx.add_empty_window();
+
+        let a = cx.new_window_entity(|window, cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .default_value("Hello world")
+        });
+        let b = cx.new_window_entity(|window, cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .default_value("Hello world")
+        });
+
+        let edits_a: Rc<RefCell<Vec<Change>>> = Rc::default();
+        let edits_b: Rc<RefCell<Vec<Change>>> = Rc::default();
+        cx.update(|_, cx| {
+            let edits = edits_a.clone();
+            cx.subscribe(&a, move |_, event: &LocalEdit, _| {
+                edits.borrow_mut().push(event.change.clone());
+            })
+            .detach();
+            let edits = edits_b.clone();
+            cx.subscribe(&b, move |_, event: &LocalEdit, _| {
+                edits.borrow_mut().push(event.change.clone());
+            })
+            .detach();
+        });
+
+        // Put the cursor of `b` after "world".
+        b.update(cx, |state, _| state.selected_range = (11..11).into());
+
+        a.update_in(cx, |state, window, cx| {
+            state.replace_text_in_range(Some(6..6), "big ", window, cx);
+        });
+        assert_eq!(edits_a.borrow().len(), 1);
+        assert_eq!(edits_a.borrow()[0].old_range(), 6..6);
+        assert_eq!(edits_a.borrow()[0].new_text(), "big ");
+
+        let changes = edits_a.borrow_mut().drain(..).collect::<Vec<_>>();
+        b.update_in(cx, |state, window, cx| {
+            state.apply_remote_changes(changes, window, cx);
+            state.set_remote_cursor(
+                RemoteCursor::new("a", "Alice", Hsla::default()).selection(6, 10),
+                cx,
+            );
+        });
+
+        b.update(cx, |state, _| {
+            assert_eq!(state.value(), "Hello big world");
+            assert_eq!(state.selected_range(), 15..15);
+            assert!(state.history.undos().is_empty());
+            assert_eq!(state.remote_cursors()[0].selected_range(), 6..10);
+        });
+        assert!(edits_b.borrow().is_empty());
+
+        // A local edit of `b` rebases the remote cursor and is emitted.
+        b.update_in(cx, |state, window, cx| {
+            state.replace_text_in_range(Some(0..0), ">> ", window, cx);
+        });
+        assert_eq!(edits_b.borrow().len(), 1);
+        let changes = edits_b.borrow_mut().drain(..).collect::<Vec<_>>();
+        a.update_in(cx, |state, window, cx| {
+            state.apply_remote_changes(changes, window, cx);
+        });
+
+        a.update(cx, |state, _| assert_eq!(state.value(), ">> Hello big world"));
+        b.update(cx, |state, _| {
+            assert_eq!(state.value(), ">> Hello big world");
+            assert_eq!(state.remote_cursors()[0].selected_range(), 9..13);
+        });
+    }
+
+    #[gpui::test]
+    fn test_undo_after_remote_changes(cx: &mut gpui::TestAppContext) {
+        cx.update(|cx| crate::init(cx));
+        let cx = cx.add_empty_window();
+
+        let state = cx.new_window_entity(|window, cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .default_value("abcdef")
+        });
+
+        state.update_in(cx, |state, window, cx| {
+            state.replace_text_in_range(Some(4..4), "Z", window, cx);
+            state.replace_text_in_range(Some(0..3), "", window, cx);
+            assert_eq!(state.value(), "dZef");
+
+            state.apply_remote_changes([Change::new(2..2, "", 2..3, "R")], window, cx);
+            assert_eq!(state.value(), "dZRef");
+
+            while !state.history.undos().is_empty() {
+                state.undo(&Undo, window, cx);
+            }
+            assert_eq!(state.value(), "abcdRef");
+
+            while !state.history.redos().is_empty() {
+                state.redo(&Redo, window, cx);
+            }
+            assert_eq!(state.value(), "dZRef");
+        });
+
+        // The local change overlapped with the remote change is dropped from the history.
+        state.update_in(cx, |state, window, cx| {
+            state.history.clear();
+            state.replace_text_in_range(Some(1..1), "X", window, cx);
+            assert_eq!(state.value(), "dXZRef");
+
+            state.apply_remote_changes([Change::new(0..3, "dXZ", 0..0, "")], window, cx);
+            assert_eq!(state.value(), "Ref");
+            assert!(state.history.undos().is_empty());
+
+            state.undo(&Undo, window, cx);
+            assert_eq!(state.value(), "Ref");
+        });
+    }
+}
@@ -0,0 +1,279 @@
+use std::ops::Range;
+
+use gpui::{
+    App, Context, Entity, InteractiveElement as _, IntoElement, ListSizingBehavior, MouseButton,
+    ParentElement as _, Render, SharedString, Styled as _, Subscription, UniformListScrollHandle,
+    Window, div, prelude::FluentBuilder as _, uniform_list,
+};
+use rust_i18n::t;
+
+use crate::{
+    ActiveTheme as _, Icon, Sizable as _, h_flex,
+    highlighter::{Diagnostic, DiagnosticEntry, DiagnosticSeverity},
+    input::{InputState, NextDiagnostic, PreviousDiagnostic, popovers::DiagnosticPopover},
+    list::ListItem,
+    v_flex,
+};
+
+impl InputState {
+    pub(super) fn next_diagnostic(
+        &mut self,
+        action: &NextDiagnostic,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let offset = self.cursor();
+        let Some(entry) = self
+            .diagnostics()
+            .and_then(|set| set.next_entry(offset, action.severity))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.show_diagnostic_entry(&entry, cx);
+    }
+
+    pub(super) fn previous_diagnostic(
+        &mut self,
+        action: &PreviousDiagnostic,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let offset = self.cursor();
+        let Some(entry) = self
+            .diagnostics()
+            .and_then(|set| set.previous_entry(offset, action.severity))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.show_diagnostic_entry(&entry, cx);
+    }
+
+    /// Move the cursor to the start of the `diagnostic` and show its popover.
+    ///
+    /// The `diagnostic` must be one of the [`InputState::diagnostics`],
+    /// otherwise this does nothing.
+    pub fn go_to_diagnostic(
+        &mut self,
+        diagnostic: &Diagnostic,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(entry) = self
+            .diagnostics()
+            .and_then(|set| set.iter().find(|entry| entry.diagnostic == *diagnostic))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.show_diagnostic_entry(&entry, cx);
+        self.focus(window, cx);
+    }
+
+    fn show_diagnostic_entry(&mut self, entry: &DiagnosticEntry, cx: &mut Context<Self>) {
+        self.move_to(entry.range.start, None, cx);
+        self.diagnostic_popover = Some(DiagnosticPopover::new(entry, cx.entity(), cx));
+        cx.notify();
+    }
+}
+
+/// A list of the [`Diagnostic`]s of an [`InputState`].
+///
+/// Click on an entry to move the editor cursor to that diagnostic.
+///
+/// ```ignore
+/// let panel = cx.new(|cx| DiagnosticsPanel::new(&editor, cx));
+/// ```
+pub struct DiagnosticsPanel {
+    state: Entity<InputState>,
+    severity: Option<DiagnosticSeverity>,
+    items: Vec<Diagnostic>,
+    /// The `DiagnosticSet::version` of the listed items.
+    diagnostics_version: Option<usize>,
+    selected_ix: Option<usize>,
+    scroll_handle: UniformListScrollHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DiagnosticsPanel {
+    /// Create a panel to list the diagnostics of the `state`.
+    pub fn new(state: &Entity<InputState>, cx: &mut Context<Self>) -> Self {
+        // The state is notified on every cursor blink, only refresh when the diagnostics are changed.
+        let _subscriptions = vec![cx.observe(state, |this, state, cx| {
+            let version = state.read(cx).diagnostics().map(|set| set.version());
+            if version != this.diagnostics_version {
+                this.refresh(cx);
+            }
+        })];
+
+        let mut this = Self {
+            state: state.clone(),
+            severity: None,
+            items: vec![],
+            diagnostics_version: None,
+            selected_ix: None,
+            scroll_handle: UniformListScrollHandle::default(),
+            _subscriptions,
+        };
+        this.refresh(cx);
+        this
+    }
+
+    /// Set to only list diagnostics with the `severity`, None for all.
+    pub fn severity(mut self, severity: Option<DiagnosticSeverity>) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Set to only list diagnostics with the `severity`, None for all.
+    pub fn set_severity(&mut self, severity: Option<DiagnosticSeverity>, cx: &mut Context<Self>) {
+        self.severity = severity;
+        self.refresh(cx);
+    }
+
+    /// Returns the listed diagnostics, ordered by position.
+    pub fn items(&self) -> &[Diagnostic] {
+        &self.items
+    }
+
+    /// Returns the selected diagnostic, if any.
+    pub fn selected_item(&self) -> Option<&Diagnostic> {
+        self.selected_ix.and_then(|ix| self.items.get(ix))
+    }
+
+    fn refresh(&mut self, cx: &mut Context<Self>) {
+        let severity = self.severity;
+        let state = self.state.read(cx);
+        self.diagnostics_version = state.diagnostics().map(|set| set.version());
+        let mut items: Vec<Diagnostic> = state
+            .diagnostics()
+            .map(|set| {
+                set.diagnostics()
+                    .filter(|diagnostic| severity.map_or(true, |s| diagnostic.severity == s))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        items.sort_by_key(|diagnostic| diagnostic.range.start);
+
+        if items != self.items {
+            self.selected_ix = None;
+            self.items = items;
+        }
+        cx.notify();
+    }
+
+    fn confirm(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diagnostic) = self.items.get(ix).cloned() else {
+            return;
+        };
+
+        self.selected_ix = Some(ix);
+        self.state.update(cx, |state, cx| {
+            state.go_to_diagnostic(&diagnostic, window, cx);
+        });
+        cx.notify();
+    }
+
+    fn render_item(
+        ix: usize,
+        diagnostic: &Diagnostic,
+        selected: bool,
+        cx: &App,
+    ) -> impl IntoElement {
+        let position: SharedString = format!(
+            "[Ln {}, Col {}]",
+            diagnostic.range.start.line + 1,
+            diagnostic.range.start.character + 1
+        )
+        .into();
+        let source = match (&diagnostic.source, &diagnostic.code) {
+            (Some(source), Some(code)) => Some(format!("{}({})", source, code)),
+            (Some(source), None) => Some(source.to_string()),
+            (None, Some(code)) => Some(code.to_string()),
+            (None, None) => None,
+        };
+
+        ListItem::new(ix).selected(selected).child(
+            h_flex()
+                .gap_2()
+                .text_sm()
+                .child(
+                    Icon::new(diagnostic.severity.icon())
+                        .small()
+                        .text_color(diagnostic.severity.fg(cx)),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .overflow_x_hidden()
+                        .text_ellipsis()
+                        .whitespace_nowrap()
+                        .child(diagnostic.message.clone()),
+                )
+                .when_some(source, |this, source| {
+                    this.child(div().text_color(cx.theme().muted_foreground).child(source))
+                })
+                .child(
+                    div()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(position),
+                ),
+        )
+    }
+}
+
+impl Render for DiagnosticsPanel {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.items.is_empty() {
+            return v_flex()
+                .id("diagnostics-panel")
+                .size_full()
+                .p_2()
+                .text_sm()
+                .text_color(cx.theme().muted_foreground)
+                .child(t!("Input.No Problems").to_string())
+                .into_any_element();
+        }
+
+        div()
+            .id("diagnostics-panel")
+            .size_full()
+            .relative()
+            .child(
+                uniform_list(
+                    "diagnostics",
+                    self.items.len(),
+                    cx.processor(move |this, visible_range: Range<usize>, _, cx| {
+                        let mut items = Vec::with_capacity(visible_range.len());
+                        for ix in visible_range {
+                            let diagnostic = &this.items[ix];
+                            let selected = Some(ix) == this.selected_ix;
+
+                            items.push(
+                                div()
+                                    .id(ix)
+                                    .child(Self::render_item(ix, diagnostic, selected, cx))
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(move |this, _, window, cx| {
+                                            this.confirm(ix, window, cx);
+                                        }),
+                                    ),
+                            );
+                        }
+
+                        items
+                    }),
+                )
+                .size_full()
+                .track_scroll(&self.scroll_handle)
+                .with_sizing_behavior(ListSizingBehavior::Auto),
+            )
+            .into_any_element()
+    }
+}
@@ -17,6 +17,8 @@ use crate::{
 use super::{InputState, LastLayout, force_cursor_visible, mode::InputMode};
 
 const BOTTOM_MARGIN_ROWS: usize = 3;
+/// The rows above and below the viewport to wrap in the large file mode.
+const LAZY_WRAP_MARGIN_ROWS: usize = 50;
 pub(super) const RIGHT_MARGIN: Pixels = px(10.);
 pub(super) const LINE_NUMBER_RIGHT_MARGIN: Pixels = px(10.);
 
@@ -488,6 +490,91 @@ impl TextElement {
         Self::layout_match_range(range, &last_layout, bounds)
     }
 
+    /// Returns the position of the `offset` relative to the text origin,
+    /// None if not in the visible lines.
+    fn position_for_offset(offset: usize, last_layout: &LastLayout) -> Option<Point<Pixels>> {
+        if offset < last_layout.visible_range_offset.start
+            || offset > last_layout.visible_range_offset.end
+        {
+            return None;
+        }
+
+        let mut prev_lines_offset = last_layout.visible_range_offset.start;
+        let mut offset_y = last_layout.visible_top;
+        for line in last_layout.lines.iter() {
+            if let Some(pos) =
+                line.position_for_index(offset.saturating_sub(prev_lines_offset), last_layout)
+            {
+                return Some(point(px(0.), offset_y) + pos);
+            }
+
+            offset_y += line.size(last_layout.line_height).height;
+            // +1 for skip the last `\n`
+            prev_lines_offset += line.len() + 1;
+        }
+
+        None
+    }
+
+    fn layout_remote_cursors(
+        &self,
+        last_layout: &LastLayout,
+        bounds: &Bounds<Pixels>,
+        text_size: Pixels,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Vec<RemoteCursorLayout> {
+        let state = self.state.read(cx);
+        if state.masked || state.remote_cursors.is_empty() {
+            return vec![];
+        }
+
+        let visible_range_offset = &last_layout.visible_range_offset;
+        let origin = bounds.origin + point(last_layout.line_number_width, px(0.));
+        let font = window.text_style().font();
+        let label_size = text_size * 0.75;
+
+        let mut layouts = vec![];
+        for cursor in state.remote_cursors.iter() {
+            let range = cursor.selected_range();
+            let selection_path = Self::layout_match_range(
+                range.start.max(visible_range_offset.start)
+                    ..range.end.min(visible_range_offset.end),
+                last_layout,
+                bounds,
+            );
+            let cursor_bounds =
+                Self::position_for_offset(cursor.head, last_layout).map(|pos| {
+                    Bounds::new(origin + pos, size(CURSOR_WIDTH, last_layout.line_height))
+                });
+            if selection_path.is_none() && cursor_bounds.is_none() {
+                continue;
+            }
+
+            let runs = vec![TextRun {
+                len: cursor.name.len(),
+                font: font.clone(),
+                color: cx.theme().background,
+                background_color: None,
+                underline: None,
+                strikethrough: None,
+            }];
+            let label = window
+                .text_system()
+                .shape_line(cursor.name.clone(), label_size, &runs, None);
+
+            layouts.push(RemoteCursorLayout {
+                color: cursor.color,
+                selection_path,
+                cursor_bounds,
+                label,
+                label_height: label_size * 1.5,
+            });
+        }
+
+        layouts
+    }
+
     /// Calculate the visible range of lines in the viewport.
     ///
     /// Returns
@@ -758,6 +845,7 @@ impl TextElement {
         let state = self.state.read(cx);
         let text = state.text();
         let is_multi_line = state.mode.is_multi_line();
+        let spelling_styles = state.spelling_styles(&visible_byte_range, cx);
 
         let (highlighter, diagnostics) = match &state.mode {
             InputMode::CodeEditor {
@@ -765,7 +853,21 @@ impl TextElement {
                 diagnostics,
                 ..
             } => (highlighter.borrow(), diagnostics),
-            _ => return None,
+            _ => {
+                if spelling_styles.is_empty() {
+                    return None;
+                }
+
+                // Cover the entire visible text with default style, like the highlighter does.
+                let end = if is_multi_line {
+                    // +1 for `\n`
+                    visible_byte_range.end + 1
+                } else {
+                    visible_byte_range.end
+                };
+                let styles = vec![(visible_byte_range.start..end, HighlightStyle::default())];
+                return Some(gpui::combine_highlights(styles, spelling_styles).collect());
+            }
         };
         let highlighter = highlighter.as_ref()?;
 
@@ -800,11 +902,24 @@ impl TextElement {
 
         // Combine marker styles
         styles = gpui::combine_highlights(diagnostic_styles, styles).collect();
+        if !spelling_styles.is_empty() {
+            styles = gpui::combine_highlights(spelling_styles, styles).collect();
+        }
 
         Some(styles)
     }
 }
 
+/// The layout of a [`super::RemoteCursor`].
+struct RemoteCursorLayout {
+    color: Hsla,
+    selection_path: Option<Path<Pixels>>,
+    cursor_bounds: Option<Bounds<Pixels>>,
+    /// The name label painted above the cursor.
+    label: ShapedLine,
+    label_height: Pixels,
+}
+
 pub(super) struct PrepaintState {
     /// The lines of entire lines.
     last_layout: LastLayout,
@@ -822,6 +937,7 @@ pub(super) struct PrepaintState {
     hover_highlight_path: Option<Path<Pixels>>,
     search_match_paths: Vec<(Path<Pixels>, bool)>,
     document_color_paths: Vec<(Path<Pixels>, Hsla)>,
+    remote_cursors: Vec<RemoteCursorLayout>,
     hover_definition_hitbox: Option<Hitbox>,
     indent_guides_path: Option<Path<Pixels>>,
     bounds: Bounds<Pixels>,
@@ -939,6 +1055,8 @@ impl Element for TextElement {
         let text_size = style.font_size.to_pixels(window.rem_size());
 
         self.state.update(cx, |state, cx| {
+            let lazy = state.is_large_file();
+            state.text_wrapper.set_lazy(lazy, cx);
             state.text_wrapper.set_font(font, text_size, cx);
             let text = state.text().clone();
             state.text_wrapper.prepare_if_need(&text, cx);
@@ -947,8 +1065,25 @@ impl Element for TextElement {
         let state = self.state.read(cx);
         let line_height = window.line_height();
 
-        let (visible_range, visible_top) =
+        let (mut visible_range, mut visible_top) =
             self.calculate_visible_range(&state, line_height, bounds.size.height);
+
+        // In large file mode, only wrap the visible lines plus a margin, then recalculate.
+        if state.text_wrapper.is_lazy() {
+            let rows = visible_range.start.saturating_sub(LAZY_WRAP_MARGIN_ROWS)
+                ..visible_range.end + LAZY_WRAP_MARGIN_ROWS;
+            let wrapped = self
+                .state
+                .update(cx, |state, cx| state.text_wrapper.wrap_rows(rows, cx));
+            if wrapped {
+                (visible_range, visible_top) = self.calculate_visible_range(
+                    self.state.read(cx),
+                    line_height,
+                    bounds.size.height,
+                );
+            }
+        }
+        let state = self.state.read(cx);
         let visible_start_offset = state.text().line_start_offset(visible_range.start);
         let visible_end_offset = state
             .text()
@@ -1190,6 +1325,8 @@ impl Element for TextElement {
         let hover_highlight_path = self.layout_hover_highlight(&last_layout, &mut bounds, cx);
         let document_color_paths =
             self.layout_document_colors(&document_colors, &last_layout, &bounds);
+        let remote_cursors =
+            self.layout_remote_cursors(&last_layout, &bounds, text_size, window, cx);
 
         let state = self.state.read(cx);
         let line_numbers = if state.mode.line_number() {
@@ -1255,6 +1392,7 @@ impl Element for TextElement {
             hover_highlight_path,
             hover_definition_hitbox,
             document_color_paths,
+            remote_cursors,
             indent_guides_path,
             ghost_first_line,
             ghost_lines,
@@ -1378,6 +1516,13 @@ impl Element for TextElement {
             }
         }
 
+        // Paint remote selections
+        for remote in prepaint.remote_cursors.iter_mut() {
+            if let Some(path) = remote.selection_path.take() {
+                window.paint_path(path, remote.color.opacity(0.25));
+            }
+        }
+
         // Paint document colors
         for (path, color) in prepaint.document_color_paths.iter() {
             window.paint_path(path.clone(), *color);
@@ -1448,6 +1593,32 @@ impl Element for TextElement {
             }
         }
 
+        // Paint remote cursors with the name labels
+        for remote in prepaint.remote_cursors.iter() {
+            let Some(cursor_bounds) = remote.cursor_bounds else {
+                continue;
+            };
+            window.paint_quad(fill(cursor_bounds, remote.color));
+
+            let label_size = size(remote.label.width + px(8.), remote.label_height);
+            let mut label_origin = point(
+                cursor_bounds.left(),
+                cursor_bounds.top() - remote.label_height,
+            );
+            if label_origin.y < input_bounds.top() {
+                label_origin.y = cursor_bounds.bottom();
+            }
+            window.paint_quad(fill(Bounds::new(label_origin, label_size), remote.color));
+            _ = remote.label.paint(
+                label_origin + point(px(4.), px(0.)),
+                remote.label_height,
+                TextAlign::Left,
+                None,
+                window,
+                cx,
+            );
+        }
+
         // Paint blinking cursor
         if focused && show_cursor {
             if let Some(cursor_bounds) = prepaint.cursor_bounds_with_scroll() {
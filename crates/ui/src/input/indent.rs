@@ -2,17 +2,21 @@ use gpui::{
     Bounds, Context, EntityInputHandler as _, Hsla, Path, PathBuilder, Pixels, SharedString,
     TextRun, TextStyle, Window, point, px,
 };
-use ropey::RopeSlice;
+use ropey::{Rope, RopeSlice};
+use serde::Deserialize;
 
 use crate::{
     RopeExt,
     input::{
-        Indent, IndentInline, InputState, LastLayout, Outdent, OutdentInline, element::TextElement,
-        mode::InputMode,
+        ConvertIndent, Indent, IndentInline, InputState, LastLayout, Outdent, OutdentInline,
+        element::TextElement, mode::InputMode,
     },
 };
 
-#[derive(Debug, Copy, Clone)]
+/// The max lines to scan for [`TabSize::detect`].
+const DETECT_INDENT_MAX_LINES: usize = 10_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
 pub struct TabSize {
     /// Default is 2
     pub tab_size: usize,
@@ -51,6 +55,98 @@ impl TabSize {
 
         count
     }
+
+    /// Detect the indentation of the `text`, returns None if there are no indented lines.
+    ///
+    /// For `hard_tabs`, the `tab_size` is the default.
+    pub fn detect(text: &Rope) -> Option<Self> {
+        let mut tab_lines = 0;
+        let mut space_lines = 0;
+        // The count of the indent changes (in spaces) between lines, index 0 is 2 spaces.
+        let mut deltas = [0usize; 7];
+        let mut min_indent = usize::MAX;
+        let mut prev_indent = 0;
+
+        for line in text.iter_lines().take(DETECT_INDENT_MAX_LINES) {
+            let mut chars = line.chars().peekable();
+            if chars.peek() == Some(&'\t') {
+                tab_lines += 1;
+                continue;
+            }
+
+            let indent = chars.by_ref().take_while(|c| *c == ' ').count();
+            // Skip the blank lines.
+            if line.chars().all(|c| c.is_whitespace()) {
+                continue;
+            }
+
+            if indent > 0 {
+                space_lines += 1;
+                min_indent = min_indent.min(indent);
+            }
+            // The 1 space change are usually alignment, e.g. ` * ` in doc comments.
+            let delta = indent.abs_diff(prev_indent);
+            if (2..=8).contains(&delta) {
+                deltas[delta - 2] += 1;
+            }
+            prev_indent = indent;
+        }
+
+        if tab_lines == 0 && space_lines == 0 {
+            return None;
+        }
+
+        if tab_lines > space_lines {
+            return Some(Self {
+                hard_tabs: true,
+                ..Default::default()
+            });
+        }
+
+        let tab_size = deltas
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            // The first of max count, prefer the smaller size.
+            .fold(None, |acc: Option<(usize, usize)>, (ix, count)| match acc {
+                Some((_, max)) if max >= *count => acc,
+                _ => Some((ix + 2, *count)),
+            })
+            .map(|(size, _)| size)
+            .or_else(|| (2..=8).contains(&min_indent).then_some(min_indent))?;
+
+        Some(Self {
+            tab_size,
+            hard_tabs: false,
+        })
+    }
+}
+
+/// Returns the leading whitespace length in bytes of the `line`.
+fn indent_len(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Re-indent each line of the `text` from the `from` indentation to the `to`.
+///
+/// The indent levels are kept, the remaining spaces (for alignment) are kept as spaces.
+fn reindent_text(text: &str, from: TabSize, to: TabSize) -> String {
+    text.split('\n')
+        .map(|line| {
+            let indent = indent_len(line);
+            let count = from.indent_count(&RopeSlice::from(&line[..indent]));
+            let tab_size = from.tab_size.max(1);
+            let (levels, rest) = (count / tab_size, count % tab_size);
+            let new_indent = if to.hard_tabs {
+                format!("{}{}", "\t".repeat(levels), " ".repeat(rest))
+            } else {
+                " ".repeat(levels * to.tab_size + rest)
+            };
+
+            format!("{}{}", new_indent, &line[indent..])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 impl InputMode {
@@ -115,7 +211,7 @@ impl TextElement {
         text_style: &TextStyle,
         window: &mut Window,
     ) -> Option<Path<Pixels>> {
-        if !state.mode.has_indent_guides() {
+        if !state.mode.has_indent_guides() || state.is_large_file() {
             return None;
         }
 
@@ -216,6 +312,101 @@ impl InputState {
         self
     }
 
+    /// Set the tab size for the input.
+    ///
+    /// This does not change the text, use [`InputState::reindent`] to convert the indentation.
+    pub fn set_tab_size(&mut self, tab: TabSize, _: &mut Window, cx: &mut Context<Self>) {
+        match &mut self.mode {
+            InputMode::PlainText { tab: t, .. } => *t = tab,
+            InputMode::CodeEditor { tab: t, .. } => *t = tab,
+            _ => {}
+        }
+        cx.notify();
+    }
+
+    /// Set to detect the indentation of the text on [`InputState::set_value`], default is false.
+    ///
+    /// When enabled the detected indentation overrides the [`InputState::tab_size`].
+    ///
+    /// NOTE: Call this before the `default_value`.
+    pub fn detect_indent(mut self, detect_indent: bool) -> Self {
+        self.detect_indent = detect_indent;
+        self
+    }
+
+    /// Returns the indentation detected when the text was loaded, None if not detected.
+    pub fn detected_indent(&self) -> Option<TabSize> {
+        self.detected_indent
+    }
+
+    /// Detect the indentation of the text, and apply to the tab size if `detect_indent` is enabled.
+    pub(super) fn update_detected_indent(&mut self) {
+        if !self.mode.is_indentable() {
+            return;
+        }
+
+        self.detected_indent = TabSize::detect(&self.text);
+        if !self.detect_indent {
+            return;
+        }
+
+        let Some(detected) = self.detected_indent else {
+            return;
+        };
+        match &mut self.mode {
+            InputMode::PlainText { tab, .. } | InputMode::CodeEditor { tab, .. } => {
+                tab.hard_tabs = detected.hard_tabs;
+                if !detected.hard_tabs {
+                    tab.tab_size = detected.tab_size;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Re-indent all the lines from the current tab size to the `tab`, and use it as the tab size.
+    pub fn reindent(&mut self, tab: TabSize, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.mode.is_indentable() {
+            return;
+        }
+
+        let from = self.mode.tab_size();
+        let text = self.text.to_string();
+        let new_text = reindent_text(&text, from, tab);
+
+        if new_text != text {
+            // Keep the cursor at the same column relative to the indentation.
+            let cursor = self.cursor();
+            let row = self.text.offset_to_point(cursor).row;
+            let column = cursor - self.text.line_start_offset(row);
+            let old_indent = indent_len(&self.text.slice_line(row).to_string());
+
+            let range_utf16 = self.range_to_utf16(&(0..self.text.len()));
+            self.replace_text_in_range_silent(Some(range_utf16), &new_text, window, cx);
+
+            let new_indent = indent_len(&self.text.slice_line(row).to_string());
+            let column = if column >= old_indent {
+                column - old_indent + new_indent
+            } else {
+                column.min(new_indent)
+            };
+            let cursor = (self.text.line_start_offset(row) + column).min(self.text.len());
+            self.selected_range = (cursor..cursor).into();
+        }
+
+        // The `Change` event is emitted by the replace if the text changed.
+        self.set_tab_size(tab, window, cx);
+    }
+
+    pub(super) fn convert_indent(
+        &mut self,
+        action: &ConvertIndent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.reindent(action.tab, window, cx);
+    }
+
     pub(super) fn indent_inline(
         &mut self,
         _: &IndentInline,
@@ -386,9 +577,10 @@ impl InputState {
 
 #[cfg(test)]
 mod tests {
-    use ropey::RopeSlice;
+    use indoc::indoc;
+    use ropey::{Rope, RopeSlice};
 
-    use super::TabSize;
+    use super::{TabSize, reindent_text};
 
     #[test]
     fn test_tab_size() {
@@ -429,4 +621,72 @@ mod tests {
         assert_eq!(tab.indent_count(&RopeSlice::from(" \t abc  ")), 6);
         assert_eq!(tab.indent_count(&RopeSlice::from("abc")), 0);
     }
+
+    #[test]
+    fn test_detect_indent() {
+        let detect = |text: &str| TabSize::detect(&Rope::from(text));
+        let spaces = |tab_size| TabSize {
+            tab_size,
+            hard_tabs: false,
+        };
+
+        assert_eq!(detect("fn main() {}\n"), None);
+        assert_eq!(
+            detect(indoc! {"
+                fn main() {
+                    if true {
+                        println!(\"Hello\");
+                    }
+                }
+            "}),
+            Some(spaces(4))
+        );
+        assert_eq!(
+            detect(indoc! {"
+                a:
+                  b:
+                    c: 1
+                  /**
+                   * comment
+                   */
+                  d: 2
+            "}),
+            Some(spaces(2))
+        );
+        assert_eq!(
+            detect("fn main() {\n\tif true {\n\t\tfoo();\n\t}\n}\n"),
+            Some(TabSize {
+                hard_tabs: true,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_reindent_text() {
+        let two = TabSize {
+            tab_size: 2,
+            hard_tabs: false,
+        };
+        let four = TabSize {
+            tab_size: 4,
+            hard_tabs: false,
+        };
+        let tabs = TabSize {
+            tab_size: 4,
+            hard_tabs: true,
+        };
+
+        let text = "a {\n  b {\n    c\n   d\n  }\n}";
+        assert_eq!(
+            reindent_text(text, two, four),
+            "a {\n    b {\n        c\n     d\n    }\n}"
+        );
+        assert_eq!(
+            reindent_text(&reindent_text(text, two, four), four, two),
+            text
+        );
+        assert_eq!(reindent_text("a {\n    b\n}", four, tabs), "a {\n\tb\n}");
+        assert_eq!(reindent_text("a {\n\tb\n}", tabs, two), "a {\n  b\n}");
+    }
 }
@@ -305,6 +305,10 @@ impl RenderOnce for Input {
                             .on_action(window.listener_for(&self.state, InputState::outdent_inline))
                             .on_action(window.listener_for(&self.state, InputState::indent_block))
                             .on_action(window.listener_for(&self.state, InputState::outdent_block))
+                            .on_action(window.listener_for(&self.state, InputState::convert_indent))
+                            .on_action(
+                                window.listener_for(&self.state, InputState::convert_line_ending),
+                            )
                     })
                     .on_action(
                         window.listener_for(&self.state, InputState::on_action_toggle_code_actions),
@@ -325,6 +329,10 @@ impl RenderOnce for Input {
                         window.listener_for(&self.state, InputState::on_action_go_to_definition),
                     )
             })
+            .when(state.mode.is_code_editor(), |this| {
+                this.on_action(window.listener_for(&self.state, InputState::next_diagnostic))
+                    .on_action(window.listener_for(&self.state, InputState::previous_diagnostic))
+            })
             .on_action(window.listener_for(&self.state, InputState::select_all))
             .on_action(window.listener_for(&self.state, InputState::select_to_start_of_line))
             .on_action(window.listener_for(&self.state, InputState::select_to_end_of_line))
@@ -0,0 +1,105 @@
+use std::{ops::Range, time::Duration};
+
+use gpui::{Context, Timer};
+
+use ropey::Rope;
+
+use crate::input::{InputState, mode::InputMode};
+
+/// The default text size in bytes to enable the large file mode, 5MB.
+pub const LARGE_FILE_THRESHOLD: usize = 5 * 1024 * 1024;
+
+/// The delay to start the background parse after the last edit.
+const PARSE_DEBOUNCE: Duration = Duration::from_millis(100);
+
+impl InputState {
+    /// Set the text size in bytes to enable the large file mode, default is [`LARGE_FILE_THRESHOLD`].
+    ///
+    /// In the large file mode, to keep the editor interactive:
+    ///
+    /// - The syntax parsing is deferred to a background task, the highlights of the visible
+    ///   lines are shifted by the edits until the parse is done.
+    /// - The soft wrap is only calculated for the visible lines plus a margin.
+    /// - The indent guides are not displayed.
+    pub fn large_file_threshold(mut self, threshold: usize) -> Self {
+        self.large_file_threshold = threshold;
+        self
+    }
+
+    /// Returns true if the text size is larger than the [`InputState::large_file_threshold`].
+    pub fn is_large_file(&self) -> bool {
+        self.text.len() > self.large_file_threshold
+    }
+
+    /// Update the syntax highlighter for the `range` replaced by the `new_text`.
+    ///
+    /// In the large file mode, the parse is run in a background task.
+    pub(super) fn update_highlighter(
+        &mut self,
+        range: &Range<usize>,
+        new_text: &str,
+        force: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let deferred = self.is_large_file();
+        let updated = self
+            .mode
+            .update_highlighter(range, &self.text, new_text, force, deferred, cx);
+
+        if deferred && updated {
+            self.parse_in_background(cx);
+        }
+    }
+
+    /// Update the soft wrap for the `range` replaced by the `new_text`.
+    ///
+    /// In the large file mode, the changed lines are wrapped lazily when they are visible.
+    pub(super) fn update_text_wrapper(
+        &mut self,
+        range: &Range<usize>,
+        new_text: &str,
+        cx: &mut Context<Self>,
+    ) {
+        let lazy = self.is_large_file();
+        self.text_wrapper.set_lazy(lazy, cx);
+        self.text_wrapper
+            .update(&self.text, range, &Rope::from(new_text), cx);
+    }
+
+    fn parse_in_background(&mut self, cx: &mut Context<Self>) {
+        let InputMode::CodeEditor { highlighter, .. } = &self.mode else {
+            return;
+        };
+
+        let highlighter = highlighter.clone();
+        self._parse_task = cx.spawn(async move |this, cx| {
+            Timer::after(PARSE_DEBOUNCE).await;
+
+            let Some((revision, parse)) = highlighter
+                .borrow()
+                .as_ref()
+                .map(|highlighter| highlighter.parse_task())
+            else {
+                return;
+            };
+
+            let Some(tree) = cx.background_executor().spawn(async move { parse() }).await else {
+                return;
+            };
+
+            if let Some(this) = this.upgrade() {
+                this.update(cx, |_, cx| {
+                    let applied = highlighter
+                        .borrow_mut()
+                        .as_mut()
+                        .map_or(false, |highlighter| {
+                            highlighter.set_parsed_tree(revision, tree)
+                        });
+                    if applied {
+                        cx.notify();
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,144 @@
+use gpui::{Context, SharedString, Window};
+use serde::Deserialize;
+
+use crate::input::{ConvertLineEnding, InputEvent, InputState};
+
+/// The line ending of the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+pub enum LineEnding {
+    /// `\n`, the default.
+    #[default]
+    Lf,
+    /// `\r\n`, used by Windows.
+    Crlf,
+    /// Both `\n` and `\r\n` are used in the text.
+    Mixed,
+}
+
+impl LineEnding {
+    /// Detect the line ending of the `text`, [`LineEnding::Lf`] if no line breaks.
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+
+        match (lf, crlf) {
+            (_, 0) => LineEnding::Lf,
+            (0, _) => LineEnding::Crlf,
+            _ => LineEnding::Mixed,
+        }
+    }
+
+    /// Returns the line break string, None for [`LineEnding::Mixed`].
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            LineEnding::Lf => Some("\n"),
+            LineEnding::Crlf => Some("\r\n"),
+            LineEnding::Mixed => None,
+        }
+    }
+
+    /// Returns the display label, e.g. "LF", "CRLF".
+    pub fn label(&self) -> SharedString {
+        match self {
+            LineEnding::Lf => "LF".into(),
+            LineEnding::Crlf => "CRLF".into(),
+            LineEnding::Mixed => "Mixed".into(),
+        }
+    }
+
+    /// Normalize the `text` to use `\n`, unless the line ending is [`LineEnding::Mixed`].
+    pub(super) fn normalize(&self, text: &str) -> String {
+        match self {
+            LineEnding::Crlf => text.replace("\r\n", "\n"),
+            LineEnding::Lf | LineEnding::Mixed => text.to_string(),
+        }
+    }
+
+    /// Restore the normalized `text` to use this line ending.
+    pub(super) fn restore(&self, text: &str) -> String {
+        match self {
+            LineEnding::Crlf => text.replace('\n', "\r\n"),
+            LineEnding::Lf | LineEnding::Mixed => text.to_string(),
+        }
+    }
+}
+
+impl InputState {
+    /// Returns the line ending detected when the text was loaded.
+    ///
+    /// The text is kept with `\n` internally (see [`InputState::text`]),
+    /// the [`InputState::value`] returns the text with this line ending.
+    ///
+    /// For [`LineEnding::Mixed`], the text is kept as it is.
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Set the line ending of the [`InputState::value`].
+    ///
+    /// When the text is [`LineEnding::Mixed`], all the `\r\n` will be converted to `\n` internally.
+    /// Set [`LineEnding::Mixed`] does nothing.
+    pub fn set_line_ending(
+        &mut self,
+        line_ending: LineEnding,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if line_ending == LineEnding::Mixed || line_ending == self.line_ending {
+            return;
+        }
+
+        if self.line_ending == LineEnding::Mixed {
+            let cursor = self.cursor();
+            let removed = self.text.slice(0..cursor).to_string().matches("\r\n").count();
+            let text = self.text.to_string().replace("\r\n", "\n");
+
+            let range_utf16 = self.range_to_utf16(&(0..self.text.len()));
+            self.replace_text_in_range_silent(Some(range_utf16), &text, window, cx);
+            let cursor = cursor.saturating_sub(removed).min(self.text.len());
+            self.selected_range = (cursor..cursor).into();
+        }
+
+        self.line_ending = line_ending;
+        cx.emit(InputEvent::Change);
+        cx.notify();
+    }
+
+    pub(super) fn convert_line_ending(
+        &mut self,
+        action: &ConvertLineEnding,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_line_ending(action.line_ending, window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineEnding;
+
+    #[test]
+    fn test_detect_line_ending() {
+        assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("Hello"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("Hello\nWorld\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("Hello\r\nWorld\r\n"), LineEnding::Crlf);
+        assert_eq!(LineEnding::detect("Hello\r\nWorld\n"), LineEnding::Mixed);
+    }
+
+    #[test]
+    fn test_normalize_restore() {
+        let text = "Hello\r\nWorld\r\n\r\n";
+        let line_ending = LineEnding::detect(text);
+        let normalized = line_ending.normalize(text);
+        assert_eq!(normalized, "Hello\nWorld\n\n");
+        assert_eq!(line_ending.restore(&normalized), text);
+
+        let text = "Hello\r\nWorld\n";
+        let line_ending = LineEnding::detect(text);
+        let normalized = line_ending.normalize(text);
+        assert_eq!(normalized, text);
+        assert_eq!(line_ending.restore(&normalized), text);
+    }
+}
@@ -1,10 +1,14 @@
 mod blink_cursor;
 mod change;
 mod clear_button;
+mod collab;
 mod cursor;
+mod diagnostics;
 mod element;
 mod indent;
 mod input;
+mod large_file;
+mod line_ending;
 mod lsp;
 mod mask_pattern;
 mod mode;
@@ -15,17 +19,24 @@ pub(crate) mod popovers;
 mod rope_ext;
 mod search;
 mod selection;
+mod spell_check;
 mod state;
 mod text_wrapper;
 
+pub use change::Change;
 pub(crate) use clear_button::*;
+pub use collab::{LocalEdit, RemoteCursor};
 pub use cursor::*;
+pub use diagnostics::DiagnosticsPanel;
 pub use indent::TabSize;
 pub use input::*;
+pub use large_file::LARGE_FILE_THRESHOLD;
+pub use line_ending::LineEnding;
 pub use lsp::*;
 pub use mask_pattern::MaskPattern;
 pub use number_input::{NumberInput, NumberInputEvent, StepAction};
 pub use otp_input::*;
+pub use spell_check::{DictionarySpellChecker, SpellCheckProvider};
 pub use state::*;
 
 pub use lsp_types::Position;
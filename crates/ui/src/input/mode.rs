@@ -188,14 +188,21 @@ impl InputMode {
         }
     }
 
+    /// Update the syntax highlighter for the `selected_range` replaced by the `new_text`.
+    ///
+    /// If `deferred` is true, the edit is applied without parsing,
+    /// the caller should parse by [`SyntaxHighlighter::parse_task`].
+    ///
+    /// Returns true if the edit has been applied to the highlighter.
     pub(super) fn update_highlighter(
         &mut self,
         selected_range: &Range<usize>,
         text: &Rope,
         new_text: &str,
         force: bool,
+        deferred: bool,
         cx: &mut App,
-    ) {
+    ) -> bool {
         match &self {
             InputMode::CodeEditor {
                 language,
@@ -203,7 +210,7 @@ impl InputMode {
                 ..
             } => {
                 if !force && highlighter.borrow().is_some() {
-                    return;
+                    return false;
                 }
 
                 let mut highlighter = highlighter.borrow_mut();
@@ -213,7 +220,7 @@ impl InputMode {
                 }
 
                 let Some(highlighter) = highlighter.as_mut() else {
-                    return;
+                    return false;
                 };
 
                 // When full text changed, the selected_range may be out of bound (The before version).
@@ -240,9 +247,15 @@ impl InputMode {
                     new_end_position: new_end_pos,
                 };
 
-                highlighter.update(Some(edit), text);
+                if deferred {
+                    highlighter.edit(edit, text);
+                } else {
+                    highlighter.update(Some(edit), text);
+                }
+
+                true
             }
-            _ => {}
+            _ => false,
         }
     }
 
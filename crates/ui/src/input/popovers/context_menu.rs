@@ -1,14 +1,16 @@
+use std::ops::Range;
+
 use gpui::{
     App, AppContext as _, Context, Corner, DismissEvent, Entity, IntoElement, MouseDownEvent,
-    ParentElement as _, Pixels, Point, Render, Styled, Subscription, Window, anchored, deferred,
-    div, prelude::FluentBuilder as _, px,
+    ParentElement as _, Pixels, Point, Render, SharedString, Styled, Subscription, Window,
+    anchored, deferred, div, prelude::FluentBuilder as _, px,
 };
 use rust_i18n::t;
 
 use crate::{
     ActiveTheme as _,
     input::{self, InputState, popovers::ContextMenu},
-    menu::PopupMenu,
+    menu::{PopupMenu, PopupMenuItem},
 };
 
 /// Context menu for mouse right clicks.
@@ -47,11 +49,43 @@ impl InputState {
         let is_selected = !self.selected_range.is_empty();
         let has_paste = is_enable && cx.read_from_clipboard().is_some();
 
+        let spelling = self.spelling_suggestions_at(offset);
+
+        let editor = cx.entity();
         let action_context = self.focus_handle_ref().clone();
         self.mouse_context_menu.update(cx, |this, cx| {
             this.mouse_position = event.position;
             this.menu.update(cx, |menu, cx| {
                 let new_menu = PopupMenu::new(cx)
+                    .when_some(spelling, |mut m, (range, word, suggestions)| {
+                        if suggestions.is_empty() {
+                            m = m.item(
+                                PopupMenuItem::new(t!("Input.No Spelling Suggestions"))
+                                    .disabled(true),
+                            );
+                        }
+
+                        for suggestion in suggestions {
+                            let editor = editor.clone();
+                            let range = range.clone();
+                            let word = word.clone();
+                            m = m.item(PopupMenuItem::new(suggestion.clone()).on_click(
+                                move |_, window, cx| {
+                                    editor.update(cx, |state, cx| {
+                                        state.replace_misspelled_word(
+                                            &range,
+                                            &word,
+                                            &suggestion,
+                                            window,
+                                            cx,
+                                        );
+                                    });
+                                },
+                            ));
+                        }
+
+                        m.separator()
+                    })
                     .when(is_code_editor, |m| {
                         m.menu_with_enable(
                             t!("Input.Go to Definition"),
@@ -84,6 +118,38 @@ impl InputState {
             cx.notify();
         });
     }
+
+    /// Returns the range, the word and the spelling suggestions of the misspelled word at the `offset`.
+    fn spelling_suggestions_at(
+        &self,
+        offset: usize,
+    ) -> Option<(Range<usize>, SharedString, Vec<SharedString>)> {
+        let provider = self.spell_check_provider.clone()?;
+        let range = self.misspelled_word_at(offset)?;
+        let word: SharedString = self.text().slice(range.clone()).to_string().into();
+        let suggestions = provider.suggestions(&word);
+
+        Some((range, word, suggestions))
+    }
+
+    /// Replace the misspelled `word` at the `range` with the `suggestion`.
+    ///
+    /// Do nothing if the text has been changed since the menu was opened.
+    fn replace_misspelled_word(
+        &mut self,
+        range: &Range<usize>,
+        word: &str,
+        suggestion: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if range.end > self.text().len() || self.text().slice(range.clone()).to_string() != word {
+            return;
+        }
+
+        let range_utf16 = self.range_to_utf16(range);
+        self.replace_text_in_range_silent(Some(range_utf16), suggestion, window, cx);
+    }
 }
 
 impl MouseContextMenu {
@@ -0,0 +1,499 @@
+use std::{collections::HashSet, ops::Range, path::Path, rc::Rc};
+
+use anyhow::Result;
+use gpui::{App, Context, HighlightStyle, SharedString, UnderlineStyle, px};
+use sum_tree::Bias;
+use unicode_segmentation::UnicodeSegmentation as _;
+
+use crate::{
+    ActiveTheme as _,
+    highlighter::SyntaxHighlighter,
+    input::{InputState, RopeExt as _, mode::InputMode},
+};
+
+/// A provider to check the spelling of the words in an [`InputState`].
+///
+/// In plain text mode all the visible words are checked, and in code editor mode
+/// only the words inside of the `comment` and `string` highlight captures,
+/// except the prose languages (e.g.: Markdown) are checked as plain text without the code.
+pub trait SpellCheckProvider {
+    /// Return true if the `word` is spelled correctly.
+    fn check(&self, word: &str) -> bool;
+
+    /// Return the suggestions to replace the misspelled `word`, best first.
+    fn suggestions(&self, word: &str) -> Vec<SharedString>;
+}
+
+/// A [`SpellCheckProvider`] based on a word list, works offline.
+///
+/// The word list can be a Hunspell `.dic` file, or a plain text file with one word per line.
+/// Affix flags (`word/FLAGS`) are ignored, so only the listed word forms are accepted.
+///
+/// ```ignore
+/// let checker = DictionarySpellChecker::load("/usr/share/hunspell/en_US.dic")?;
+/// let state = InputState::new(window, cx)
+///     .multi_line(true)
+///     .spell_check(checker);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DictionarySpellChecker {
+    words: HashSet<String>,
+    max_suggestions: usize,
+}
+
+impl DictionarySpellChecker {
+    /// Create a spell checker with the given words.
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            words: words
+                .into_iter()
+                .map(|word| normalize_word(word.as_ref()))
+                .filter(|word| !word.is_empty())
+                .collect(),
+            max_suggestions: 5,
+        }
+    }
+
+    /// Parse the dictionary from the source of a Hunspell `.dic` file or a word list.
+    pub fn parse(source: &str) -> Self {
+        let mut lines = source.lines().peekable();
+
+        // The first line of a Hunspell dictionary is the approximate word count.
+        if let Some(first) = lines.peek() {
+            if first.trim().parse::<usize>().is_ok() {
+                lines.next();
+            }
+        }
+
+        Self::new(lines.filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+
+            // Ignore the morphological fields after the word.
+            let word = line.split_whitespace().next()?;
+            // Ignore the affix flags.
+            let word = word.split('/').next()?;
+            Some(word.to_string())
+        }))
+    }
+
+    /// Load the dictionary from a Hunspell `.dic` file or a word list file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&source))
+    }
+
+    /// Set the max number of suggestions, default is 5.
+    pub fn max_suggestions(mut self, max_suggestions: usize) -> Self {
+        self.max_suggestions = max_suggestions;
+        self
+    }
+
+    /// Add a word to the dictionary.
+    pub fn add_word(&mut self, word: impl AsRef<str>) {
+        let word = normalize_word(word.as_ref());
+        if !word.is_empty() {
+            self.words.insert(word);
+        }
+    }
+
+    /// Returns the number of words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns true if the dictionary has no words.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl SpellCheckProvider for DictionarySpellChecker {
+    fn check(&self, word: &str) -> bool {
+        let word = normalize_word(word);
+        if self.words.contains(&word) {
+            return true;
+        }
+
+        // Like Hunspell, a lowercase entry also accepts the capitalized and uppercase forms,
+        // and a capitalized entry also accepts the uppercase form.
+        match word_case(&word) {
+            WordCase::Capitalized => self.words.contains(&word.to_lowercase()),
+            WordCase::Upper => {
+                let lower = word.to_lowercase();
+                self.words.contains(&lower) || self.words.contains(&capitalize(&lower))
+            }
+            WordCase::Lower | WordCase::Mixed => false,
+        }
+    }
+
+    fn suggestions(&self, word: &str) -> Vec<SharedString> {
+        let word = normalize_word(word);
+        let case = word_case(&word);
+        let lower = word.to_lowercase();
+        let chars: Vec<char> = lower.chars().collect();
+        let max_distance = if chars.len() <= 4 { 1 } else { 2 };
+
+        let mut candidates: Vec<(usize, &String)> = self
+            .words
+            .iter()
+            .filter_map(|candidate| {
+                let candidate_len = candidate.chars().count();
+                if candidate_len.abs_diff(chars.len()) > max_distance {
+                    return None;
+                }
+
+                let distance = edit_distance(&chars, &candidate.to_lowercase());
+                (distance <= max_distance).then_some((distance, candidate))
+            })
+            .collect();
+        candidates.sort();
+
+        let mut suggestions: Vec<SharedString> = vec![];
+        for (_, candidate) in candidates {
+            let suggestion: SharedString = match (case, word_case(candidate)) {
+                (WordCase::Capitalized, WordCase::Lower) => capitalize(candidate).into(),
+                (WordCase::Upper, _) => candidate.to_uppercase().into(),
+                _ => candidate.clone().into(),
+            };
+
+            if !suggestions.contains(&suggestion) {
+                suggestions.push(suggestion);
+            }
+            if suggestions.len() >= self.max_suggestions {
+                break;
+            }
+        }
+
+        suggestions
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WordCase {
+    Lower,
+    Capitalized,
+    Upper,
+    Mixed,
+}
+
+fn word_case(word: &str) -> WordCase {
+    let mut chars = word.chars().filter(|c| c.is_alphabetic());
+    let Some(first) = chars.next() else {
+        return WordCase::Lower;
+    };
+    let rest: Vec<char> = chars.collect();
+
+    if first.is_lowercase() {
+        if rest.iter().all(|c| c.is_lowercase()) {
+            WordCase::Lower
+        } else {
+            WordCase::Mixed
+        }
+    } else if rest.iter().all(|c| c.is_lowercase()) {
+        WordCase::Capitalized
+    } else if rest.iter().all(|c| c.is_uppercase()) {
+        WordCase::Upper
+    } else {
+        WordCase::Mixed
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Use the ASCII apostrophe for the typographic one, e.g.: `don’t` to `don't`.
+fn normalize_word(word: &str) -> String {
+    word.trim().replace('’', "'")
+}
+
+/// The optimal string alignment distance between two words.
+fn edit_distance(a: &[char], b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        rows[0][j] = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// Returns the byte ranges of the misspelled words in the `text`.
+///
+/// Words with digits or underscores (e.g.: identifiers) are not checked.
+pub(super) fn misspelled_words(text: &str, provider: &dyn SpellCheckProvider) -> Vec<Range<usize>> {
+    text.split_word_bound_indices()
+        .filter(|(_, word)| {
+            word.chars().any(|c| c.is_alphabetic())
+                && !word.chars().any(|c| c.is_numeric() || c == '_')
+        })
+        .filter(|(_, word)| !provider.check(word))
+        .map(|(ix, word)| ix..ix + word.len())
+        .collect()
+}
+
+/// Returns true if the `language` is written in prose, e.g.: Markdown.
+fn is_prose_language(language: &str) -> bool {
+    matches!(language, "markdown" | "md" | "mdx" | "text" | "plain")
+}
+
+/// Returns true if the capture `name` is the `prefix` or a sub-capture of it, e.g.: `comment.doc`.
+fn capture_matches(name: &str, prefix: &str) -> bool {
+    name == prefix
+        || name
+            .strip_prefix(prefix)
+            .is_some_and(|s| s.starts_with('.'))
+}
+
+/// Returns the ranges to check the spelling in the `range` of a code editor.
+///
+/// For the prose languages (e.g.: Markdown), the whole text is checked except the code and URLs,
+/// for the other languages only the `comment` and `string` captures are checked.
+fn spell_check_segments(
+    language: &str,
+    highlighter: Option<&SyntaxHighlighter>,
+    range: &Range<usize>,
+) -> Vec<Range<usize>> {
+    let captures = highlighter
+        .map(|highlighter| highlighter.captures(range))
+        .unwrap_or_default();
+
+    if is_prose_language(language) {
+        let mut excluded = captures
+            .into_iter()
+            .filter(|(_, name)| {
+                ["text.literal", "text.uri", "link_uri"]
+                    .iter()
+                    .any(|prefix| capture_matches(name, prefix))
+            })
+            .map(|(node_range, _)| node_range)
+            .collect::<Vec<_>>();
+        excluded.sort_by_key(|node_range| node_range.start);
+
+        let mut segments = vec![];
+        let mut start = range.start;
+        for node_range in excluded {
+            let end = node_range.start.min(range.end);
+            if end > start {
+                segments.push(start..end);
+            }
+            start = start.max(node_range.end);
+        }
+        if start < range.end {
+            segments.push(start..range.end);
+        }
+        return segments;
+    }
+
+    captures
+        .into_iter()
+        .filter(|(_, name)| {
+            ["comment", "string"]
+                .iter()
+                .any(|prefix| capture_matches(name, prefix))
+        })
+        .map(|(node_range, _)| node_range.start.max(range.start)..node_range.end.min(range.end))
+        .filter(|node_range| node_range.start < node_range.end)
+        .collect()
+}
+
+/// The highlight style for the misspelled words.
+pub(super) fn misspelled_style(cx: &App) -> HighlightStyle {
+    HighlightStyle {
+        underline: Some(UnderlineStyle {
+            color: Some(cx.theme().danger),
+            thickness: px(1.),
+            wavy: true,
+        }),
+        ..Default::default()
+    }
+}
+
+impl InputState {
+    /// Set the [`SpellCheckProvider`] to check the spelling of the words.
+    pub fn spell_check(mut self, provider: impl SpellCheckProvider + 'static) -> Self {
+        self.spell_check_provider = Some(Rc::new(provider));
+        self
+    }
+
+    /// Set the [`SpellCheckProvider`], None to disable spell checking.
+    pub fn set_spell_check_provider(
+        &mut self,
+        provider: Option<Rc<dyn SpellCheckProvider>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.spell_check_provider = provider;
+        cx.notify();
+    }
+
+    /// Returns the byte ranges of the misspelled words in the `range`.
+    pub(super) fn misspelled_ranges(&self, range: &Range<usize>) -> Vec<Range<usize>> {
+        let Some(provider) = self.spell_check_provider.as_ref() else {
+            return vec![];
+        };
+        if self.masked {
+            return vec![];
+        }
+
+        let text = self.text();
+        let range = range.start.min(text.len())..range.end.min(text.len());
+        let segments = match &self.mode {
+            InputMode::CodeEditor {
+                language,
+                highlighter,
+                ..
+            } => spell_check_segments(language, highlighter.borrow().as_ref(), &range),
+            _ => vec![range],
+        };
+
+        let mut ranges: Vec<Range<usize>> = vec![];
+        for segment in segments {
+            let start = text.clip_offset(segment.start, Bias::Left);
+            let end = text.clip_offset(segment.end, Bias::Right);
+            let content = text.slice(start..end).to_string();
+            for word_range in misspelled_words(&content, provider.as_ref()) {
+                let word_range = start + word_range.start..start + word_range.end;
+                // Captures may overlap, avoid to check the same word twice.
+                if !ranges.contains(&word_range) {
+                    ranges.push(word_range);
+                }
+            }
+        }
+        ranges.sort_by_key(|range| range.start);
+        ranges
+    }
+
+    /// Returns the highlight styles for the misspelled words in the `range`.
+    pub(super) fn spelling_styles(
+        &self,
+        range: &Range<usize>,
+        cx: &App,
+    ) -> Vec<(Range<usize>, HighlightStyle)> {
+        let style = misspelled_style(cx);
+        self.misspelled_ranges(range)
+            .into_iter()
+            .map(|range| (range, style))
+            .collect()
+    }
+
+    /// Returns the range of the misspelled word at the `offset`, if any.
+    pub(super) fn misspelled_word_at(&self, offset: usize) -> Option<Range<usize>> {
+        let text = self.text();
+        let row = text.offset_to_point(offset).row;
+        let line_range = text.line_start_offset(row)..text.line_end_offset(row);
+
+        self.misspelled_ranges(&line_range)
+            .into_iter()
+            .find(|range| range.start <= offset && offset <= range.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DictionarySpellChecker, SpellCheckProvider as _, misspelled_words};
+
+    #[test]
+    fn test_parse_dictionary() {
+        let checker = DictionarySpellChecker::parse(
+            "5\nhello/MS\nworld\tpo:noun\nParis\ndon't\n\n# comment\n",
+        );
+        assert_eq!(checker.len(), 4);
+
+        assert!(checker.check("hello"));
+        assert!(checker.check("Hello"));
+        assert!(checker.check("HELLO"));
+        assert!(checker.check("world"));
+        assert!(checker.check("Paris"));
+        assert!(checker.check("PARIS"));
+        assert!(!checker.check("paris"));
+        assert!(checker.check("don't"));
+        assert!(checker.check("don’t"));
+        assert!(!checker.check("helo"));
+        assert!(!checker.check("hElLo"));
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let checker = DictionarySpellChecker::new(["hello", "help", "world", "word", "would"])
+            .max_suggestions(2);
+
+        assert_eq!(checker.suggestions("helo"), vec!["hello", "help"]);
+        assert_eq!(checker.suggestions("Wrold"), vec!["World", "Word"]);
+        assert_eq!(checker.suggestions("WROLD"), vec!["WORLD", "WORD"]);
+        assert!(checker.suggestions("xyzzy").is_empty());
+    }
+
+    #[test]
+    fn test_misspelled_words() {
+        let checker = DictionarySpellChecker::new(["this", "is", "a", "test"]);
+        let text = "This is a tset, a_b 123abc x2 tеst!";
+        let ranges = misspelled_words(text, &checker);
+        let words = ranges
+            .iter()
+            .map(|range| &text[range.clone()])
+            .collect::<Vec<_>>();
+        assert_eq!(words, vec!["tset", "tеst"]);
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_spell_check_segments() {
+        use ropey::Rope;
+
+        use super::spell_check_segments;
+        use crate::highlighter::SyntaxHighlighter;
+
+        let checker = DictionarySpellChecker::new(["hello", "world", "see", "the", "docs"]);
+        let check = |language: &str, code: &str| {
+            let text = Rope::from_str(code);
+            let mut highlighter = SyntaxHighlighter::new(language);
+            highlighter.update(None, &text);
+
+            let mut words = spell_check_segments(language, Some(&highlighter), &(0..code.len()))
+                .into_iter()
+                .flat_map(|segment| {
+                    misspelled_words(&code[segment.clone()], &checker)
+                        .into_iter()
+                        .map(move |range| segment.start + range.start..segment.start + range.end)
+                })
+                .map(|range| code[range].to_string())
+                .collect::<Vec<_>>();
+            // Captures may overlap.
+            words.dedup();
+            words
+        };
+
+        let markdown = "# Helo world\n\nSee teh docs.\n\n```rust\nlet wrold = 1;\n```\n";
+        assert_eq!(check("markdown", markdown), vec!["Helo", "teh"]);
+
+        let rust = "// helo world\nlet wrold = \"teh docs\";\n";
+        assert_eq!(check("rust", rust), vec!["helo", "teh"]);
+    }
+}
@@ -34,9 +34,15 @@ use crate::input::{
     search::{self, SearchPanel},
     text_wrapper::LineLayout,
 };
-use crate::input::{InlineCompletion, RopeExt as _, Selection};
+use crate::input::{
+    InlineCompletion, LARGE_FILE_THRESHOLD, LineEnding, LocalEdit, RemoteCursor, RopeExt as _,
+    Selection, SpellCheckProvider, TabSize,
+};
 use crate::{Root, history::History};
-use crate::{highlighter::DiagnosticSet, input::text_wrapper::LineItem};
+use crate::{
+    highlighter::{DiagnosticSet, DiagnosticSeverity},
+    input::text_wrapper::LineItem,
+};
 
 static FORCE_CURSOR_VISIBLE: AtomicBool = AtomicBool::new(false);
 
@@ -55,6 +61,36 @@ pub struct Enter {
     pub secondary: bool,
 }
 
+/// Move the cursor to the next diagnostic and show its popover.
+#[derive(Action, Clone, Default, PartialEq, Eq, Deserialize)]
+#[action(namespace = input, no_json)]
+pub struct NextDiagnostic {
+    /// Only go to diagnostics with this severity, None for all.
+    pub severity: Option<DiagnosticSeverity>,
+}
+
+/// Move the cursor to the previous diagnostic and show its popover.
+#[derive(Action, Clone, Default, PartialEq, Eq, Deserialize)]
+#[action(namespace = input, no_json)]
+pub struct PreviousDiagnostic {
+    /// Only go to diagnostics with this severity, None for all.
+    pub severity: Option<DiagnosticSeverity>,
+}
+
+/// Convert the line ending of the text.
+#[derive(Action, Clone, PartialEq, Eq, Deserialize)]
+#[action(namespace = input, no_json)]
+pub struct ConvertLineEnding {
+    pub line_ending: LineEnding,
+}
+
+/// Re-indent the text from the current tab size to the `tab`.
+#[derive(Action, Clone, PartialEq, Eq, Deserialize)]
+#[action(namespace = input, no_json)]
+pub struct ConvertIndent {
+    pub tab: TabSize,
+}
+
 actions!(
     input,
     [
@@ -229,6 +265,8 @@ pub(crate) fn init(cx: &mut App) {
         KeyBinding::new("cmd-f", Search, Some(CONTEXT)),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-f", Search, Some(CONTEXT)),
+        KeyBinding::new("f8", NextDiagnostic::default(), Some(CONTEXT)),
+        KeyBinding::new("shift-f8", PreviousDiagnostic::default(), Some(CONTEXT)),
     ]);
 
     search::init(cx);
@@ -287,7 +325,7 @@ impl LastLayout {
 pub struct InputState {
     focus_handle: FocusHandle,
     pub(super) mode: InputMode,
-    text: Rope,
+    pub(super) text: Rope,
     paint_epoch: u64,
     pub(super) text_wrapper: TextWrapper,
     pub(super) history: History<Change>,
@@ -332,7 +370,7 @@ pub struct InputState {
     pub(super) placeholder: SharedString,
 
     /// Popover
-    diagnostic_popover: Option<Entity<DiagnosticPopover>>,
+    pub(super) diagnostic_popover: Option<Entity<DiagnosticPopover>>,
     /// Completion/CodeAction context menu
     pub(super) context_menu: Option<ContextMenu>,
     pub(super) mouse_context_menu: Entity<MouseContextMenu>,
@@ -343,6 +381,17 @@ pub struct InputState {
     pub(super) hover_definition: HoverDefinition,
 
     pub lsp: Lsp,
+    /// The provider to check the spelling of words.
+    pub(super) spell_check_provider: Option<Rc<dyn SpellCheckProvider>>,
+    /// The cursors of the remote collaborators.
+    pub(super) remote_cursors: Vec<RemoteCursor>,
+    /// The line ending detected when the text was loaded.
+    pub(super) line_ending: LineEnding,
+    /// Whether to apply the detected indentation to the tab size.
+    pub(super) detect_indent: bool,
+    pub(super) detected_indent: Option<TabSize>,
+    /// The text size in bytes to enable the large file mode.
+    pub(super) large_file_threshold: usize,
 
     /// A flag to indicate if we have a pending update to the text.
     ///
@@ -359,10 +408,12 @@ pub struct InputState {
     _subscriptions: Vec<Subscription>,
 
     pub(super) _context_menu_task: Task<Result<()>>,
+    pub(super) _parse_task: Task<()>,
     pub(super) inline_completion: InlineCompletion,
 }
 
 impl EventEmitter<InputEvent> for InputState {}
+impl EventEmitter<LocalEdit> for InputState {}
 
 impl InputState {
     /// Create a Input state with default [`InputMode::SingleLine`] mode.
@@ -429,6 +480,12 @@ impl InputState {
             mask_pattern: MaskPattern::default(),
             text_align: TextAlign::Left,
             lsp: Lsp::default(),
+            spell_check_provider: None,
+            remote_cursors: vec![],
+            line_ending: LineEnding::default(),
+            detect_indent: false,
+            detected_indent: None,
+            large_file_threshold: LARGE_FILE_THRESHOLD,
             diagnostic_popover: None,
             context_menu: None,
             mouse_context_menu,
@@ -439,6 +496,7 @@ impl InputState {
             size: Size::default(),
             _subscriptions,
             _context_menu_task: Task::ready(Ok(())),
+            _parse_task: Task::ready(()),
             _pending_update: false,
             inline_completion: InlineCompletion::default(),
         }
@@ -624,6 +682,9 @@ impl InputState {
 
     /// Set the text of the input field.
     ///
+    /// The line ending and indentation will be detected,
+    /// see [`Self::line_ending`] and [`Self::detected_indent`].
+    ///
     /// And the selection_range will be reset to 0..0.
     pub fn set_value(
         &mut self,
@@ -635,8 +696,11 @@ impl InputState {
         let was_disabled = self.disabled;
         self.disabled = false;
         let text: SharedString = value.into();
+        self.line_ending = LineEnding::detect(&text);
+        let text = self.line_ending.normalize(&text);
         self.replace_text(text.clone(), window, cx);
         self.text_wrapper.set_default_text(&Rope::from(text.as_str()));
+        self.update_detected_indent();
         self.disabled = was_disabled;
         self.history.ignore = false;
 
@@ -816,7 +880,9 @@ impl InputState {
             "default_value is init-only; use set_value for runtime updates"
         );
         let text: SharedString = value.into();
-        self.text = Rope::from(text.as_str());
+        self.line_ending = LineEnding::detect(&text);
+        self.text = Rope::from(self.line_ending.normalize(&text).as_str());
+        self.update_detected_indent();
         if let Some(diagnostics) = self.mode.diagnostics_mut() {
             diagnostics.reset(&self.text)
         }
@@ -825,9 +891,9 @@ impl InputState {
         self
     }
 
-    /// Return the value of the input field.
+    /// Return the value of the input field, with the original [`Self::line_ending`].
     pub fn value(&self) -> SharedString {
-        SharedString::new(self.text.to_string())
+        SharedString::new(self.line_ending.restore(&self.text.to_string()))
     }
 
     /// Return the value without mask.
@@ -1524,6 +1590,8 @@ impl InputState {
             let mut new_text = clipboard.text().unwrap_or_default();
             if !self.mode.is_multi_line() {
                 new_text = new_text.replace('\n', "");
+            } else if self.line_ending != LineEnding::Mixed {
+                new_text = new_text.replace("\r\n", "\n");
             }
 
             self.replace_text_in_range_silent(None, &new_text, window, cx);
@@ -1720,6 +1788,77 @@ impl InputState {
         cx.notify()
     }
 
+    /// Select the byte range (UTF-8), the offsets are clipped to the char boundaries.
+    ///
+    /// The cursor is moved to the end of the range.
+    pub fn select_range(
+        &mut self,
+        range: Range<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let start = self
+            .text
+            .clip_offset(range.start.min(self.text.len()), Bias::Left);
+        let end = self
+            .text
+            .clip_offset(range.end.min(self.text.len()), Bias::Right)
+            .max(start);
+
+        self.selected_range = (start..end).into();
+        self.selection_reversed = false;
+        self.selected_word_range = None;
+        self.update_preferred_column();
+        self.scroll_to(end, None, cx);
+        self.focus(window, cx);
+        cx.notify();
+    }
+
+    /// Returns the byte offset of the first line in the viewport.
+    pub(crate) fn first_visible_offset(&self, window: &Window) -> usize {
+        let line_height = self
+            .last_layout
+            .as_ref()
+            .map(|layout| layout.line_height)
+            .unwrap_or(window.line_height());
+
+        let scroll_y = -self.scroll_handle.offset().y;
+        let mut offset_y = px(0.);
+        for (row, line) in self.text_wrapper.lines.iter().enumerate() {
+            offset_y += line.height(line_height);
+            if offset_y > scroll_y {
+                return self.text.line_start_offset(row);
+            }
+        }
+
+        self.text.len()
+    }
+
+    /// Scroll to put the line of the `offset` at the top of the viewport.
+    pub(crate) fn scroll_to_line_top(
+        &mut self,
+        offset: usize,
+        window: &Window,
+        cx: &mut Context<Self>,
+    ) {
+        let line_height = self
+            .last_layout
+            .as_ref()
+            .map(|layout| layout.line_height)
+            .unwrap_or(window.line_height());
+
+        let row = self.text.offset_to_point(offset.min(self.text.len())).row;
+        let offset_y = self
+            .text_wrapper
+            .lines
+            .iter()
+            .take(row)
+            .fold(px(0.), |y, line| y + line.height(line_height));
+
+        let scroll_x = self.scroll_handle.offset().x;
+        self.update_scroll_offset(Some(point(scroll_x, -offset_y)), cx);
+    }
+
     #[inline]
     pub(super) fn offset_from_utf16(&self, offset: usize) -> usize {
         self.text.offset_utf16_to_offset(offset)
@@ -2067,15 +2206,20 @@ impl EntityInputHandler for InputState {
             }
         }
 
+        if self.mode.is_single_line() && !self.mask_pattern.is_none() {
+            // The mask may change the entire text.
+            let text = self.text.to_string();
+            self.did_local_edit(&old_text, &(0..old_text.len()), &text, cx);
+        } else {
+            self.did_local_edit(&old_text, &range, new_text, cx);
+        }
         self.push_history(&old_text, &range, &new_text);
         self.history.end_grouping();
         if let Some(diagnostics) = self.mode.diagnostics_mut() {
             diagnostics.reset(&self.text)
         }
-        self.text_wrapper
-            .update(&self.text, &range, &Rope::from(new_text), cx);
-        self.mode
-            .update_highlighter(&range, &self.text, &new_text, true, cx);
+        self.update_text_wrapper(&range, new_text, cx);
+        self.update_highlighter(&range, &new_text, true, cx);
         self.lsp.update(&self.text, window, cx);
         self.selected_range = (new_offset..new_offset).into();
         self.ime_marked_range.take();
@@ -2127,10 +2271,8 @@ impl EntityInputHandler for InputState {
         if let Some(diagnostics) = self.mode.diagnostics_mut() {
             diagnostics.reset(&self.text)
         }
-        self.text_wrapper
-            .update(&self.text, &range, &Rope::from(new_text), cx);
-        self.mode
-            .update_highlighter(&range, &self.text, &new_text, true, cx);
+        self.update_text_wrapper(&range, new_text, cx);
+        self.update_highlighter(&range, &new_text, true, cx);
         self.lsp.update(&self.text, window, cx);
         if new_text.is_empty() {
             // Cancel selection, when cancel IME input.
@@ -2146,6 +2288,7 @@ impl EntityInputHandler for InputState {
                 .into();
         }
         self.mode.update_auto_grow(&self.text_wrapper);
+        self.did_local_edit(&old_text, &range, new_text, cx);
         self.history.start_grouping();
         self.push_history(&old_text, &range, new_text);
         cx.notify();
@@ -2236,8 +2379,7 @@ impl Focusable for InputState {
 impl Render for InputState {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         if self._pending_update {
-            self.mode
-                .update_highlighter(&(0..0), &self.text, "", false, cx);
+            self.update_highlighter(&(0..0), "", false, cx);
             self.lsp.update(&self.text, window, cx);
             self._pending_update = false;
         }
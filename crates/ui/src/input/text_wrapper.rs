@@ -17,6 +17,8 @@ pub(super) struct LineItem {
     ///
     /// Not contains the line end `\n`.
     pub(super) wrapped_lines: Vec<Range<usize>>,
+    /// False if the soft wrap of this line is not calculated yet, see [`TextWrapper::set_lazy`].
+    wrapped: bool,
 }
 
 impl LineItem {
@@ -61,6 +63,8 @@ pub(super) struct TextWrapper {
     pub(super) longest_row: LongestRow,
     /// The lines by split \n
     pub(super) lines: Vec<LineItem>,
+    /// If true, only wrap the lines by [`TextWrapper::wrap_rows`].
+    lazy: bool,
 
     _initialized: bool,
 }
@@ -76,6 +80,7 @@ impl TextWrapper {
             soft_lines: 0,
             longest_row: LongestRow::default(),
             lines: Vec::new(),
+            lazy: false,
             _initialized: false,
         }
     }
@@ -116,6 +121,58 @@ impl TextWrapper {
         self.update_all(&self.text.clone(), cx);
     }
 
+    /// Set to calculate the soft wrap lazily, used for the large text.
+    ///
+    /// When lazy, the changed lines are not wrapped until [`TextWrapper::wrap_rows`] is called,
+    /// the height of them is one line.
+    pub(super) fn set_lazy(&mut self, lazy: bool, cx: &mut App) {
+        if self.lazy == lazy {
+            return;
+        }
+
+        self.lazy = lazy;
+        if !lazy {
+            self.wrap_rows(0..self.lines.len(), cx);
+        }
+    }
+
+    /// Returns true if there are lines not wrapped yet.
+    #[inline]
+    pub(super) fn is_lazy(&self) -> bool {
+        self.lazy && self.wrap_width.is_some()
+    }
+
+    /// Calculate the soft wrap of the lines in the `rows` those are not wrapped yet.
+    ///
+    /// Returns true if any line has been wrapped.
+    pub(super) fn wrap_rows(&mut self, rows: Range<usize>, cx: &mut App) -> bool {
+        let rows = rows.start.min(self.lines.len())..rows.end.min(self.lines.len());
+        if self.lines[rows.clone()].iter().all(|line| line.wrapped) {
+            return false;
+        }
+
+        let mut line_wrapper = cx
+            .text_system()
+            .line_wrapper(self.font.clone(), self.font_size);
+        let wrap_width = self.wrap_width;
+        for line in self.lines[rows].iter_mut().filter(|line| !line.wrapped) {
+            let old_lines_len = line.lines_len();
+            line.wrapped_lines = wrap_line_ranges(
+                &line.line.to_string(),
+                wrap_width,
+                &mut |line_str, wrap_width| {
+                    line_wrapper
+                        .wrap_line(&[LineFragment::text(line_str)], wrap_width)
+                        .collect()
+                },
+            );
+            line.wrapped = true;
+            self.soft_lines = self.soft_lines - old_lines_len + line.lines_len();
+        }
+
+        true
+    }
+
     pub(super) fn prepare_if_need(&mut self, text: &Rope, cx: &mut App) {
         if self._initialized {
             return;
@@ -189,6 +246,7 @@ impl TextWrapper {
 
         let mut new_lines = vec![];
         let wrap_width = self.wrap_width;
+        let lazy = self.is_lazy();
 
         // line not contains `\n`.
         for (ix, line) in Rope::from(changed_text.slice(new_range))
@@ -196,31 +254,22 @@ impl TextWrapper {
             .enumerate()
         {
             let line_str = line.to_string();
-            let mut wrapped_lines = vec![];
-            let mut prev_boundary_ix = 0;
 
             if line_str.len() > longest_row_len {
                 longest_row_ix = new_start_row + ix;
                 longest_row_len = line_str.len();
             }
 
-            // If wrap_width is Pixels::MAX, skip wrapping to disable word wrap
-            if let Some(wrap_width) = wrap_width {
-                // Here only have wrapped line, if there is no wrap meet, the `line_wraps` result will empty.
-                for boundary in wrap_line(&line_str, wrap_width) {
-                    wrapped_lines.push(prev_boundary_ix..boundary.ix);
-                    prev_boundary_ix = boundary.ix;
-                }
-            }
-
-            // Reset of the line
-            if !line_str[prev_boundary_ix..].is_empty() || prev_boundary_ix == 0 {
-                wrapped_lines.push(prev_boundary_ix..line.len());
-            }
+            let (wrapped_lines, wrapped) = if lazy {
+                (vec![0..line_str.len()], false)
+            } else {
+                (wrap_line_ranges(&line_str, wrap_width, &mut *wrap_line), true)
+            };
 
             new_lines.push(LineItem {
                 line: Rope::from(line),
                 wrapped_lines,
+                wrapped,
             });
         }
 
@@ -311,6 +360,37 @@ impl TextWrapper {
     }
 }
 
+/// Returns the soft wrapped lines relative byte ranges of the `line_str`, include the first line.
+///
+/// If `wrap_width` is None, the line is not wrapped.
+fn wrap_line_ranges<F>(
+    line_str: &str,
+    wrap_width: Option<Pixels>,
+    wrap_line: &mut F,
+) -> Vec<Range<usize>>
+where
+    F: FnMut(&str, Pixels) -> Vec<gpui::Boundary>,
+{
+    let mut wrapped_lines = vec![];
+    let mut prev_boundary_ix = 0;
+
+    // If wrap_width is Pixels::MAX, skip wrapping to disable word wrap
+    if let Some(wrap_width) = wrap_width {
+        // Here only have wrapped line, if there is no wrap meet, the `line_wraps` result will empty.
+        for boundary in wrap_line(line_str, wrap_width) {
+            wrapped_lines.push(prev_boundary_ix..boundary.ix);
+            prev_boundary_ix = boundary.ix;
+        }
+    }
+
+    // Reset of the line
+    if !line_str[prev_boundary_ix..].is_empty() || prev_boundary_ix == 0 {
+        wrapped_lines.push(prev_boundary_ix..line_str.len());
+    }
+
+    wrapped_lines
+}
+
 /// The actually display point in the text.
 ///
 /// This is usually used to describe the
@@ -743,21 +823,25 @@ mod tests {
             LineItem {
                 line: Rope::from("Hello, 世界!\r"),
                 wrapped_lines: vec![0..15],
+                wrapped: true,
             },
             // range: 16..36
             LineItem {
                 line: Rope::from("This is second line."),
                 wrapped_lines: vec![0..10, 10..20],
+                wrapped: true,
             },
             // range: 37..56
             LineItem {
                 line: Rope::from("This is third line."),
                 wrapped_lines: vec![0..9, 9..15, 15..20],
+                wrapped: true,
             },
             // range: 57..79
             LineItem {
                 line: Rope::from("这里是第 4 行。"),
                 wrapped_lines: vec![0..22],
+                wrapped: true,
             },
         ];
 
@@ -0,0 +1,359 @@
+//! A list to render the blocks of a document, only the blocks in the visible area are rendered.
+//!
+//! Unlike the [`VirtualList`](crate::VirtualList), the heights of the blocks are not known in advance,
+//! they are measured when the blocks are rendered, and estimated by the average height before that.
+//!
+//! This is used for the TextView that is not `scrollable`, the visible area is clipped by the parents,
+//! e.g. a long changelog in a scrollable panel.
+//!
+//! All the blocks are rendered and sized by the content until the list is clipped by a scrolling parent,
+//! so the list without a scrolling parent (e.g. in the popovers of the editor) is laid out as a plain `div`.
+use std::{cell::RefCell, rc::Rc};
+
+use gpui::{
+    AnyElement, App, AvailableSpace, Bounds, Element, ElementId, GlobalElementId,
+    InspectorElementId, IntoElement, LayoutId, Pixels, Style, Window, point, px, relative, size,
+};
+
+use crate::text::node::{BlockNode, Span};
+
+/// The extra height to render the blocks out of the visible area, to avoid blank when scrolling.
+const OVERDRAW: Pixels = px(200.);
+
+/// The measured heights of the blocks in a [`BlockList`].
+#[derive(Clone, Default)]
+pub(super) struct BlockListState(Rc<RefCell<BlockListStateInner>>);
+
+#[derive(Default)]
+struct BlockListStateInner {
+    items: Vec<BlockHeight>,
+    /// True if the list was clipped by a scrolling parent in the last frame,
+    /// only the visible blocks are rendered.
+    virtualized: bool,
+    /// The width of the list in the last frame, used if the width is sized by the content.
+    last_width: Option<Pixels>,
+}
+
+struct BlockHeight {
+    /// The span of the block, used to check the block is changed.
+    span: Option<Span>,
+    /// The measured height, `None` if the block has not been rendered.
+    height: Option<Pixels>,
+}
+
+impl BlockListState {
+    /// Sync the blocks, keep the measured heights of the blocks that are not changed.
+    pub(super) fn sync(&self, blocks: &[BlockNode]) {
+        let mut state = self.0.borrow_mut();
+        state.items.truncate(blocks.len());
+        for (ix, block) in blocks.iter().enumerate() {
+            let span = block.span();
+            match state.items.get_mut(ix) {
+                Some(item) if item.span == span => {}
+                Some(item) => {
+                    item.span = span;
+                    item.height = None;
+                }
+                None => state.items.push(BlockHeight { span, height: None }),
+            }
+        }
+    }
+}
+
+impl BlockListStateInner {
+    /// The average height of the measured blocks, used for the blocks not rendered yet.
+    fn estimated_height(&self, line_height: Pixels) -> Pixels {
+        let (count, total) = self
+            .items
+            .iter()
+            .filter_map(|item| item.height)
+            .fold((0, px(0.)), |(count, total), height| {
+                (count + 1, total + height)
+            });
+
+        if count == 0 {
+            line_height * 2.
+        } else {
+            total / count as f32
+        }
+    }
+
+    fn content_height(&self, line_height: Pixels) -> Pixels {
+        let estimated_height = self.estimated_height(line_height);
+        self.items
+            .iter()
+            .map(|item| item.height.unwrap_or(estimated_height))
+            .fold(px(0.), |total, height| total + height)
+    }
+
+    /// Save the measured heights, returns true if any height is changed.
+    fn set_heights(&mut self, heights: impl IntoIterator<Item = (usize, Pixels)>) -> bool {
+        let mut changed = false;
+        for (ix, height) in heights {
+            if let Some(item) = self.items.get_mut(ix) {
+                if item.height != Some(height) {
+                    item.height = Some(height);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Create a [`BlockList`] to render the blocks by `render_block`.
+pub(super) fn block_list(
+    id: impl Into<ElementId>,
+    state: &BlockListState,
+    render_block: impl Fn(usize, &mut Window, &mut App) -> AnyElement + 'static,
+) -> BlockList {
+    BlockList {
+        id: id.into(),
+        state: state.clone(),
+        render_block: Box::new(render_block),
+    }
+}
+
+/// The list element to render the visible blocks, see [`block_list`].
+pub(super) struct BlockList {
+    id: ElementId,
+    state: BlockListState,
+    render_block: Box<dyn Fn(usize, &mut Window, &mut App) -> AnyElement>,
+}
+
+/// The blocks rendered in the request layout, if the list is not virtualized.
+pub(super) struct BlockListLayout {
+    items: Vec<(AnyElement, LayoutId)>,
+}
+
+impl IntoElement for BlockList {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl BlockList {
+    /// Render the visible blocks by the measured or estimated heights.
+    fn prepaint_visible_blocks(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Vec<AnyElement> {
+        let visible_bounds = window.content_mask().bounds;
+        let visible_top = visible_bounds.top() - OVERDRAW;
+        let visible_bottom = visible_bounds.bottom() + OVERDRAW;
+
+        let mut visible_blocks = vec![];
+        {
+            let state = self.state.0.borrow();
+            let estimated_height = state.estimated_height(window.line_height());
+            let mut top = bounds.top();
+            for (ix, item) in state.items.iter().enumerate() {
+                if top > visible_bottom {
+                    break;
+                }
+
+                let height = item.height.unwrap_or(estimated_height);
+                if top + height >= visible_top {
+                    visible_blocks.push((ix, top));
+                }
+                top += height;
+            }
+        }
+
+        let available_space = size(
+            AvailableSpace::Definite(bounds.size.width),
+            AvailableSpace::MinContent,
+        );
+        let mut items = Vec::with_capacity(visible_blocks.len());
+        let mut measured_heights = Vec::with_capacity(visible_blocks.len());
+        let mut next_top = None;
+        for (ix, top) in visible_blocks {
+            // Place the blocks one by one, the measured height may differ from the estimated.
+            let top = next_top.unwrap_or(top);
+            let mut item = (self.render_block)(ix, window, cx);
+            let item_size = item.layout_as_root(available_space, window, cx);
+            item.prepaint_at(point(bounds.left(), top), window, cx);
+            items.push(item);
+            measured_heights.push((ix, item_size.height));
+            next_top = Some(top + item_size.height);
+        }
+
+        // Layout again with the measured heights in next frame.
+        if self.state.0.borrow_mut().set_heights(measured_heights) {
+            window.request_animation_frame();
+        }
+
+        items
+    }
+}
+
+impl Element for BlockList {
+    type RequestLayoutState = Option<BlockListLayout>;
+    type PrepaintState = Vec<AnyElement>;
+
+    fn id(&self) -> Option<ElementId> {
+        Some(self.id.clone())
+    }
+
+    fn source_location(&self) -> Option<&'static std::panic::Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _: Option<&GlobalElementId>,
+        _: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let (virtualized, count) = {
+            let state = self.state.0.borrow();
+            (state.virtualized, state.items.len())
+        };
+
+        // Render all the blocks, the list is sized by the content.
+        if !virtualized {
+            let items = (0..count)
+                .map(|ix| {
+                    let mut item = (self.render_block)(ix, window, cx);
+                    let layout_id = item.request_layout(window, cx);
+                    (item, layout_id)
+                })
+                .collect::<Vec<_>>();
+            let layout_id = window.request_layout(
+                Style::default(),
+                items.iter().map(|(_, layout_id)| *layout_id),
+                cx,
+            );
+            return (layout_id, Some(BlockListLayout { items }));
+        }
+
+        let mut style = Style::default();
+        style.size.width = relative(1.).into();
+
+        let state = self.state.clone();
+        let line_height = window.line_height();
+        let layout_id = window.request_measured_layout(
+            style,
+            move |known_dimensions, available_space, _, _| {
+                let state = state.0.borrow();
+                let width = known_dimensions
+                    .width
+                    .unwrap_or(match available_space.width {
+                        AvailableSpace::Definite(width) => width,
+                        AvailableSpace::MinContent | AvailableSpace::MaxContent => {
+                            state.last_width.unwrap_or_default()
+                        }
+                    });
+                let height = known_dimensions
+                    .height
+                    .unwrap_or_else(|| state.content_height(line_height));
+
+                size(width, height)
+            },
+        );
+
+        (layout_id, None)
+    }
+
+    fn prepaint(
+        &mut self,
+        _: Option<&GlobalElementId>,
+        _: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self::PrepaintState {
+        // Virtualize the list if it's clipped by a scrolling parent.
+        let visible_bounds = window.content_mask().bounds;
+        let clipped =
+            bounds.top() < visible_bounds.top() || bounds.bottom() > visible_bounds.bottom();
+        {
+            let mut state = self.state.0.borrow_mut();
+            state.virtualized = clipped;
+            state.last_width = Some(bounds.size.width);
+        }
+
+        let Some(layout) = layout.take() else {
+            return self.prepaint_visible_blocks(bounds, window, cx);
+        };
+
+        let heights = layout
+            .items
+            .iter()
+            .enumerate()
+            .map(|(ix, (_, layout_id))| (ix, window.layout_bounds(*layout_id).size.height))
+            .collect::<Vec<_>>();
+        self.state.0.borrow_mut().set_heights(heights);
+
+        layout
+            .items
+            .into_iter()
+            .map(|(mut item, _)| {
+                item.prepaint(window, cx);
+                item
+            })
+            .collect()
+    }
+
+    fn paint(
+        &mut self,
+        _: Option<&GlobalElementId>,
+        _: Option<&InspectorElementId>,
+        _: Bounds<Pixels>,
+        _: &mut Self::RequestLayoutState,
+        items: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        for item in items.iter_mut() {
+            item.paint(window, cx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::px;
+
+    use super::BlockListState;
+    use crate::{
+        highlighter::HighlightTheme,
+        text::{format, node::NodeContext},
+    };
+
+    #[test]
+    fn test_block_list_state() {
+        let mut cx = NodeContext::default();
+        let theme = HighlightTheme::default_light();
+        let document =
+            format::markdown::parse("# Title\n\nHello\n\nWorld", &mut cx, &theme).unwrap();
+
+        let state = BlockListState::default();
+        state.sync(&document.blocks);
+        {
+            let mut inner = state.0.borrow_mut();
+            assert_eq!(inner.items.len(), 3);
+            assert_eq!(inner.estimated_height(px(20.)), px(40.));
+            assert_eq!(inner.content_height(px(20.)), px(120.));
+
+            inner.items[0].height = Some(px(30.));
+            inner.items[1].height = Some(px(10.));
+            assert_eq!(inner.estimated_height(px(20.)), px(20.));
+            assert_eq!(inner.content_height(px(20.)), px(60.));
+        }
+
+        // Keep the heights of the unchanged blocks.
+        let document = format::markdown::parse("# Title\n\nHello world", &mut cx, &theme).unwrap();
+        state.sync(&document.blocks);
+        let inner = state.0.borrow();
+        assert_eq!(inner.items.len(), 2);
+        assert_eq!(inner.items[0].height, Some(px(30.)));
+        assert_eq!(inner.items[1].height, None);
+    }
+}
@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+
+use gpui::{App, ClipboardEntry, ClipboardItem};
+
+use crate::text::TextViewClipboardMetadata;
+
+thread_local! {
+    /// The system clipboard is kept alive, on Linux the clipboard content is served by the owner.
+    static SYSTEM_CLIPBOARD: RefCell<Option<arboard::Clipboard>> = const { RefCell::new(None) };
+    /// The last copy written to the system clipboard, with the formats it can't carry.
+    static LAST_COPY: RefCell<Option<(String, TextViewClipboardMetadata)>> =
+        const { RefCell::new(None) };
+}
+
+/// Write the `html` to the system clipboard, with the `text` as the plain text alternative.
+///
+/// The HTML is written as `public.html` on macOS, `CF_HTML` on Windows and `text/html` on Linux.
+/// Returns false if the system clipboard is not available.
+fn write_system_html(html: &str, text: &str) -> bool {
+    // Don't touch the system clipboard in the unit tests.
+    if cfg!(test) {
+        return false;
+    }
+
+    SYSTEM_CLIPBOARD.with_borrow_mut(|clipboard| {
+        let clipboard = match clipboard {
+            Some(clipboard) => clipboard,
+            None => match arboard::Clipboard::new() {
+                Ok(new_clipboard) => clipboard.insert(new_clipboard),
+                Err(err) => {
+                    tracing::warn!("failed to open the system clipboard: {}", err);
+                    return false;
+                }
+            },
+        };
+
+        match clipboard.set().html(html, Some(text)) {
+            Ok(_) => true,
+            Err(err) => {
+                tracing::warn!("failed to write HTML to the system clipboard: {}", err);
+                false
+            }
+        }
+    })
+}
+
+/// Write the selection to the clipboard as plain text and HTML.
+///
+/// The [`TextViewClipboardMetadata`] is kept for [`TextViewClipboardMetadata::read`].
+/// If the system clipboard is not available, the plain text is written with the metadata by GPUI.
+pub(super) fn write_selection(text: String, metadata: TextViewClipboardMetadata, cx: &mut App) {
+    if write_system_html(&metadata.html, &text) {
+        LAST_COPY.set(Some((text, metadata)));
+        return;
+    }
+
+    LAST_COPY.set(None);
+    cx.write_to_clipboard(ClipboardItem::new_string_with_json_metadata(text, metadata));
+}
+
+/// Write the `html` to the clipboard, with the markup as the plain text alternative.
+///
+/// So the formatted content is pasted in the rich text editors, and the markup in the plain text editors.
+/// If the system clipboard is not available, only the markup is written as plain text.
+pub(super) fn write_html(html: String, cx: &mut App) {
+    LAST_COPY.set(None);
+    if write_system_html(&html, &html) {
+        return;
+    }
+
+    cx.write_to_clipboard(ClipboardItem::new_string(html));
+}
+
+impl TextViewClipboardMetadata {
+    /// Read the metadata of the selection copied from a [`crate::text::TextView`] in this app,
+    /// returns None if the clipboard content is not copied by the `Copy` of a TextView.
+    pub fn read(cx: &App) -> Option<Self> {
+        let item = cx.read_from_clipboard()?;
+        let entry = item.entries().iter().find_map(|entry| match entry {
+            ClipboardEntry::String(entry) => Some(entry),
+            _ => None,
+        })?;
+        if let Some(metadata) = entry.metadata_json::<Self>() {
+            return Some(metadata);
+        }
+
+        LAST_COPY.with_borrow(|last_copy| {
+            last_copy
+                .as_ref()
+                .filter(|(text, _)| text == entry.text())
+                .map(|(_, metadata)| metadata.clone())
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use std::str::FromStr;
+
+use gpui::SharedString;
+
+/// The value of an attribute in the MDX JSX element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentAttrValue {
+    /// The quoted value, e.g. `kind="warning"`.
+    Literal(SharedString),
+    /// The source of the expression value, e.g. `3` for `count={3}`.
+    Expression(SharedString),
+}
+
+impl ComponentAttrValue {
+    /// Returns the literal value or the source of the expression.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Literal(value) | Self::Expression(value) => value.as_str(),
+        }
+    }
+}
+
+/// The name and attributes of the MDX JSX element, e.g. `<Alert kind="warning">`.
+///
+/// See [`TextViewStyle::component`].
+///
+/// [`TextViewStyle::component`]: crate::text::TextViewStyle::component
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentProps {
+    /// The name of the element, e.g. `Alert`, empty for the fragment `<>`.
+    pub name: SharedString,
+    /// The attributes in the order of the source, the value is `None` for the boolean attribute like `<Tag outline>`.
+    pub attrs: Vec<(SharedString, Option<ComponentAttrValue>)>,
+}
+
+impl ComponentProps {
+    /// Returns true if the element has the attribute `name`.
+    pub fn has(&self, name: &str) -> bool {
+        self.attrs.iter().any(|(key, _)| key.as_str() == name)
+    }
+
+    /// Returns the value of the attribute `name`, the expression is returned as the source.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.as_str() == name)
+            .and_then(|(_, value)| value.as_ref())
+            .map(|value| value.as_str().trim())
+    }
+
+    /// Parse the value of the attribute `name`, e.g. `count={3}` as a number.
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name)?.parse().ok()
+    }
+
+    /// Returns the name with the attributes as written in the source, e.g. `Badge count={3} dot`.
+    pub(crate) fn to_tag(&self) -> String {
+        let mut tag = self.name.to_string();
+        for (name, value) in self.attrs.iter() {
+            match value {
+                Some(ComponentAttrValue::Literal(value)) => {
+                    tag.push_str(&format!(" {}=\"{}\"", name, value))
+                }
+                Some(ComponentAttrValue::Expression(value)) => {
+                    tag.push_str(&format!(" {}={{{}}}", name, value))
+                }
+                None => tag.push_str(&format!(" {}", name)),
+            }
+        }
+        tag
+    }
+
+    /// Returns true if the attribute `name` is set without value, or the value is `true`.
+    pub fn flag(&self, name: &str) -> bool {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.as_str() == name)
+            .is_some_and(|(_, value)| match value {
+                None => true,
+                Some(value) => value.as_str().trim() == "true",
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentAttrValue, ComponentProps};
+
+    #[test]
+    fn test_component_props() {
+        let props = ComponentProps {
+            name: "Badge".into(),
+            attrs: vec![
+                (
+                    "kind".into(),
+                    Some(ComponentAttrValue::Literal("warning".into())),
+                ),
+                (
+                    "count".into(),
+                    Some(ComponentAttrValue::Expression(" 3 ".into())),
+                ),
+                ("dot".into(), None),
+                (
+                    "outline".into(),
+                    Some(ComponentAttrValue::Expression("false".into())),
+                ),
+            ],
+        };
+
+        assert_eq!(props.get("kind"), Some("warning"));
+        assert_eq!(props.parse::<usize>("count"), Some(3));
+        assert_eq!(props.parse::<usize>("kind"), None);
+        assert!(props.has("dot"));
+        assert!(props.flag("dot"));
+        assert!(!props.flag("outline"));
+        assert!(!props.flag("missing"));
+        assert_eq!(props.get("dot"), None);
+    }
+}
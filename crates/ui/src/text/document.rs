@@ -1,17 +1,47 @@
+use std::{
+    collections::HashMap,
+    ops::{Range, RangeInclusive},
+    sync::Arc,
+};
+
 use gpui::{
-    App, InteractiveElement as _, IntoElement, ListState, ParentElement as _, SharedString,
-    Styled as _, Window, div,
+    AnyElement, App, InteractiveElement as _, IntoElement, ListState, ParentElement as _,
+    SharedString, Styled as _, Window, div, prelude::FluentBuilder as _,
 };
 
-use crate::text::node::{BlockNode, NodeContext};
+use crate::{
+    ElementExt as _,
+    text::{
+        FrontMatter,
+        block_list::{BlockListState, block_list},
+        node::{BlockNode, NodeContext, Span, Table},
+    },
+};
 
 /// The parsed document AST.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub(crate) struct ParsedDocument {
-    pub(crate) source: SharedString,
+    pub(crate) source: String,
     pub(crate) blocks: Vec<BlockNode>,
 }
 
+/// The item of the table of contents, see [`TextViewState::table_of_contents`].
+///
+/// [`TextViewState::table_of_contents`]: crate::text::TextViewState::table_of_contents
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocItem {
+    /// The heading level, 1 to 6.
+    pub level: u8,
+    /// The plain text of the heading.
+    pub title: SharedString,
+    /// The anchor slug of the heading, used for [`TextViewState::scroll_to_anchor`].
+    ///
+    /// [`TextViewState::scroll_to_anchor`]: crate::text::TextViewState::scroll_to_anchor
+    pub slug: SharedString,
+    /// The sub headings with a greater level.
+    pub children: Vec<TocItem>,
+}
+
 #[derive(Default, Clone, Copy)]
 pub(crate) struct NodeRenderOptions {
     pub(crate) ix: usize,
@@ -30,14 +60,152 @@ impl NodeRenderOptions {
 }
 
 impl ParsedDocument {
-    pub(super) fn selected_text(&self) -> String {
+    /// Returns the selected text in the blocks of the `range`.
+    ///
+    /// The blocks between the first and the last are fully selected,
+    /// they may be not rendered if they are out of the viewport.
+    pub(super) fn selected_text(&self, range: RangeInclusive<usize>) -> String {
+        let (first, last) = (*range.start(), *range.end());
         let mut text = String::new();
-        for block in self.blocks.iter() {
-            text.push_str(&block.selected_text());
+        for (ix, block) in self.blocks.iter().enumerate().take(last + 1).skip(first) {
+            text.push_str(&block.selected_text(ix > first && ix < last));
         }
         text
     }
 
+    /// Returns the front matter, which is always the first block.
+    pub(super) fn front_matter(&self) -> Option<&FrontMatter> {
+        match self.blocks.first() {
+            Some(BlockNode::FrontMatter { front_matter, .. }) => Some(front_matter),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the top-level block of the `anchor`, see [`BlockNode::has_anchor`].
+    pub(super) fn anchor_block(&self, anchor: &str) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.has_anchor(anchor))
+    }
+
+    /// Returns the table at the `span`, including the tables nested in the blocks.
+    pub(super) fn table(&self, span: Span) -> Option<&Table> {
+        fn find(blocks: &[BlockNode], span: Span) -> Option<&Table> {
+            blocks.iter().find_map(|block| match block {
+                BlockNode::Table(table) if table.span == Some(span) => Some(table),
+                _ => find(block.children(), span),
+            })
+        }
+
+        find(&self.blocks, span)
+    }
+
+    /// Returns the index of the last top-level block starts at or before the source byte `offset`.
+    ///
+    /// The footnotes are moved to the end, so the blocks are not ordered by the offset.
+    pub(super) fn block_at_offset(&self, offset: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, block)| Some((ix, block.span()?.start)))
+            .filter(|(_, start)| *start <= offset)
+            .max_by_key(|(_, start)| *start)
+            .map(|(ix, _)| ix)
+    }
+
+    /// Generate the unique slugs of the headings.
+    pub(super) fn update_heading_slugs(&mut self) {
+        self.update_heading_slugs_in(0..self.blocks.len(), &mut HashMap::new());
+    }
+
+    /// Generate the unique slugs of the headings in the blocks of the `range`,
+    /// the `counts` are the slugs in the blocks before, and updated with the slugs in the range.
+    pub(super) fn update_heading_slugs_in(
+        &mut self,
+        range: Range<usize>,
+        counts: &mut HashMap<String, usize>,
+    ) {
+        for block in self.blocks[range].iter_mut() {
+            block.update_heading_slugs(counts);
+        }
+    }
+
+    /// Returns the table of contents, the headings are nested by the level.
+    pub(super) fn table_of_contents(&self) -> Vec<TocItem> {
+        fn collect(items: &mut Vec<TocItem>, block: &BlockNode) {
+            match block {
+                BlockNode::Heading {
+                    level,
+                    children,
+                    slug,
+                    ..
+                } => push_toc_item(
+                    items,
+                    TocItem {
+                        level: *level,
+                        title: children.text().into(),
+                        slug: slug.clone(),
+                        children: vec![],
+                    },
+                ),
+                _ => {
+                    for child in block.children() {
+                        collect(items, child);
+                    }
+                }
+            }
+        }
+
+        let mut items = vec![];
+        for block in self.blocks.iter() {
+            collect(&mut items, block);
+        }
+        items
+    }
+
+    /// Returns the slug of the last top-level heading at or before the block `ix`.
+    pub(super) fn heading_before(&self, ix: usize) -> Option<SharedString> {
+        self.blocks
+            .iter()
+            .take(ix + 1)
+            .rev()
+            .find_map(|block| match block {
+                BlockNode::Heading { slug, .. } => Some(slug.clone()),
+                _ => None,
+            })
+    }
+
+    /// Returns the index of the first block of the trailing footnote section.
+    pub(super) fn footnote_section_start(&self) -> usize {
+        self.blocks
+            .iter()
+            .rposition(|block| !matches!(block, BlockNode::Footnote { .. }))
+            .map_or(0, |ix| ix + 1)
+    }
+
+    /// Move the footnote definitions in the blocks from `start` into the trailing section
+    /// with the `footnotes`, sorted by the number like GitHub.
+    pub(super) fn update_footnote_section(&mut self, start: usize, mut footnotes: Vec<BlockNode>) {
+        for block in self.blocks.split_off(start) {
+            if matches!(block, BlockNode::Footnote { .. }) {
+                footnotes.push(block);
+            } else {
+                self.blocks.push(block);
+            }
+        }
+
+        footnotes.sort_by_key(|block| match block {
+            BlockNode::Footnote { number, .. } => *number,
+            _ => 0,
+        });
+        for (ix, block) in footnotes.iter_mut().enumerate() {
+            if let BlockNode::Footnote { section_start, .. } = block {
+                *section_start = ix == 0;
+            }
+        }
+        self.blocks.extend(footnotes);
+    }
+
     /// Converts the node to markdown format.
     ///
     /// This is used to generate markdown for test.
@@ -51,52 +219,76 @@ impl ParsedDocument {
     }
 
     pub(super) fn render_root(
-        &self,
+        self: Arc<Self>,
         list_state: Option<ListState>,
+        block_list_state: &BlockListState,
         node_cx: &NodeContext,
-        window: &mut Window,
-        cx: &mut App,
     ) -> impl IntoElement {
-        let options = NodeRenderOptions {
-            is_last: true,
-            ..Default::default()
-        };
-
         let Some(list_state) = list_state else {
+            block_list_state.sync(&self.blocks);
             return div()
                 .id("document")
-                .children(self.blocks.iter().enumerate().map(move |(ix, node)| {
-                    node.render_block(NodeRenderOptions { ix, ..options }, node_cx, window, cx)
+                .child(block_list("blocks", block_list_state, {
+                    let node_cx = node_cx.clone();
+                    move |ix, window, cx| self.render_block(ix, true, &node_cx, window, cx)
                 }));
         };
 
-        let blocks = &self.blocks;
-
-        if list_state.item_count() != blocks.len() {
-            list_state.reset(blocks.len());
+        // Only re-measure the changed blocks, the last block may be changed by streaming.
+        let old_count = list_state.item_count();
+        let new_count = self.blocks.len();
+        if old_count != new_count {
+            let start = old_count.saturating_sub(1).min(new_count);
+            list_state.splice(start..old_count, new_count - start);
         }
 
         div().id("document").size_full().child(
             gpui::list(list_state, {
                 let node_cx = node_cx.clone();
-                let blocks = blocks.clone();
                 move |ix, window, cx| {
-                    let is_last = ix + 1 == blocks.len();
-                    blocks[ix]
-                        .render_block(
-                            NodeRenderOptions {
-                                ix,
-                                is_last,
-                                ..options
-                            },
-                            &node_cx,
-                            window,
-                            cx,
-                        )
-                        .into_any_element()
+                    let is_last = ix + 1 == self.blocks.len();
+                    self.render_block(ix, is_last, &node_cx, window, cx)
                 }
             })
             .size_full(),
         )
     }
+
+    /// Render the top-level block, and save the bounds of it for the selection.
+    fn render_block(
+        &self,
+        ix: usize,
+        is_last: bool,
+        node_cx: &NodeContext,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> AnyElement {
+        let options = NodeRenderOptions {
+            ix,
+            is_last,
+            ..Default::default()
+        };
+
+        div()
+            .child(self.blocks[ix].render_block(options, node_cx, window, cx))
+            .when_some(node_cx.state.clone(), |this, state| {
+                this.on_prepaint(move |bounds, _, cx| {
+                    _ = state.update(cx, |state, _| {
+                        state.update_block_bounds(ix, bounds);
+                    });
+                })
+            })
+            .into_any_element()
+    }
+}
+
+/// Push the `item` as the child of the last item if it has a greater level.
+fn push_toc_item(items: &mut Vec<TocItem>, item: TocItem) {
+    if let Some(last) = items.last_mut() {
+        if item.level > last.level {
+            return push_toc_item(&mut last.children, item);
+        }
+    }
+
+    items.push(item);
 }
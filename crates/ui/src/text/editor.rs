@@ -0,0 +1,654 @@
+use std::{ops::Range, rc::Rc};
+
+use anyhow::Result;
+use gpui::{
+    App, AppContext as _, ClipboardEntry, Context, Entity, Image, InteractiveElement as _,
+    IntoElement, KeyBinding, ParentElement as _, Pixels, RenderOnce, SharedString, StyleRefinement,
+    Styled, Subscription, Task, Window, actions, div,
+};
+
+use crate::{
+    ActiveTheme as _, IconName, Sizable as _, StyledExt as _,
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    input::{Input, InputEvent, InputState, Paste},
+    text::{TextView, TextViewEvent, TextViewState, TextViewStyle},
+    v_flex,
+};
+
+const CONTEXT: &'static str = "MarkdownEditor";
+
+actions!(
+    markdown_editor,
+    [Bold, Italic, Link, BulletList, Code, InsertTable]
+);
+
+pub(super) fn init(cx: &mut App) {
+    cx.bind_keys(vec![
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-b", Bold, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-b", Bold, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-i", Italic, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-i", Italic, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-k", Link, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-k", Link, Some(CONTEXT)),
+    ]);
+}
+
+/// The handler of the pasted image, returns the URL of the saved or uploaded image.
+pub type PasteImageFn = dyn Fn(Image, &mut Window, &mut App) -> Task<Result<SharedString>>;
+
+/// The Markdown formats of the [`MarkdownEditor`] toolbar, applied to the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkdownFormat {
+    /// Toggle `**bold**`.
+    Bold,
+    /// Toggle `_italic_`.
+    Italic,
+    /// Insert `[text](url)`, the selection is used as the text, or the url if it's a URL.
+    Link,
+    /// Toggle `- ` of the selected lines.
+    BulletList,
+    /// Toggle `` `code` ``, the multi-line selection is wrapped in a code block.
+    Code,
+    /// Insert a table after the selection.
+    Table,
+}
+
+/// The edit to apply a [`MarkdownFormat`], replace the `range` with the `text` and then select the `selection`.
+#[derive(Debug, Clone, PartialEq)]
+struct FormatEdit {
+    range: Range<usize>,
+    text: String,
+    selection: Range<usize>,
+}
+
+impl MarkdownFormat {
+    /// Returns the edit to apply the format to the `selection` of the `source`.
+    fn edit(&self, source: &str, selection: Range<usize>) -> FormatEdit {
+        let selected = &source[selection.clone()];
+        match self {
+            Self::Bold => toggle_wrap(source, selection, "**", "bold"),
+            Self::Italic => toggle_wrap(source, selection, "_", "italic"),
+            Self::Code if selected.contains('\n') => {
+                let prefix = if is_line_start(source, selection.start) {
+                    ""
+                } else {
+                    "\n"
+                };
+                let code = selected.trim_end_matches('\n');
+                let start = selection.start + prefix.len() + "```\n".len();
+                FormatEdit {
+                    text: format!("{}```\n{}\n```", prefix, code),
+                    selection: start..start + code.len(),
+                    range: selection,
+                }
+            }
+            Self::Code => toggle_wrap(source, selection, "`", "code"),
+            Self::Link => {
+                let is_url = selected.starts_with("http://") || selected.starts_with("https://");
+                let (text, url) = match selected {
+                    "" => ("text", "url"),
+                    url if is_url => ("text", url),
+                    text => (text, "url"),
+                };
+                let is_text_placeholder = selected.is_empty() || is_url;
+                let text_start = selection.start + 1;
+                let url_start = text_start + text.len() + "](".len();
+                FormatEdit {
+                    text: format!("[{}]({})", text, url),
+                    // Select the placeholder to type over.
+                    selection: if is_text_placeholder {
+                        text_start..text_start + text.len()
+                    } else {
+                        url_start..url_start + url.len()
+                    },
+                    range: selection,
+                }
+            }
+            Self::BulletList => toggle_bullet_list(source, selection),
+            Self::Table => {
+                let offset = selection.end;
+                let prefix = if offset == 0 || source[..offset].ends_with("\n\n") {
+                    ""
+                } else if source[..offset].ends_with('\n') {
+                    "\n"
+                } else {
+                    "\n\n"
+                };
+                let suffix = if source[offset..].starts_with('\n') || offset == source.len() {
+                    ""
+                } else {
+                    "\n"
+                };
+                let start = offset + prefix.len() + "| ".len();
+                FormatEdit {
+                    text: format!(
+                        "{}| Column 1 | Column 2 |\n| --- | --- |\n| Cell | Cell |\n{}",
+                        prefix, suffix
+                    ),
+                    selection: start..start + "Column 1".len(),
+                    range: offset..offset,
+                }
+            }
+        }
+    }
+}
+
+fn is_line_start(source: &str, offset: usize) -> bool {
+    offset == 0 || source[..offset].ends_with('\n')
+}
+
+/// Wrap the selection with the `marker`, or unwrap if it's already wrapped, e.g. `**|bold|**`.
+fn toggle_wrap(
+    source: &str,
+    selection: Range<usize>,
+    marker: &str,
+    placeholder: &str,
+) -> FormatEdit {
+    let selected = &source[selection.clone()];
+    let len = marker.len();
+    if source[..selection.start].ends_with(marker) && source[selection.end..].starts_with(marker) {
+        return FormatEdit {
+            range: selection.start - len..selection.end + len,
+            text: selected.to_string(),
+            selection: selection.start - len..selection.end - len,
+        };
+    }
+
+    let text = if selected.is_empty() {
+        placeholder
+    } else {
+        selected
+    };
+    let start = selection.start + len;
+    FormatEdit {
+        text: format!("{}{}{}", marker, text, marker),
+        selection: start..start + text.len(),
+        range: selection,
+    }
+}
+
+/// Add the `- ` to the selected lines, or remove if all the lines are already list items.
+fn toggle_bullet_list(source: &str, selection: Range<usize>) -> FormatEdit {
+    let start = source[..selection.start]
+        .rfind('\n')
+        .map(|ix| ix + 1)
+        .unwrap_or(0);
+    // The line after the selection end at the line start is not selected.
+    let end = if selection.end > selection.start && is_line_start(source, selection.end) {
+        selection.end - 1
+    } else {
+        selection.end
+            + source[selection.end..]
+                .find('\n')
+                .unwrap_or(source.len() - selection.end)
+    };
+
+    let lines = source[start..end].split('\n').collect::<Vec<_>>();
+    let is_list = lines
+        .iter()
+        .all(|line| line.is_empty() || line.starts_with("- "));
+    let text = lines
+        .iter()
+        .map(|line| match line.strip_prefix("- ") {
+            Some(line) if is_list => line.to_string(),
+            _ if line.is_empty() && lines.len() > 1 => String::new(),
+            _ => format!("- {}", line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let new_end = start + text.len();
+    FormatEdit {
+        selection: if selection.is_empty() {
+            new_end..new_end
+        } else {
+            start..new_end
+        },
+        text,
+        range: start..end,
+    }
+}
+
+/// The state of the [`MarkdownEditor`], a Markdown code editor with the live preview.
+pub struct MarkdownEditorState {
+    input: Entity<InputState>,
+    preview: Entity<TextViewState>,
+    on_paste_image: Option<Rc<PasteImageFn>>,
+    /// The first visible offset of the editor in the last scroll sync, to avoid syncing back.
+    synced_offset: usize,
+    /// The scroll offset of the editor in the last scroll sync, the editor is notified on every
+    /// cursor blink, so only sync when it's scrolled.
+    synced_scroll_y: Option<Pixels>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl MarkdownEditorState {
+    /// Create a new MarkdownEditorState with the empty source, see [`MarkdownEditorState::set_value`].
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .code_editor("markdown")
+                .soft_wrap(true)
+        });
+        let preview = cx.new(|cx| {
+            TextViewState::markdown("", cx)
+                .scrollable(true)
+                .selectable(true)
+        });
+
+        let _subscriptions = vec![
+            cx.subscribe_in(&input, window, |this, input, event: &InputEvent, _, cx| {
+                if let InputEvent::Change = event {
+                    // The internal text with LF, to keep the offsets of the preview same as the editor.
+                    let text = input.read(cx).text().to_string();
+                    // The lines may be changed without scrolling.
+                    this.synced_scroll_y = None;
+                    this.preview.update(cx, |preview, cx| {
+                        preview.set_text(&text, cx);
+                    });
+                }
+            }),
+            cx.observe_in(&input, window, |this, _, window, cx| {
+                this.sync_preview_scroll(window, cx);
+            }),
+            cx.subscribe_in(
+                &preview,
+                window,
+                |this, _, event: &TextViewEvent, window, cx| match event {
+                    TextViewEvent::Scrolled { offset } => {
+                        this.sync_editor_scroll(*offset, window, cx);
+                    }
+                    TextViewEvent::TaskToggled { range, checked } => {
+                        let marker = if *checked { "[x]" } else { "[ ]" };
+                        this.input.update(cx, |input, cx| {
+                            input.select_range(range.clone(), window, cx);
+                            input.replace(marker, window, cx);
+                        });
+                    }
+                    _ => {}
+                },
+            ),
+        ];
+
+        Self {
+            input,
+            preview,
+            on_paste_image: None,
+            synced_offset: 0,
+            synced_scroll_y: None,
+            _subscriptions,
+        }
+    }
+
+    /// Set the handler of the pasted image, the returned URL is inserted as `![](url)` at the cursor.
+    ///
+    /// Without the handler, only the pasted text is inserted.
+    pub fn on_paste_image<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Image, &mut Window, &mut App) -> Task<Result<SharedString>> + 'static,
+    {
+        self.on_paste_image = Some(Rc::new(f));
+        self
+    }
+
+    /// Returns the state of the source editor.
+    pub fn input(&self) -> &Entity<InputState> {
+        &self.input
+    }
+
+    /// Returns the state of the preview.
+    pub fn preview(&self) -> &Entity<TextViewState> {
+        &self.preview
+    }
+
+    /// Returns the Markdown source.
+    pub fn value(&self, cx: &App) -> SharedString {
+        self.input.read(cx).value()
+    }
+
+    /// Set the Markdown source, the preview is updated as well.
+    pub fn set_value(
+        &mut self,
+        value: impl Into<SharedString>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.input.update(cx, |input, cx| {
+            input.set_value(value, window, cx);
+        });
+    }
+
+    /// Apply the `format` to the selection of the editor.
+    pub fn apply_format(
+        &mut self,
+        format: MarkdownFormat,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.input.update(cx, |input, cx| {
+            let edit = format.edit(&input.text().to_string(), input.selected_range());
+            input.select_range(edit.range, window, cx);
+            input.replace(edit.text, window, cx);
+            input.select_range(edit.selection, window, cx);
+        });
+    }
+
+    /// Scroll the preview to the block at the top of the editor.
+    fn sync_preview_scroll(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let scroll_y = self.input.read(cx).scroll_handle.offset().y;
+        if self.synced_scroll_y == Some(scroll_y) {
+            return;
+        }
+
+        self.synced_scroll_y = Some(scroll_y);
+        let offset = self.input.read(cx).first_visible_offset(window);
+        if offset == self.synced_offset {
+            return;
+        }
+
+        self.synced_offset = offset;
+        self.preview.update(cx, |preview, cx| {
+            preview.scroll_to_offset(offset, cx);
+        });
+    }
+
+    /// Scroll the editor to the block at the top of the preview, the `offset` is the start of the block.
+    fn sync_editor_scroll(&mut self, offset: usize, window: &mut Window, cx: &mut Context<Self>) {
+        // Keep the editor if it's already in the block.
+        let block_start = self.preview.read(cx).block_start(self.synced_offset);
+        if block_start == Some(offset) {
+            return;
+        }
+
+        self.input.update(cx, |input, cx| {
+            input.scroll_to_line_top(offset, window, cx);
+        });
+        let input = self.input.read(cx);
+        self.synced_scroll_y = Some(input.scroll_handle.offset().y);
+        self.synced_offset = input.first_visible_offset(window);
+    }
+
+    fn on_action_paste(&mut self, _: &Paste, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(on_paste_image) = self.on_paste_image.clone() else {
+            return;
+        };
+        let Some(image) = cx.read_from_clipboard().and_then(|item| {
+            item.entries().iter().find_map(|entry| match entry {
+                ClipboardEntry::Image(image) => Some(image.clone()),
+                _ => None,
+            })
+        }) else {
+            return;
+        };
+
+        // Handled here, not paste the text of the image into the editor.
+        cx.stop_propagation();
+        let task = on_paste_image(image, window, cx);
+        let input = self.input.clone();
+        cx.spawn_in(window, async move |_, cx| {
+            let url = match task.await {
+                Ok(url) => url,
+                Err(err) => {
+                    tracing::error!("failed to save the pasted image: {:?}", err);
+                    return;
+                }
+            };
+
+            _ = input.update_in(cx, |input, window, cx| {
+                input.insert(format!("![]({})", url), window, cx);
+            });
+        })
+        .detach();
+    }
+
+    fn on_action_bold(&mut self, _: &Bold, window: &mut Window, cx: &mut Context<Self>) {
+        self.apply_format(MarkdownFormat::Bold, window, cx);
+    }
+
+    fn on_action_italic(&mut self, _: &Italic, window: &mut Window, cx: &mut Context<Self>) {
+        self.apply_format(MarkdownFormat::Italic, window, cx);
+    }
+
+    fn on_action_link(&mut self, _: &Link, window: &mut Window, cx: &mut Context<Self>) {
+        self.apply_format(MarkdownFormat::Link, window, cx);
+    }
+
+    fn on_action_bullet_list(
+        &mut self,
+        _: &BulletList,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_format(MarkdownFormat::BulletList, window, cx);
+    }
+
+    fn on_action_code(&mut self, _: &Code, window: &mut Window, cx: &mut Context<Self>) {
+        self.apply_format(MarkdownFormat::Code, window, cx);
+    }
+
+    fn on_action_insert_table(
+        &mut self,
+        _: &InsertTable,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.apply_format(MarkdownFormat::Table, window, cx);
+    }
+}
+
+/// A Markdown editor with the toolbar and the live preview, the scroll positions are synced by the source blocks.
+#[derive(IntoElement)]
+pub struct MarkdownEditor {
+    state: Entity<MarkdownEditorState>,
+    text_view_style: TextViewStyle,
+    style: StyleRefinement,
+}
+
+impl MarkdownEditor {
+    pub fn new(state: &Entity<MarkdownEditorState>) -> Self {
+        Self {
+            state: state.clone(),
+            text_view_style: TextViewStyle::default(),
+            style: StyleRefinement::default(),
+        }
+    }
+
+    /// Set the [`TextViewStyle`] of the preview.
+    pub fn text_view_style(mut self, style: TextViewStyle) -> Self {
+        self.text_view_style = style;
+        self
+    }
+
+    fn render_toolbar(&self, _: &mut Window, cx: &mut App) -> impl IntoElement {
+        let items = [
+            ("bold", "B", "Bold", MarkdownFormat::Bold),
+            ("italic", "I", "Italic", MarkdownFormat::Italic),
+            ("code", "<>", "Code", MarkdownFormat::Code),
+        ];
+
+        h_flex()
+            .gap_1()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .children(items.into_iter().map(|(id, label, tooltip, format)| {
+                let state = self.state.clone();
+                Button::new(id)
+                    .xsmall()
+                    .ghost()
+                    .label(label)
+                    .tooltip(tooltip)
+                    .on_click(move |_, window, cx| {
+                        state.update(cx, |state, cx| state.apply_format(format, window, cx));
+                    })
+            }))
+            .children(
+                [
+                    ("link", IconName::ExternalLink, "Link", MarkdownFormat::Link),
+                    ("list", IconName::Menu, "List", MarkdownFormat::BulletList),
+                    (
+                        "table",
+                        IconName::LayoutDashboard,
+                        "Table",
+                        MarkdownFormat::Table,
+                    ),
+                ]
+                .into_iter()
+                .map(|(id, icon, tooltip, format)| {
+                    let state = self.state.clone();
+                    Button::new(id)
+                        .xsmall()
+                        .ghost()
+                        .icon(icon)
+                        .tooltip(tooltip)
+                        .on_click(move |_, window, cx| {
+                            state.update(cx, |state, cx| state.apply_format(format, window, cx));
+                        })
+                }),
+            )
+    }
+}
+
+impl Styled for MarkdownEditor {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl RenderOnce for MarkdownEditor {
+    fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let state = self.state.read(cx);
+        let input = state.input.clone();
+        let preview = state.preview.clone();
+
+        v_flex()
+            .key_context(CONTEXT)
+            .size_full()
+            .overflow_hidden()
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .on_action(window.listener_for(&self.state, MarkdownEditorState::on_action_bold))
+            .on_action(window.listener_for(&self.state, MarkdownEditorState::on_action_italic))
+            .on_action(window.listener_for(&self.state, MarkdownEditorState::on_action_link))
+            .on_action(window.listener_for(&self.state, MarkdownEditorState::on_action_bullet_list))
+            .on_action(window.listener_for(&self.state, MarkdownEditorState::on_action_code))
+            .on_action(
+                window.listener_for(&self.state, MarkdownEditorState::on_action_insert_table),
+            )
+            .child(self.render_toolbar(window, cx))
+            .child(
+                h_flex()
+                    .flex_1()
+                    .min_h_0()
+                    .items_start()
+                    .child(
+                        div()
+                            .flex_1()
+                            .h_full()
+                            .min_w_0()
+                            .border_r_1()
+                            .border_color(cx.theme().border)
+                            // Capture to handle the pasted image before the editor.
+                            .capture_action(
+                                window.listener_for(
+                                    &self.state,
+                                    MarkdownEditorState::on_action_paste,
+                                ),
+                            )
+                            .child(Input::new(&input).appearance(false).h_full()),
+                    )
+                    .child(
+                        div().flex_1().h_full().min_w_0().child(
+                            TextView::new(&preview)
+                                .style(self.text_view_style)
+                                .scrollable(true)
+                                .selectable(true)
+                                .size_full()
+                                .p_3(),
+                        ),
+                    ),
+            )
+            .refine_style(&self.style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FormatEdit, MarkdownFormat};
+
+    fn apply(format: MarkdownFormat, source: &str) -> (String, String) {
+        let start = source.find('|').unwrap();
+        let end = source.rfind('|').unwrap() - 1;
+        let source = source.replace('|', "");
+        let edit: FormatEdit = format.edit(&source, start..end.max(start));
+
+        let mut text = source.clone();
+        text.replace_range(edit.range, &edit.text);
+        let selected = text[edit.selection].to_string();
+        (text, selected)
+    }
+
+    #[test]
+    fn test_format_edit() {
+        assert_eq!(
+            apply(MarkdownFormat::Bold, "Hello |world|!"),
+            ("Hello **world**!".into(), "world".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Bold, "Hello **|world|**!"),
+            ("Hello world!".into(), "world".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Italic, "Hello |"),
+            ("Hello _italic_".into(), "italic".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Code, "Run |cargo test| now"),
+            ("Run `cargo test` now".into(), "cargo test".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Code, "Run:|a\nb\n|"),
+            ("Run:\n```\na\nb\n```".into(), "a\nb".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Link, "See |docs|."),
+            ("See [docs](url).".into(), "url".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Link, "See |text|."),
+            ("See [text](url).".into(), "url".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Link, "See |https://example.com|."),
+            ("See [text](https://example.com).".into(), "text".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::BulletList, "one\nt|wo\nthr|ee\nfour"),
+            ("one\n- two\n- three\nfour".into(), "- two\n- three".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::BulletList, "|- one\n\n- two|"),
+            ("one\n\ntwo".into(), "one\n\ntwo".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::BulletList, "one\n|"),
+            ("one\n- ".into(), "".into())
+        );
+        assert_eq!(
+            apply(MarkdownFormat::Table, "Text|"),
+            (
+                "Text\n\n| Column 1 | Column 2 |\n| --- | --- |\n| Cell | Cell |\n".into(),
+                "Column 1".into()
+            )
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Export the selection of the document as Markdown or HTML, to copy with the formatting.
+use std::ops::{Range, RangeInclusive};
+
+use crate::{
+    highlighter::escape_html,
+    text::{
+        document::ParsedDocument,
+        node::{BlockNode, InlineNode, Paragraph, TextMark},
+    },
+};
+
+impl ParsedDocument {
+    /// Returns the selected blocks in the `range` as Markdown.
+    ///
+    /// The fully selected blocks are copied from the Markdown source as is,
+    /// the partially selected blocks are converted from the selected nodes.
+    pub(super) fn selected_markdown(&self, range: RangeInclusive<usize>) -> String {
+        self.map_selected_blocks(range, |block, all| {
+            if !all && !block.is_fully_selected() {
+                return block.selected_markdown(false);
+            }
+
+            // The HTML nodes have no span, convert them to Markdown.
+            block
+                .span()
+                .and_then(|span| self.source.get(span.start..span.end))
+                .map(|source| source.trim().to_string())
+                .unwrap_or_else(|| block.to_markdown())
+        })
+        .join("\n\n")
+    }
+
+    /// Returns the selected blocks in the `range` as HTML.
+    pub(super) fn selected_html(&self, range: RangeInclusive<usize>) -> String {
+        self.map_selected_blocks(range, |block, all| block.selected_html(all))
+            .join("\n")
+    }
+
+    /// Map the blocks in the `range`, the second argument is true for the fully selected blocks
+    /// between the first and the last, see [`ParsedDocument::selected_text`].
+    fn map_selected_blocks(
+        &self,
+        range: RangeInclusive<usize>,
+        f: impl Fn(&BlockNode, bool) -> String,
+    ) -> Vec<String> {
+        let (first, last) = (*range.start(), *range.end());
+        self.blocks
+            .iter()
+            .enumerate()
+            .take(last + 1)
+            .skip(first)
+            .map(|(ix, block)| f(block, ix > first && ix < last))
+            .filter(|text| !text.is_empty())
+            .collect()
+    }
+}
+
+impl BlockNode {
+    /// Returns true if all the text in the block is selected.
+    fn is_fully_selected(&self) -> bool {
+        let text = self.selected_text(true);
+        !text.is_empty() && self.selected_text(false) == text
+    }
+
+    /// Returns the selected part of the node as Markdown, all if `all` is true.
+    fn selected_markdown(&self, all: bool) -> String {
+        match self {
+            BlockNode::Paragraph(paragraph) => paragraph.selected_markdown(all),
+            BlockNode::Heading {
+                level, children, ..
+            } => {
+                let text = children.selected_markdown(all);
+                if text.is_empty() {
+                    return text;
+                }
+                format!("{} {}", "#".repeat(*level as usize), text)
+            }
+            BlockNode::List {
+                children, ordered, ..
+            } => children
+                .iter()
+                .filter(|item| item.is_list_item())
+                .enumerate()
+                .filter_map(|(ix, item)| {
+                    let text = item.selected_markdown(all);
+                    if text.is_empty() {
+                        return None;
+                    }
+                    let marker = if *ordered {
+                        format!("{}. ", ix + 1)
+                    } else {
+                        "- ".to_string()
+                    };
+                    Some(prefix_lines(&text, &marker, &" ".repeat(marker.len())))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            BlockNode::ListItem {
+                children, checked, ..
+            } => {
+                let text = children_markdown(children, all);
+                match checked {
+                    Some(_) if text.is_empty() => text,
+                    Some(true) => format!("[x] {}", text),
+                    Some(false) => format!("[ ] {}", text),
+                    None => text,
+                }
+            }
+            BlockNode::Blockquote { children, .. } | BlockNode::Alert { children, .. } => {
+                prefix_lines(&children_markdown(children, all), "> ", "> ")
+            }
+            BlockNode::Root { children, .. }
+            | BlockNode::Footnote { children, .. }
+            | BlockNode::Component { children, .. } => children_markdown(children, all),
+            BlockNode::CodeBlock(code_block) => {
+                let code = code_block.selected_text(all);
+                if code.is_empty() {
+                    return code;
+                }
+                format!(
+                    "```{}\n{}\n```",
+                    code_block.lang().unwrap_or_default(),
+                    code.trim_end_matches('\n')
+                )
+            }
+            BlockNode::Table(table) => {
+                let mut rows = vec![];
+                for (ix, row) in table.children.iter().enumerate() {
+                    let cells = row
+                        .children
+                        .iter()
+                        .map(|cell| cell.children.selected_markdown(all).replace('|', "\\|"))
+                        .collect::<Vec<_>>();
+                    if cells.iter().all(|cell| cell.is_empty()) {
+                        continue;
+                    }
+
+                    rows.push(format!("| {} |", cells.join(" | ")));
+                    if ix == 0 {
+                        rows.push(format!("|{}", " --- |".repeat(cells.len())));
+                    }
+                }
+                rows.join("\n")
+            }
+            _ if all => self.to_markdown(),
+            _ => String::new(),
+        }
+    }
+
+    /// Returns the selected part of the node as HTML, all if `all` is true.
+    fn selected_html(&self, all: bool) -> String {
+        match self {
+            BlockNode::Paragraph(paragraph) => wrap_tag("p", paragraph.selected_html(all)),
+            BlockNode::Heading {
+                level, children, ..
+            } => wrap_tag(&format!("h{}", level), children.selected_html(all)),
+            BlockNode::List {
+                children, ordered, ..
+            } => wrap_tag(
+                if *ordered { "ol" } else { "ul" },
+                children_html(children, all),
+            ),
+            BlockNode::ListItem {
+                children, checked, ..
+            } => {
+                let html = children_html(children, all);
+                match checked {
+                    Some(_) if html.is_empty() => html,
+                    Some(checked) => format!(
+                        "<li><input type=\"checkbox\" disabled{}> {}</li>",
+                        if *checked { " checked" } else { "" },
+                        html
+                    ),
+                    None => wrap_tag("li", html),
+                }
+            }
+            BlockNode::Blockquote { children, .. } | BlockNode::Alert { children, .. } => {
+                wrap_tag("blockquote", children_html(children, all))
+            }
+            BlockNode::Root { children, .. }
+            | BlockNode::Footnote { children, .. }
+            | BlockNode::Component { children, .. } => children_html(children, all),
+            BlockNode::CodeBlock(code_block) => {
+                let code = code_block.selected_text(all);
+                if code.is_empty() {
+                    return code;
+                }
+                let class = code_block
+                    .lang()
+                    .map(|lang| format!(" class=\"language-{}\"", escape_html(&lang)))
+                    .unwrap_or_default();
+                format!(
+                    "<pre><code{}>{}</code></pre>",
+                    class,
+                    escape_html(code.trim_end_matches('\n'))
+                )
+            }
+            BlockNode::Table(table) => {
+                let mut rows = String::new();
+                for (ix, row) in table.children.iter().enumerate() {
+                    let cells = row
+                        .children
+                        .iter()
+                        .map(|cell| (cell, cell.children.selected_html(all)))
+                        .collect::<Vec<_>>();
+                    if cells.iter().all(|(_, html)| html.is_empty()) {
+                        continue;
+                    }
+
+                    let tag = if ix == 0 { "th" } else { "td" };
+                    rows.push_str("<tr>");
+                    for (cell, html) in cells {
+                        rows.push_str(&format!("<{}", tag));
+                        if cell.colspan > 1 {
+                            rows.push_str(&format!(" colspan=\"{}\"", cell.colspan));
+                        }
+                        if cell.rowspan > 1 {
+                            rows.push_str(&format!(" rowspan=\"{}\"", cell.rowspan));
+                        }
+                        rows.push_str(&format!(">{}</{}>", html, tag));
+                    }
+                    rows.push_str("</tr>");
+                }
+                wrap_tag("table", rows)
+            }
+            BlockNode::Math { math, .. } if all => {
+                format!("<pre><code>{}</code></pre>", escape_html(&math.source))
+            }
+            BlockNode::Divider { .. } if all => "<hr>".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+impl Paragraph {
+    /// Returns the selected inline nodes as Markdown.
+    fn selected_markdown(&self, all: bool) -> String {
+        let mut text = String::new();
+        for (node, range) in self.selected_nodes(all) {
+            if let Some(math) = &node.math {
+                text.push_str(&format!("${}$", math.source));
+                continue;
+            }
+            if let Some(component) = &node.component {
+                text.push_str(&component.to_markdown());
+                continue;
+            }
+
+            for (range, mark) in mark_segments(node, range) {
+                let mut part = node.text[range].to_string();
+                if mark.code {
+                    part = format!("`{}`", part);
+                }
+                if mark.bold {
+                    part = format!("**{}**", part);
+                }
+                if mark.italic {
+                    part = format!("*{}*", part);
+                }
+                if mark.strikethrough {
+                    part = format!("~~{}~~", part);
+                }
+                if let Some(link) = &mark.link {
+                    part = match &link.identifier {
+                        Some(identifier) if link.url.is_empty() => {
+                            format!("[{}][{}]", part, identifier)
+                        }
+                        _ => format!("[{}]({})", part, link.url),
+                    };
+                }
+                text.push_str(&part);
+            }
+
+            if let Some(image) = &node.image {
+                let image_text = format!(
+                    "![{}]({})",
+                    image.alt.clone().unwrap_or_default(),
+                    image.url
+                );
+                match &image.link {
+                    Some(link) => text.push_str(&format!("[{}]({})", image_text, link.url)),
+                    None => text.push_str(&image_text),
+                }
+            }
+        }
+        text
+    }
+
+    /// Returns the selected inline nodes as HTML.
+    fn selected_html(&self, all: bool) -> String {
+        let mut html = String::new();
+        for (node, range) in self.selected_nodes(all) {
+            if let Some(math) = &node.math {
+                html.push_str(&format!("<code>{}</code>", escape_html(&math.source)));
+                continue;
+            }
+            if let Some(component) = &node.component {
+                html.push_str(&component.children.selected_html(true));
+                continue;
+            }
+
+            for (range, mark) in mark_segments(node, range) {
+                let mut part = escape_html(&node.text[range]);
+                if mark.code {
+                    part = format!("<code>{}</code>", part);
+                }
+                if mark.bold {
+                    part = format!("<strong>{}</strong>", part);
+                }
+                if mark.italic {
+                    part = format!("<em>{}</em>", part);
+                }
+                if mark.strikethrough {
+                    part = format!("<del>{}</del>", part);
+                }
+                if mark.underline {
+                    part = format!("<u>{}</u>", part);
+                }
+                if let Some(link) = &mark.link {
+                    part = format!("<a href=\"{}\">{}</a>", escape_html(&link.url), part);
+                }
+                html.push_str(&part);
+            }
+
+            if let Some(image) = &node.image {
+                let image_html = format!(
+                    "<img src=\"{}\" alt=\"{}\">",
+                    escape_html(&image.url),
+                    escape_html(&image.alt.clone().unwrap_or_default())
+                );
+                match &image.link {
+                    Some(link) => html.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(&link.url),
+                        image_html
+                    )),
+                    None => html.push_str(&image_html),
+                }
+            }
+        }
+        html
+    }
+}
+
+/// Split the `range` of the node text by the boundaries of the marks, with the combined mark of each part.
+fn mark_segments(node: &InlineNode, range: Range<usize>) -> Vec<(Range<usize>, TextMark)> {
+    let mut bounds = vec![range.start, range.end];
+    for (mark_range, _) in node.marks.iter() {
+        for bound in [mark_range.start, mark_range.end] {
+            if range.contains(&bound) {
+                bounds.push(bound);
+            }
+        }
+    }
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    bounds
+        .windows(2)
+        .map(|bound| {
+            let part = bound[0]..bound[1];
+            let mut mark = TextMark::default();
+            for (mark_range, style) in node.marks.iter() {
+                if mark_range.start <= part.start && part.end <= mark_range.end {
+                    mark.bold |= style.bold;
+                    mark.italic |= style.italic;
+                    mark.strikethrough |= style.strikethrough;
+                    mark.underline |= style.underline;
+                    mark.code |= style.code;
+                    if style.link.is_some() {
+                        mark.link = style.link.clone();
+                    }
+                }
+            }
+            (part, mark)
+        })
+        .collect()
+}
+
+fn children_markdown(children: &[BlockNode], all: bool) -> String {
+    children
+        .iter()
+        .map(|child| child.selected_markdown(all))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn children_html(children: &[BlockNode], all: bool) -> String {
+    children
+        .iter()
+        .map(|child| child.selected_html(all))
+        .collect()
+}
+
+/// Prefix the first line with `first` and the other lines with `rest`.
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(ix, line)| {
+            let prefix = if ix == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn wrap_tag(tag: &str, html: String) -> String {
+    if html.is_empty() {
+        return html;
+    }
+    format!("<{}>{}</{}>", tag, html, tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        highlighter::HighlightTheme,
+        text::{format, node::NodeContext},
+    };
+
+    #[test]
+    fn test_selected_markdown_and_html() {
+        let mut cx = NodeContext::default();
+        let document = format::markdown::parse(
+            "# Title\n\nHello **bold** and [link](https://example.com) 1 < 2.\n\n- one\n- [x] two\n\n```rust\nfn main() {}\n```",
+            &mut cx,
+            &HighlightTheme::default_light(),
+        )
+        .unwrap();
+        let last = document.blocks.len() - 1;
+
+        // The blocks between the first and the last are fully selected.
+        assert_eq!(
+            document.selected_markdown(0..=last + 1),
+            "Hello **bold** and [link](https://example.com) 1 < 2.\n\n- one\n- [x] two\n\n```rust\nfn main() {}\n```"
+        );
+        assert_eq!(
+            document.selected_html(0..=last + 1),
+            "<p>Hello <strong>bold</strong> and <a href=\"https://example.com\">link</a> 1 &lt; 2.</p>\n\
+             <ul><li><p>one</p></li><li><input type=\"checkbox\" disabled checked> <p>two</p></li></ul>\n\
+             <pre><code class=\"language-rust\">fn main() {}</code></pre>"
+        );
+    }
+}
@@ -0,0 +1,728 @@
+//! A subset of CSS for the HTML TextView.
+//!
+//! Only the simple selectors (tag, class, id and the compound of them, e.g. `p.note`) are supported,
+//! the combinators, attribute selectors, pseudo classes and at-rules (e.g. `@media`) are ignored.
+
+use gpui::{
+    AbsoluteLength, DefiniteLength, Edges, EdgesRefinement, FontStyle, FontWeight, Hsla, Length,
+    Pixels, Rgba, StrikethroughStyle, Styled, TextAlign, UnderlineStyle, px, relative, rems,
+};
+
+use crate::text::node::TextMark;
+
+/// The rules parsed from the `<style>` elements.
+#[derive(Debug, Default)]
+pub(super) struct StyleSheet {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    selector: Selector,
+    declarations: Vec<(String, String)>,
+}
+
+/// A compound selector, e.g. `p`, `.note`, `#intro` or `p.note`.
+#[derive(Debug, Default, PartialEq)]
+struct Selector {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+impl Selector {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        let mut parts = vec![];
+        let mut start = 0;
+        for (ix, c) in text.char_indices() {
+            match c {
+                '.' | '#' => {
+                    parts.push(&text[start..ix]);
+                    start = ix;
+                }
+                c if c.is_alphanumeric() || c == '-' || c == '_' || c == '*' => {}
+                // The combinators, attribute selectors and pseudo classes.
+                _ => return None,
+            }
+        }
+        parts.push(&text[start..]);
+
+        let mut selector = Selector::default();
+        for (ix, part) in parts.into_iter().enumerate() {
+            if let Some(class) = part.strip_prefix('.') {
+                if class.is_empty() {
+                    return None;
+                }
+                selector.classes.push(class.to_string());
+            } else if let Some(id) = part.strip_prefix('#') {
+                if id.is_empty() {
+                    return None;
+                }
+                selector.id = Some(id.to_string());
+            } else if ix == 0 && part != "*" && !part.is_empty() {
+                selector.tag = Some(part.to_lowercase());
+            }
+        }
+
+        Some(selector)
+    }
+
+    /// The specificity in `(id, class, tag)`.
+    fn specificity(&self) -> (usize, usize, usize) {
+        (
+            self.id.is_some() as usize,
+            self.classes.len(),
+            self.tag.is_some() as usize,
+        )
+    }
+
+    fn matches(&self, tag: &str, id: Option<&str>, classes: &[&str]) -> bool {
+        self.tag
+            .as_ref()
+            .map_or(true, |t| t.eq_ignore_ascii_case(tag))
+            && self.id.as_ref().map_or(true, |t| Some(t.as_str()) == id)
+            && self
+                .classes
+                .iter()
+                .all(|class| classes.contains(&class.as_str()))
+    }
+}
+
+impl StyleSheet {
+    /// Parse the content of a `<style>` element and add the rules.
+    pub(super) fn add(&mut self, source: &str) {
+        let source = strip_comments(source);
+        let mut rest = source.as_str();
+        while let Some(open) = rest.find('{') {
+            let Some(len) = block_len(&rest[open..]) else {
+                break;
+            };
+            // Skip the statements like `@import url(..);` before the rule.
+            let prelude = rest[..open].rsplit(';').next().unwrap_or_default().trim();
+            let body = &rest[open + 1..open + len - 1];
+            rest = &rest[open + len..];
+
+            if prelude.starts_with('@') {
+                continue;
+            }
+
+            let declarations = parse_declarations(body);
+            for selector in prelude.split(',').filter_map(Selector::parse) {
+                self.rules.push(Rule {
+                    selector,
+                    declarations: declarations.clone(),
+                });
+            }
+        }
+    }
+
+    /// Returns the style of an element by the matched rules and the `inline` style attribute.
+    ///
+    /// The rules are applied in the order of specificity and then the source order,
+    /// the inline style is applied at last.
+    pub(super) fn style(
+        &self,
+        tag: &str,
+        id: Option<&str>,
+        classes: &[&str],
+        inline: Option<&str>,
+    ) -> CssStyle {
+        let mut rules = self
+            .rules
+            .iter()
+            .filter(|rule| rule.selector.matches(tag, id, classes))
+            .collect::<Vec<_>>();
+        rules.sort_by_key(|rule| rule.selector.specificity());
+
+        let mut style = CssStyle::default();
+        for rule in rules {
+            for (name, value) in rule.declarations.iter() {
+                style.apply(name, value);
+            }
+        }
+        if let Some(inline) = inline {
+            for (name, value) in parse_declarations(inline) {
+                style.apply(&name, &value);
+            }
+        }
+
+        style
+    }
+}
+
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Returns the length of the `{..}` block at the start of the `text`, including the nested blocks.
+fn block_len(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (ix, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(ix + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Parse the declarations like `color: red; margin: 0 4px`, the names are in lowercase.
+///
+/// The `!important` is ignored.
+pub(super) fn parse_declarations(text: &str) -> Vec<(String, String)> {
+    text.split(';')
+        .filter_map(|decl| {
+            let (name, value) = decl.split_once(':')?;
+            let value = value.trim();
+            let value = value.strip_suffix("!important").unwrap_or(value).trim();
+            Some((name.trim().to_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Split the value by whitespace, but keep the spaces in the parentheses, e.g. `rgb(0, 0, 0)`.
+fn split_values(value: &str) -> Vec<&str> {
+    let mut values = vec![];
+    let mut depth = 0;
+    let mut start = None;
+    for (ix, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    values.push(&value[start..ix]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(ix);
+    }
+    if let Some(start) = start {
+        values.push(&value[start..]);
+    }
+
+    values
+}
+
+/// Parse length value from style attribute.
+///
+/// When is percentage, it will be converted to relative length.
+/// The `em` and `rem` are converted to rems, others are converted to pixels.
+pub(super) fn value_to_length(value: &str) -> Option<DefiniteLength> {
+    let value = value.trim();
+    if let Some(value) = value.strip_suffix("%") {
+        value.parse::<f32>().ok().map(|v| relative(v / 100.))
+    } else if let Some(value) = value
+        .strip_suffix("rem")
+        .or_else(|| value.strip_suffix("em"))
+    {
+        value.parse().ok().map(|v| rems(v).into())
+    } else if let Some(value) = value.strip_suffix("pt") {
+        value.parse::<f32>().ok().map(|v| px(v * 4. / 3.).into())
+    } else {
+        value
+            .trim_end_matches("px")
+            .parse()
+            .ok()
+            .map(|v| px(v).into())
+    }
+}
+
+/// Parse the CSS color: `#RGB`, `#RRGGBB`, `#RRGGBBAA`, `rgb(..)`, `rgba(..)` or the basic color names.
+pub(super) fn parse_color(value: &str) -> Option<Hsla> {
+    let value = value.trim().to_lowercase();
+    if value.starts_with('#') {
+        return Rgba::try_from(value.as_str()).ok().map(Into::into);
+    }
+
+    if let Some(args) = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|args| args.strip_suffix(')'))
+    {
+        let parts = args
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        let channel = |part: &str, max: f32| -> Option<f32> {
+            let value = match part.strip_suffix('%') {
+                Some(percent) => percent.parse::<f32>().ok()? / 100.,
+                None => part.parse::<f32>().ok()? / max,
+            };
+            Some(value.clamp(0., 1.))
+        };
+
+        return Some(
+            Rgba {
+                r: channel(*parts.get(0)?, 255.)?,
+                g: channel(*parts.get(1)?, 255.)?,
+                b: channel(*parts.get(2)?, 255.)?,
+                a: parts.get(3).map_or(Some(1.), |&part| channel(part, 1.))?,
+            }
+            .into(),
+        );
+    }
+
+    let hex = match value.as_str() {
+        "transparent" => return Some(gpui::transparent_black()),
+        "black" => 0x000000,
+        "white" => 0xffffff,
+        "red" => 0xff0000,
+        "green" => 0x008000,
+        "blue" => 0x0000ff,
+        "yellow" => 0xffff00,
+        "orange" => 0xffa500,
+        "purple" => 0x800080,
+        "pink" => 0xffc0cb,
+        "brown" => 0xa52a2a,
+        "gray" | "grey" => 0x808080,
+        "silver" => 0xc0c0c0,
+        "maroon" => 0x800000,
+        "navy" => 0x000080,
+        "teal" => 0x008080,
+        "olive" => 0x808000,
+        "lime" => 0x00ff00,
+        "aqua" | "cyan" => 0x00ffff,
+        "fuchsia" | "magenta" => 0xff00ff,
+        _ => return None,
+    };
+    Some(gpui::rgb(hex).into())
+}
+
+fn parse_font_weight(value: &str) -> Option<FontWeight> {
+    match value {
+        "normal" | "lighter" => Some(FontWeight::NORMAL),
+        "bold" | "bolder" => Some(FontWeight::BOLD),
+        _ => value.parse::<f32>().ok().map(FontWeight),
+    }
+}
+
+fn parse_font_size(value: &str) -> Option<AbsoluteLength> {
+    let size = match value {
+        "xx-small" => 0.5625,
+        "x-small" => 0.625,
+        "small" => 0.875,
+        "medium" => 1.,
+        "large" => 1.125,
+        "x-large" => 1.5,
+        "xx-large" => 2.,
+        _ => {
+            return match value_to_length(value)? {
+                DefiniteLength::Absolute(length) => Some(length),
+                DefiniteLength::Fraction(fraction) => Some(rems(fraction).into()),
+            };
+        }
+    };
+    Some(rems(size).into())
+}
+
+fn parse_text_align(value: &str) -> Option<TextAlign> {
+    match value {
+        "left" | "start" => Some(TextAlign::Left),
+        "center" => Some(TextAlign::Center),
+        "right" | "end" => Some(TextAlign::Right),
+        _ => None,
+    }
+}
+
+fn parse_margin(value: &str) -> Option<Length> {
+    if value == "auto" {
+        Some(Length::Auto)
+    } else {
+        value_to_length(value).map(Length::Definite)
+    }
+}
+
+fn parse_border_width(value: &str) -> Option<Pixels> {
+    match value {
+        "thin" => Some(px(1.)),
+        "medium" => Some(px(3.)),
+        "thick" => Some(px(5.)),
+        _ => value.trim_end_matches("px").parse().ok().map(px),
+    }
+}
+
+/// Expand the shorthand of 1 to 4 values, e.g. `margin: 4px 8px`, to the edges.
+fn parse_edges<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Edges<Option<T>>>
+where
+    T: Clone + Default + std::fmt::Debug + PartialEq,
+{
+    let values = split_values(value)
+        .into_iter()
+        .map(parse)
+        .collect::<Option<Vec<_>>>()?;
+    let (top, right, bottom, left) = match values.as_slice() {
+        [a] => (a, a, a, a),
+        [a, b] => (a, b, a, b),
+        [a, b, c] => (a, b, c, b),
+        [a, b, c, d] => (a, b, c, d),
+        _ => return None,
+    };
+
+    Some(Edges {
+        top: Some(top.clone()),
+        right: Some(right.clone()),
+        bottom: Some(bottom.clone()),
+        left: Some(left.clone()),
+    })
+}
+
+fn set_edge<T>(edges: &mut Edges<Option<T>>, side: &str, value: Option<T>)
+where
+    T: Clone + Default + std::fmt::Debug + PartialEq,
+{
+    let Some(value) = value else {
+        return;
+    };
+
+    match side {
+        "top" => edges.top = Some(value),
+        "right" => edges.right = Some(value),
+        "bottom" => edges.bottom = Some(value),
+        "left" => edges.left = Some(value),
+        _ => {}
+    }
+}
+
+/// Parse the border shorthand like `1px solid #ddd` to the width and color.
+fn parse_border(value: &str) -> (Option<Pixels>, Option<Hsla>) {
+    let mut width = None;
+    let mut color = None;
+    let mut has_style = false;
+    for value in split_values(value) {
+        match value {
+            "none" | "hidden" => return (Some(px(0.)), None),
+            "solid" | "dashed" | "dotted" | "double" | "groove" | "ridge" | "inset" | "outset" => {
+                has_style = true
+            }
+            _ => {
+                if let Some(w) = parse_border_width(value) {
+                    width = Some(w);
+                } else if let Some(c) = parse_color(value) {
+                    color = Some(c);
+                }
+            }
+        }
+    }
+
+    // The default border width is `medium`.
+    if width.is_none() && has_style {
+        width = Some(px(3.));
+    }
+    (width, color)
+}
+
+fn refine_edges<T>(target: &mut EdgesRefinement<T>, edges: &Edges<Option<T>>)
+where
+    T: Clone + Default + std::fmt::Debug + PartialEq,
+{
+    target.top = edges.top.clone().or(target.top.take());
+    target.right = edges.right.clone().or(target.right.take());
+    target.bottom = edges.bottom.clone().or(target.bottom.take());
+    target.left = edges.left.clone().or(target.left.take());
+}
+
+/// The supported CSS properties of an element.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct CssStyle {
+    pub(crate) color: Option<Hsla>,
+    pub(crate) background: Option<Hsla>,
+    pub(crate) font_weight: Option<FontWeight>,
+    pub(crate) italic: Option<bool>,
+    pub(crate) font_size: Option<AbsoluteLength>,
+    pub(crate) underline: Option<bool>,
+    pub(crate) strikethrough: Option<bool>,
+    pub(crate) text_align: Option<TextAlign>,
+    pub(crate) margin: Edges<Option<Length>>,
+    pub(crate) padding: Edges<Option<DefiniteLength>>,
+    pub(crate) border_widths: Edges<Option<Pixels>>,
+    /// The border color of all sides, the color of each side is not supported.
+    pub(crate) border_color: Option<Hsla>,
+}
+
+impl CssStyle {
+    pub(crate) fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Apply a declaration, the invalid or unsupported declaration is ignored.
+    fn apply(&mut self, name: &str, value: &str) {
+        let value = value.trim();
+        if let Some(side) = name.strip_prefix("margin-") {
+            set_edge(&mut self.margin, side, parse_margin(value));
+            return;
+        }
+        if let Some(side) = name.strip_prefix("padding-") {
+            set_edge(&mut self.padding, side, value_to_length(value));
+            return;
+        }
+        if let Some(side) = name.strip_prefix("border-") {
+            if matches!(side, "top" | "right" | "bottom" | "left") {
+                let (width, color) = parse_border(value);
+                set_edge(&mut self.border_widths, side, width);
+                self.border_color = color.or(self.border_color);
+                return;
+            }
+        }
+
+        match name {
+            "color" => self.color = parse_color(value).or(self.color),
+            "background" | "background-color" => {
+                self.background = split_values(value)
+                    .into_iter()
+                    .find_map(parse_color)
+                    .or(self.background)
+            }
+            "font-weight" => self.font_weight = parse_font_weight(value).or(self.font_weight),
+            "font-style" => match value {
+                "italic" | "oblique" => self.italic = Some(true),
+                "normal" => self.italic = Some(false),
+                _ => {}
+            },
+            "font-size" => self.font_size = parse_font_size(value).or(self.font_size),
+            "text-decoration" | "text-decoration-line" => {
+                for value in split_values(value) {
+                    match value {
+                        "underline" => self.underline = Some(true),
+                        "line-through" => self.strikethrough = Some(true),
+                        "none" => {
+                            self.underline = Some(false);
+                            self.strikethrough = Some(false);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "text-align" => self.text_align = parse_text_align(value).or(self.text_align),
+            "margin" => {
+                if let Some(edges) = parse_edges(value, parse_margin) {
+                    self.margin = edges;
+                }
+            }
+            "padding" => {
+                if let Some(edges) = parse_edges(value, value_to_length) {
+                    self.padding = edges;
+                }
+            }
+            "border" => {
+                let (width, color) = parse_border(value);
+                if let Some(width) = width {
+                    self.border_widths = Edges::all(Some(width));
+                }
+                self.border_color = color.or(self.border_color);
+            }
+            "border-width" => {
+                if let Some(edges) = parse_edges(value, parse_border_width) {
+                    self.border_widths = edges;
+                }
+            }
+            "border-color" => {
+                self.border_color = split_values(value)
+                    .into_iter()
+                    .find_map(parse_color)
+                    .or(self.border_color)
+            }
+            "border-style" if matches!(value, "none" | "hidden") => {
+                self.border_widths = Edges::all(Some(px(0.)));
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the [`TextMark`] of the text properties, used for the inline elements.
+    ///
+    /// The `font-size` is not supported for inline text.
+    pub(super) fn text_mark(&self) -> Option<TextMark> {
+        let mark = TextMark {
+            bold: self
+                .font_weight
+                .is_some_and(|w| w.0 >= FontWeight::SEMIBOLD.0),
+            italic: self.italic == Some(true),
+            strikethrough: self.strikethrough == Some(true),
+            underline: self.underline == Some(true),
+            color: self.color,
+            background: self.background,
+            ..Default::default()
+        };
+
+        (mark != TextMark::default()).then_some(mark)
+    }
+
+    /// Apply the style to a block element.
+    pub(crate) fn apply_to<E: Styled>(&self, mut el: E) -> E {
+        let style = el.style();
+        style.text.color = self.color.or(style.text.color);
+        style.text.font_weight = self.font_weight.or(style.text.font_weight);
+        style.text.font_size = self.font_size.or(style.text.font_size);
+        style.text.text_align = self.text_align.or(style.text.text_align);
+        if let Some(italic) = self.italic {
+            style.text.font_style = Some(if italic {
+                FontStyle::Italic
+            } else {
+                FontStyle::Normal
+            });
+        }
+        if self.underline == Some(true) {
+            style.text.underline = Some(UnderlineStyle {
+                thickness: px(1.),
+                ..Default::default()
+            });
+        }
+        if self.strikethrough == Some(true) {
+            style.text.strikethrough = Some(StrikethroughStyle {
+                thickness: px(1.),
+                ..Default::default()
+            });
+        }
+        if let Some(background) = self.background {
+            style.background = Some(background.into());
+        }
+
+        refine_edges(&mut style.margin, &self.margin);
+        refine_edges(&mut style.padding, &self.padding);
+        refine_edges(
+            &mut style.border_widths,
+            &Edges {
+                top: self.border_widths.top.map(Into::into),
+                right: self.border_widths.right.map(Into::into),
+                bottom: self.border_widths.bottom.map(Into::into),
+                left: self.border_widths.left.map(Into::into),
+            },
+        );
+        style.border_color = self.border_color.or(style.border_color);
+        el
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{Edges, FontWeight, Length, Rgba, TextAlign, px, relative, rems};
+
+    use super::{CssStyle, Selector, StyleSheet, parse_color, value_to_length};
+
+    #[test]
+    fn test_value_to_length() {
+        assert_eq!(value_to_length("100px"), Some(px(100.).into()));
+        assert_eq!(value_to_length("100%"), Some(relative(1.)));
+        assert_eq!(value_to_length("56%"), Some(relative(0.56)));
+        assert_eq!(value_to_length("240"), Some(px(240.).into()));
+        assert_eq!(value_to_length("1.5em"), Some(rems(1.5).into()));
+        assert_eq!(value_to_length("2rem"), Some(rems(2.).into()));
+        assert_eq!(value_to_length("12pt"), Some(px(16.).into()));
+        assert_eq!(value_to_length("auto"), None);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#ff0000"), Some(gpui::rgb(0xff0000).into()));
+        assert_eq!(parse_color("Red"), Some(gpui::rgb(0xff0000).into()));
+        assert_eq!(
+            parse_color("rgb(0, 0, 255)"),
+            Some(gpui::rgb(0x0000ff).into())
+        );
+        assert_eq!(
+            parse_color("rgba(0 0 255 / 50%)"),
+            Some(
+                Rgba {
+                    r: 0.,
+                    g: 0.,
+                    b: 1.,
+                    a: 0.5
+                }
+                .into()
+            )
+        );
+        assert_eq!(parse_color("transparent"), Some(gpui::transparent_black()));
+        assert_eq!(parse_color("var(--color)"), None);
+    }
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(
+            Selector::parse("p.note#intro"),
+            Some(Selector {
+                tag: Some("p".into()),
+                id: Some("intro".into()),
+                classes: vec!["note".into()],
+            })
+        );
+        assert_eq!(Selector::parse("div p"), None);
+        assert_eq!(Selector::parse("a:hover"), None);
+
+        let selector = Selector::parse(".note.warn").unwrap();
+        assert!(selector.matches("div", None, &["warn", "note"]));
+        assert!(!selector.matches("div", None, &["note"]));
+    }
+
+    #[test]
+    fn test_style_sheet() {
+        let mut sheet = StyleSheet::default();
+        sheet.add(
+            r#"
+            /* The comment { } */
+            @import url("base.css");
+            p, li { color: green; margin: 4px 8px; }
+            .note { color: blue !important; font-weight: 600 }
+            @media (max-width: 600px) { p { color: black } }
+            #intro { text-align: center; border: 1px solid #ddd }
+            p { font-style: italic; padding-left: 1em }
+            a:hover { color: red }
+            "#,
+        );
+
+        let style = sheet.style("p", Some("intro"), &["note"], Some("color: #ff0000"));
+        assert_eq!(
+            style,
+            CssStyle {
+                color: Some(gpui::rgb(0xff0000).into()),
+                font_weight: Some(FontWeight(600.)),
+                italic: Some(true),
+                text_align: Some(TextAlign::Center),
+                margin: Edges {
+                    top: Some(Length::Definite(px(4.).into())),
+                    right: Some(Length::Definite(px(8.).into())),
+                    bottom: Some(Length::Definite(px(4.).into())),
+                    left: Some(Length::Definite(px(8.).into())),
+                },
+                padding: Edges {
+                    left: Some(rems(1.).into()),
+                    ..Default::default()
+                },
+                border_widths: Edges::all(Some(px(1.))),
+                border_color: Some(gpui::rgb(0xdddddd).into()),
+                ..Default::default()
+            }
+        );
+
+        // The class rule is more specific than the tag rule.
+        let style = sheet.style("li", None, &["note"], None);
+        assert_eq!(style.color, Some(gpui::rgb(0x0000ff).into()));
+        assert!(style.text_mark().unwrap().bold);
+
+        assert!(sheet.style("span", None, &[], None).is_empty());
+        assert_eq!(sheet.style("span", None, &[], None).text_mark(), None);
+    }
+}
@@ -5,15 +5,16 @@ use std::collections::HashMap;
 use std::ops::Range;
 use std::rc::Rc;
 
-use gpui::{DefiniteLength, SharedString, px, relative};
+use gpui::{DefiniteLength, SharedString};
 use html5ever::tendril::TendrilSink;
 use html5ever::{LocalName, ParseOpts, local_name, parse_document};
 use markup5ever_rcdom::{Node, NodeData, RcDom};
 
 use crate::text::document::ParsedDocument;
+use crate::text::format::css::{self, CssStyle, StyleSheet};
 use crate::text::node::{
-    self, BlockNode, ImageNode, InlineNode, LinkMark, NodeContext, Paragraph, Table, TableRow,
-    TextMark,
+    self, BlockNode, ColumnumnAlign, ImageNode, InlineNode, LinkMark, NodeContext, Paragraph,
+    Table, TableRow, TextMark,
 };
 
 const BLOCK_ELEMENTS: [&str; 35] = [
@@ -69,10 +70,13 @@ pub(crate) fn parse(source: &str, cx: &mut NodeContext) -> Result<ParsedDocument
         .read_from(&mut cursor)
         .map_err(|e| SharedString::from(format!("{:?}", e)))?;
 
+    let mut css = StyleSheet::default();
+    collect_style_sheet(&dom.document, &mut css);
+
     let mut paragraph = Paragraph::default();
     // NOTE: The outer paragraph is not used.
     let node: BlockNode =
-        parse_node(&dom.document, &mut paragraph, cx).unwrap_or(BlockNode::Unknown);
+        parse_node(&dom.document, &mut paragraph, cx, &css).unwrap_or(BlockNode::Unknown);
     let node = node.compact();
 
     Ok(ParsedDocument {
@@ -104,43 +108,57 @@ fn attr_value(attrs: &RefCell<Vec<html5ever::Attribute>>, name: LocalName) -> Op
 }
 
 /// Get style properties to HashMap
-/// TODO: Use cssparser to parse style attribute.
 fn style_attrs(attrs: &RefCell<Vec<html5ever::Attribute>>) -> HashMap<String, String> {
-    let mut styles = HashMap::new();
     let Some(css_text) = attr_value(attrs, local_name!("style")) else {
-        return styles;
+        return HashMap::new();
     };
 
-    for decl in css_text.split(';') {
-        let mut parts = decl.splitn(2, ':');
-        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
-            styles.insert(
-                key.trim().to_lowercase().to_string(),
-                value.trim().to_string(),
-            );
+    css::parse_declarations(&css_text).into_iter().collect()
+}
+
+/// Collect the rules in all `<style>` elements.
+fn collect_style_sheet(node: &Rc<Node>, css: &mut StyleSheet) {
+    if let NodeData::Element { ref name, .. } = node.data {
+        if name.local == local_name!("style") {
+            let mut source = String::new();
+            for child in node.children.borrow().iter() {
+                if let NodeData::Text { ref contents } = child.data {
+                    source.push_str(&contents.borrow());
+                }
+            }
+            css.add(&source);
+            return;
         }
     }
 
-    styles
+    for child in node.children.borrow().iter() {
+        collect_style_sheet(child, css);
+    }
 }
 
-/// Parse length value from style attribute.
-///
-/// When is percentage, it will be converted to relative length.
-/// Else, it will be converted to pixels.
-fn value_to_length(value: &str) -> Option<DefiniteLength> {
-    if value.ends_with("%") {
-        value
-            .trim_end_matches("%")
-            .parse::<f32>()
-            .ok()
-            .map(|v| relative(v / 100.))
-    } else {
-        value
-            .trim_end_matches("px")
-            .parse()
-            .ok()
-            .map(|v| px(v).into())
+/// Get the CSS style of the element from the `<style>` rules and the `style` attribute.
+fn element_style(
+    css: &StyleSheet,
+    name: &LocalName,
+    attrs: &RefCell<Vec<html5ever::Attribute>>,
+) -> CssStyle {
+    let id = attr_value(attrs, local_name!("id"));
+    let class = attr_value(attrs, local_name!("class")).unwrap_or_default();
+    let classes = class.split_whitespace().collect::<Vec<_>>();
+    let inline = attr_value(attrs, local_name!("style"));
+    css.style(name, id.as_deref(), &classes, inline.as_deref())
+}
+
+/// Wrap the node in a [`BlockNode::Root`] with the style, if the style is not empty.
+fn styled_node(node: BlockNode, style: CssStyle) -> BlockNode {
+    if style.is_empty() {
+        return node;
+    }
+
+    BlockNode::Root {
+        children: vec![node],
+        style: Some(Box::new(style)),
+        span: None,
     }
 }
 
@@ -152,27 +170,27 @@ fn attr_width_height(
     let mut height = None;
 
     if let Some(value) = attr_value(attrs, local_name!("width")) {
-        width = value_to_length(&value);
+        width = css::value_to_length(&value);
     }
 
     if let Some(value) = attr_value(attrs, local_name!("height")) {
-        height = value_to_length(&value);
+        height = css::value_to_length(&value);
     }
 
     if width.is_none() || height.is_none() {
         let styles = style_attrs(attrs);
         if width.is_none() {
-            width = styles.get("width").and_then(|v| value_to_length(&v));
+            width = styles.get("width").and_then(|v| css::value_to_length(&v));
         }
         if height.is_none() {
-            height = styles.get("height").and_then(|v| value_to_length(&v));
+            height = styles.get("height").and_then(|v| css::value_to_length(&v));
         }
     }
 
     (width, height)
 }
 
-fn parse_table_row(table: &mut Table, node: &Rc<Node>) {
+fn parse_table_row(table: &mut Table, node: &Rc<Node>, css: &StyleSheet) {
     let mut row = TableRow::default();
     let mut count = 0;
     for child in node.children.borrow().iter() {
@@ -182,12 +200,9 @@ fn parse_table_row(table: &mut Table, node: &Rc<Node>) {
                 ref attrs,
                 ..
             } if name.local == local_name!("td") || name.local == local_name!("th") => {
-                if child.children.borrow().is_empty() {
-                    continue;
-                }
-
+                // Keep the empty cells to make the columns of the rows aligned.
                 count += 1;
-                parse_table_cell(&mut row, child, attrs);
+                parse_table_cell(&mut row, child, &name.local, attrs, css);
             }
             _ => {}
         }
@@ -201,16 +216,33 @@ fn parse_table_row(table: &mut Table, node: &Rc<Node>) {
 fn parse_table_cell(
     row: &mut node::TableRow,
     node: &Rc<Node>,
+    name: &LocalName,
     attrs: &RefCell<Vec<html5ever::Attribute>>,
+    css: &StyleSheet,
 ) {
     let mut paragraph = Paragraph::default();
     for child in node.children.borrow().iter() {
-        parse_paragraph(&mut paragraph, child);
+        parse_paragraph(&mut paragraph, child, css);
     }
     let width = attr_width_height(attrs).0;
+    let span = |name: LocalName| {
+        attr_value(attrs, name)
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1)
+    };
+    let align = match attr_value(attrs, local_name!("align")).as_deref() {
+        Some("left") => Some(ColumnumnAlign::Left),
+        Some("center") => Some(ColumnumnAlign::Center),
+        Some("right") => Some(ColumnumnAlign::Right),
+        _ => element_style(css, name, attrs).text_align.map(Into::into),
+    };
     let table_cell = node::TableCell {
         children: paragraph,
         width,
+        colspan: span(local_name!("colspan")),
+        rowspan: span(local_name!("rowspan")),
+        align,
     };
     row.children.push(table_cell);
 }
@@ -235,9 +267,88 @@ fn trim_text(text: &str) -> String {
     out
 }
 
+/// Push the CSS text mark of the element.
+///
+/// The overlapped highlights are combined in arbitrary order, so the color and background
+/// are only applied to the ranges without them (or a link) in the children marks.
+fn push_style_mark(
+    marks: &mut Vec<(Range<usize>, TextMark)>,
+    len: usize,
+    css: &StyleSheet,
+    name: &html5ever::QualName,
+    attrs: &RefCell<Vec<html5ever::Attribute>>,
+) {
+    let Some(mark) = element_style(css, &name.local, attrs).text_mark() else {
+        return;
+    };
+
+    let mut new_marks = vec![];
+    if let Some(color) = mark.color {
+        let covered = marks
+            .iter()
+            .filter(|(_, m)| m.color.is_some() || m.link.as_ref().is_some_and(|l| l.decorate))
+            .map(|(range, _)| range.clone());
+        for range in uncovered_ranges(len, covered) {
+            new_marks.push((
+                range,
+                TextMark {
+                    color: Some(color),
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+    if let Some(background) = mark.background {
+        let covered = marks
+            .iter()
+            .filter(|(_, m)| m.background.is_some())
+            .map(|(range, _)| range.clone());
+        for range in uncovered_ranges(len, covered) {
+            new_marks.push((
+                range,
+                TextMark {
+                    background: Some(background),
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+
+    let mark = TextMark {
+        color: None,
+        background: None,
+        ..mark
+    };
+    if mark != TextMark::default() {
+        new_marks.push((0..len, mark));
+    }
+    marks.extend(new_marks);
+}
+
+/// Returns the ranges in `0..len` not covered by the `covered` ranges.
+fn uncovered_ranges(len: usize, covered: impl Iterator<Item = Range<usize>>) -> Vec<Range<usize>> {
+    let mut covered = covered.collect::<Vec<_>>();
+    covered.sort_by_key(|range| range.start);
+
+    let mut ranges = vec![];
+    let mut start = 0;
+    for range in covered {
+        if range.start > start {
+            ranges.push(start..range.start);
+        }
+        start = start.max(range.end);
+    }
+    if start < len {
+        ranges.push(start..len);
+    }
+
+    ranges
+}
+
 fn parse_paragraph(
     paragraph: &mut Paragraph,
     node: &Rc<Node>,
+    css: &StyleSheet,
 ) -> (String, Vec<(Range<usize>, TextMark)>) {
     let mut text = String::new();
     let mut marks = vec![];
@@ -266,43 +377,63 @@ fn parse_paragraph(
             local_name!("em") | local_name!("i") => {
                 let mut child_paragraph = Paragraph::default();
                 for child in node.children.borrow().iter() {
-                    let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child);
+                    let (child_text, child_marks) =
+                        parse_paragraph(&mut child_paragraph, &child, css);
                     merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
                 }
                 marks.push((0..text.len(), TextMark::default().italic()));
+                push_style_mark(&mut marks, text.len(), css, name, attrs);
                 paragraph.push(InlineNode::new(&text).marks(marks.clone()));
             }
             local_name!("strong") | local_name!("b") => {
                 let mut child_paragraph = Paragraph::default();
                 for child in node.children.borrow().iter() {
-                    let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child);
+                    let (child_text, child_marks) =
+                        parse_paragraph(&mut child_paragraph, &child, css);
                     merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
                 }
                 marks.push((0..text.len(), TextMark::default().bold()));
+                push_style_mark(&mut marks, text.len(), css, name, attrs);
                 paragraph.push(InlineNode::new(&text).marks(marks.clone()));
             }
             local_name!("del") | local_name!("s") => {
                 let mut child_paragraph = Paragraph::default();
                 for child in node.children.borrow().iter() {
-                    let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child);
+                    let (child_text, child_marks) =
+                        parse_paragraph(&mut child_paragraph, &child, css);
                     merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
                 }
                 marks.push((0..text.len(), TextMark::default().strikethrough()));
+                push_style_mark(&mut marks, text.len(), css, name, attrs);
+                paragraph.push(InlineNode::new(&text).marks(marks.clone()));
+            }
+            local_name!("u") | local_name!("ins") => {
+                let mut child_paragraph = Paragraph::default();
+                for child in node.children.borrow().iter() {
+                    let (child_text, child_marks) =
+                        parse_paragraph(&mut child_paragraph, &child, css);
+                    merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
+                }
+                marks.push((0..text.len(), TextMark::default().underline()));
+                push_style_mark(&mut marks, text.len(), css, name, attrs);
                 paragraph.push(InlineNode::new(&text).marks(marks.clone()));
             }
             local_name!("code") => {
                 let mut child_paragraph = Paragraph::default();
                 for child in node.children.borrow().iter() {
-                    let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child);
+                    let (child_text, child_marks) =
+                        parse_paragraph(&mut child_paragraph, &child, css);
                     merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
                 }
                 marks.push((0..text.len(), TextMark::default().code()));
+                push_style_mark(&mut marks, text.len(), css, name, attrs);
                 paragraph.push(InlineNode::new(&text).marks(marks.clone()));
             }
             local_name!("a") => {
                 let mut child_paragraph = Paragraph::default();
                 for child in node.children.borrow().iter() {
-                    let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child);
+                    let (child_text, child_marks) =
+                        parse_paragraph(&mut child_paragraph, &child, css);
                     merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
                 }
 
@@ -318,6 +449,7 @@ fn parse_paragraph(
                         ..Default::default()
                     }),
                 ));
+                push_style_mark(&mut marks, text.len(), css, name, attrs);
                 paragraph.push(InlineNode::new(&text).marks(marks.clone()));
             }
             local_name!("img") => {
@@ -339,22 +471,25 @@ fn parse_paragraph(
                     width,
                     height,
                     title: title.map(Into::into),
+                    ..Default::default()
                 });
             }
             _ => {
                 // All unknown tags to as text
                 let mut child_paragraph = Paragraph::default();
                 for child in node.children.borrow().iter() {
-                    let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child);
+                    let (child_text, child_marks) =
+                        parse_paragraph(&mut child_paragraph, &child, css);
                     merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
                 }
+                push_style_mark(&mut marks, text.len(), css, name, attrs);
                 paragraph.push(InlineNode::new(&text).marks(marks.clone()));
             }
         },
         _ => {
             let mut child_paragraph = Paragraph::default();
             for child in node.children.borrow().iter() {
-                let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child);
+                let (child_text, child_marks) = parse_paragraph(&mut child_paragraph, &child, css);
                 merge_child_text(&mut text, &mut marks, &child_text, &child_marks);
             }
             paragraph.push(InlineNode::new(&text).marks(marks.clone()));
@@ -368,6 +503,7 @@ fn parse_node(
     node: &Rc<Node>,
     paragraph: &mut Paragraph,
     cx: &mut NodeContext,
+    css: &StyleSheet,
 ) -> Option<BlockNode> {
     match node.data {
         NodeData::Text { ref contents } => {
@@ -406,19 +542,24 @@ fn parse_node(
 
                 let mut paragraph = Paragraph::default();
                 for child in node.children.borrow().iter() {
-                    parse_paragraph(&mut paragraph, child);
+                    parse_paragraph(&mut paragraph, child, css);
                 }
 
-                let heading = BlockNode::Heading {
-                    level,
-                    children: paragraph,
-                    span: None,
-                };
+                let heading = styled_node(
+                    BlockNode::Heading {
+                        level,
+                        children: paragraph,
+                        slug: SharedString::default(),
+                        span: None,
+                    },
+                    element_style(css, &name.local, attrs),
+                );
                 if children.len() > 0 {
                     children.push(heading);
 
                     Some(BlockNode::Root {
                         children,
+                        style: None,
                         span: None,
                     })
                 } else {
@@ -448,12 +589,14 @@ fn parse_node(
                     alt: alt.map(Into::into),
                     width,
                     height,
+                    ..Default::default()
                 });
 
                 if children.len() > 0 {
                     children.push(BlockNode::Paragraph(paragraph));
                     Some(BlockNode::Root {
                         children,
+                        style: None,
                         span: None,
                     })
                 } else {
@@ -462,12 +605,15 @@ fn parse_node(
             }
             local_name!("ul") | local_name!("ol") => {
                 let ordered = name.local == local_name!("ol");
-                let children = consume_children_nodes(node, paragraph, cx);
-                Some(BlockNode::List {
-                    children,
-                    ordered,
-                    span: None,
-                })
+                let children = consume_children_nodes(node, paragraph, cx, css);
+                Some(styled_node(
+                    BlockNode::List {
+                        children,
+                        ordered,
+                        span: None,
+                    },
+                    element_style(css, &name.local, attrs),
+                ))
             }
             local_name!("li") => {
                 let mut children = vec![];
@@ -475,7 +621,7 @@ fn parse_node(
 
                 for child in node.children.borrow().iter() {
                     let mut child_paragraph = Paragraph::default();
-                    if let Some(child_node) = parse_node(child, &mut child_paragraph, cx) {
+                    if let Some(child_node) = parse_node(child, &mut child_paragraph, cx, css) {
                         children.push(child_node);
                     }
                     if child_paragraph.text_len() > 0 {
@@ -493,6 +639,16 @@ fn parse_node(
 
                 consume_paragraph(&mut children, paragraph);
 
+                // Style the content, the List only renders the ListItem children.
+                let style = element_style(css, &name.local, attrs);
+                if !style.is_empty() {
+                    children = vec![BlockNode::Root {
+                        children,
+                        style: Some(Box::new(style)),
+                        span: None,
+                    }];
+                }
+
                 Some(BlockNode::ListItem {
                     children,
                     spread: false,
@@ -509,24 +665,29 @@ fn parse_node(
                     match child.data {
                         NodeData::Element { ref name, .. }
                             if name.local == local_name!("tbody")
-                                || name.local == local_name!("thead") =>
+                                || name.local == local_name!("thead")
+                                || name.local == local_name!("tfoot") =>
                         {
                             for sub_child in child.children.borrow().iter() {
-                                parse_table_row(&mut table, &sub_child);
+                                parse_table_row(&mut table, &sub_child, css);
                             }
                         }
                         _ => {
-                            parse_table_row(&mut table, &child);
+                            parse_table_row(&mut table, &child, css);
                         }
                     }
                 }
                 consume_paragraph(&mut children, paragraph);
 
-                let table = BlockNode::Table(table);
+                let table = styled_node(
+                    BlockNode::Table(table),
+                    element_style(css, &name.local, attrs),
+                );
                 if children.len() > 0 {
                     children.push(table);
                     Some(BlockNode::Root {
                         children,
+                        style: None,
                         span: None,
                     })
                 } else {
@@ -534,11 +695,14 @@ fn parse_node(
                 }
             }
             local_name!("blockquote") => {
-                let children = consume_children_nodes(node, paragraph, cx);
-                Some(BlockNode::Blockquote {
-                    children,
-                    span: None,
-                })
+                let children = consume_children_nodes(node, paragraph, cx, css);
+                Some(styled_node(
+                    BlockNode::Blockquote {
+                        children,
+                        span: None,
+                    },
+                    element_style(css, &name.local, attrs),
+                ))
             }
             local_name!("style") | local_name!("script") => None,
             _ => {
@@ -551,26 +715,42 @@ fn parse_node(
 
                     // Insert before text as a node -- The "Hello"
                     consume_paragraph(&mut children, paragraph);
+                    let start = children.len();
 
                     // Inner of the block element -- The "Inner text of block element"
                     for child in node.children.borrow().iter() {
-                        if let Some(child_node) = parse_node(child, paragraph, cx) {
+                        if let Some(child_node) = parse_node(child, paragraph, cx, css) {
                             children.push(child_node);
                         }
                     }
                     consume_paragraph(&mut children, paragraph);
 
+                    let style = element_style(css, &name.local, attrs);
+                    if !style.is_empty() && children.len() > start {
+                        let inner = children.split_off(start);
+                        let node = BlockNode::Root {
+                            children: inner,
+                            style: Some(Box::new(style)),
+                            span: None,
+                        };
+                        if children.is_empty() {
+                            return Some(node);
+                        }
+                        children.push(node);
+                    }
+
                     if children.is_empty() {
                         None
                     } else {
                         Some(BlockNode::Root {
                             children,
+                            style: None,
                             span: None,
                         })
                     }
                 } else {
                     // Others to as Inline
-                    parse_paragraph(paragraph, node);
+                    parse_paragraph(paragraph, node, css);
 
                     if paragraph.is_image() {
                         Some(BlockNode::Paragraph(paragraph.take()))
@@ -581,9 +761,10 @@ fn parse_node(
             }
         },
         NodeData::Document => {
-            let children = consume_children_nodes(node, paragraph, cx);
+            let children = consume_children_nodes(node, paragraph, cx, css);
             Some(BlockNode::Root {
                 children,
+                style: None,
                 span: None,
             })
         }
@@ -597,11 +778,12 @@ fn consume_children_nodes(
     node: &Node,
     paragraph: &mut Paragraph,
     cx: &mut NodeContext,
+    css: &StyleSheet,
 ) -> Vec<BlockNode> {
     let mut children = vec![];
     consume_paragraph(&mut children, paragraph);
     for child in node.children.borrow().iter() {
-        if let Some(child_node) = parse_node(child, paragraph, cx) {
+        if let Some(child_node) = parse_node(child, paragraph, cx, css) {
             children.push(child_node);
         }
         consume_paragraph(&mut children, paragraph);
@@ -620,11 +802,13 @@ fn consume_paragraph(children: &mut Vec<BlockNode>, paragraph: &mut Paragraph) {
 
 #[cfg(test)]
 mod tests {
-    use gpui::{px, relative};
+    use gpui::{TextAlign, px, relative};
 
     use crate::text::{
         document::ParsedDocument,
-        node::{BlockNode, ImageNode, InlineNode, NodeContext, Paragraph},
+        node::{
+            BlockNode, ColumnumnAlign, ImageNode, InlineNode, NodeContext, Paragraph, TableSlot,
+        },
     };
 
     use super::trim_text;
@@ -699,11 +883,132 @@ mod tests {
     }
 
     #[test]
-    fn test_value_to_length() {
-        assert_eq!(super::value_to_length("100px"), Some(px(100.).into()));
-        assert_eq!(super::value_to_length("100%"), Some(relative(1.)));
-        assert_eq!(super::value_to_length("56%"), Some(relative(0.56)));
-        assert_eq!(super::value_to_length("240"), Some(px(240.).into()));
+    fn test_css() {
+        let html = r#"
+            <style>
+                p { color: #ff0000 }
+                .note { background-color: rgb(0, 0, 255); padding: 4px 8px }
+                #title { text-align: center }
+            </style>
+            <h1 id="title">Title</h1>
+            <p class="note">Hello <span style="font-weight: bold; color: green">world</span> <u>!</u></p>
+        "#;
+        let mut cx = NodeContext::default();
+        let node = super::parse(html, &mut cx).unwrap();
+        let blocks = node.blocks[0].children();
+
+        let BlockNode::Root {
+            children,
+            style: Some(style),
+            ..
+        } = &blocks[0]
+        else {
+            panic!("expected a styled heading, got {:?}", blocks[0]);
+        };
+        assert_eq!(style.text_align, Some(TextAlign::Center));
+        assert!(matches!(children[0], BlockNode::Heading { level: 1, .. }));
+
+        let BlockNode::Root {
+            children,
+            style: Some(style),
+            ..
+        } = &blocks[1]
+        else {
+            panic!("expected a styled paragraph, got {:?}", blocks[1]);
+        };
+        assert_eq!(style.color, Some(gpui::rgb(0xff0000).into()));
+        assert_eq!(style.background, Some(gpui::rgb(0x0000ff).into()));
+        assert_eq!(style.padding.left, Some(px(8.).into()));
+
+        let BlockNode::Paragraph(paragraph) = &children[0] else {
+            panic!("expected a paragraph, got {:?}", children[0]);
+        };
+        let find = |text: &str| {
+            paragraph
+                .children
+                .iter()
+                .find(|node| node.text == text)
+                .unwrap()
+        };
+        let world = find("world");
+        assert!(world.marks.iter().any(|(_, mark)| mark.bold));
+        assert!(
+            world
+                .marks
+                .iter()
+                .any(|(_, mark)| mark.color == Some(gpui::rgb(0x008000).into()))
+        );
+        assert!(find("!").marks.iter().any(|(_, mark)| mark.underline));
+    }
+
+    #[test]
+    fn test_table() {
+        let html = r#"
+            <table>
+                <thead><tr><th colspan="2">Name</th><th align="right">Age</th></tr></thead>
+                <tbody>
+                    <tr><td rowspan="2">A</td><td>B</td><td style="text-align: center">1</td></tr>
+                    <tr><td></td><td>2</td></tr>
+                </tbody>
+            </table>
+        "#;
+        let mut cx = NodeContext::default();
+        let node = super::parse(html, &mut cx).unwrap();
+        let table = node
+            .blocks
+            .iter()
+            .flat_map(|block| std::iter::once(block).chain(block.children()))
+            .find_map(|block| match block {
+                BlockNode::Table(table) => Some(table),
+                _ => None,
+            })
+            .expect("expected a table");
+
+        assert_eq!(table.children[0].children[0].colspan, 2);
+        assert_eq!(table.children[1].children[0].rowspan, 2);
+        assert_eq!(
+            table.children[0].children[1].align,
+            Some(ColumnumnAlign::Right)
+        );
+        assert_eq!(
+            table.children[1].children[2].align,
+            Some(ColumnumnAlign::Center)
+        );
+
+        assert_eq!(
+            table.slots(),
+            vec![
+                vec![
+                    TableSlot::Cell { row: 0, cell: 0 },
+                    TableSlot::SpannedCol,
+                    TableSlot::Cell { row: 0, cell: 1 },
+                ],
+                vec![
+                    TableSlot::Cell { row: 1, cell: 0 },
+                    TableSlot::Cell { row: 1, cell: 1 },
+                    TableSlot::Cell { row: 1, cell: 2 },
+                ],
+                vec![
+                    TableSlot::SpannedRow {
+                        colspan: 1,
+                        last: true
+                    },
+                    TableSlot::Cell { row: 2, cell: 0 },
+                    TableSlot::Cell { row: 2, cell: 1 },
+                ],
+            ]
+        );
+        assert_eq!(table.to_tsv(), "Name\t\tAge\nA\tB\t1\n\t\t2");
+        assert_eq!(table.cells_to_tsv(1..=2, 1..=2), "B\t1\n\t2");
+    }
+
+    #[test]
+    fn test_uncovered_ranges() {
+        assert_eq!(
+            super::uncovered_ranges(10, vec![4..6, 2..5, 8..10].into_iter()),
+            vec![0..2, 6..8]
+        );
+        assert_eq!(super::uncovered_ranges(3, vec![].into_iter()), vec![0..3]);
     }
 
     #[test]
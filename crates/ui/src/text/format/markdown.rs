@@ -1,22 +1,21 @@
 use gpui::SharedString;
-use std::ops::Range;
 use markdown::{
-    ParseOptions,
+    Constructs, ParseOptions,
     mdast::{self, Node},
 };
 
 use crate::{
     highlighter::HighlightTheme,
     text::{
+        component::{ComponentAttrValue, ComponentProps},
         document::ParsedDocument,
+        front_matter::FrontMatter,
+        math::Math,
         node::{
-            self, BlockNode, CodeBlock, ImageNode, InlineNode, LinkMark, NodeContext, Paragraph,
-            Span, Table, TableRow, TextMark,
-        },
-        utils::{
-            encode_uri_component, is_absolute_path, parse_file_ref_token, parse_url_token,
-            split_whitespace_token_ranges, FileRef,
+            self, AlertKind, BlockNode, CodeBlock, ImageNode, InlineNode, LinkMark, NodeContext,
+            Paragraph, Span, Table, TableRow, TextMark,
         },
+        utils::superscript_number,
     },
 };
 
@@ -28,73 +27,80 @@ pub(crate) fn parse(
     cx: &mut NodeContext,
     highlight_theme: &HighlightTheme,
 ) -> Result<ParsedDocument, SharedString> {
-    markdown::to_mdast(&source, &ParseOptions::gfm())
-        .map(|n| ast_to_document(source, n, cx, highlight_theme))
-        .map_err(|e| e.to_string().into())
+    parse_with_constructs(source, constructs(), cx, highlight_theme)
 }
 
+/// Parse MDX into a tree of nodes, the JSX elements are parsed as the components.
+///
+/// Like MDX, the HTML, autolinks and indented code are disabled, because the `<` starts a JSX element
+/// and the children of the elements are usually indented.
+///
+/// If the JSX is invalid, e.g. the element is not closed yet in streaming, the source is parsed
+/// without the JSX elements, they are kept as text until the source is valid.
+pub(crate) fn parse_mdx(
+    source: &str,
+    cx: &mut NodeContext,
+    highlight_theme: &HighlightTheme,
+) -> Result<ParsedDocument, SharedString> {
+    parse_mdx_with_fallback(source, cx, highlight_theme).map(|(document, _)| document)
+}
 
-fn build_inline_code_marks(text: &str, cx: &NodeContext) -> Vec<(Range<usize>, TextMark)> {
-    let mut marks = vec![(0..text.len(), TextMark::default().code())];
-    let options = &cx.style.code_token_links;
-    if !options.enabled || text.is_empty() {
-        return marks;
-    }
-
-    for range in split_whitespace_token_ranges(text) {
-        if range.start >= range.end {
-            continue;
-        }
-        let token = &text[range.clone()];
-        if token.trim().is_empty() {
-            continue;
-        }
-
-        if let Some(url) = parse_url_token(token) {
-            let mut link = LinkMark::default();
-            link.url = url.into();
-            link.requires_modifiers = true;
-            link.decorate = false;
-            marks.push((range.clone(), TextMark::default().link(link)));
-            continue;
-        }
+/// Same as [`parse_mdx`], and returns true if the JSX is invalid and parsed without the JSX elements.
+pub(crate) fn parse_mdx_with_fallback(
+    source: &str,
+    cx: &mut NodeContext,
+    highlight_theme: &HighlightTheme,
+) -> Result<(ParsedDocument, bool), SharedString> {
+    let constructs = Constructs {
+        mdx_jsx_flow: true,
+        mdx_jsx_text: true,
+        html_flow: false,
+        html_text: false,
+        autolink: false,
+        code_indented: false,
+        ..constructs()
+    };
 
-        if let Some(file_ref) = parse_file_ref_token(token) {
-            let can_link = options.worktree_id.is_some() || is_absolute_path(&file_ref.path);
-            if !can_link {
-                continue;
-            }
-            if let Some(url) = build_ctx_open_url(&file_ref, options.worktree_id.as_ref()) {
-                let mut link = LinkMark::default();
-                link.url = url;
-                link.requires_modifiers = true;
-                link.decorate = false;
-                marks.push((range.clone(), TextMark::default().link(link)));
-            }
+    // The fallback is parsed with the initial context, not the one changed by the failed pass.
+    let initial_cx = cx.clone();
+    match parse_with_constructs(source, constructs.clone(), cx, highlight_theme) {
+        Ok(document) => Ok((document, false)),
+        Err(_) => {
+            *cx = initial_cx;
+            let constructs = Constructs {
+                mdx_jsx_flow: false,
+                mdx_jsx_text: false,
+                ..constructs
+            };
+            parse_with_constructs(source, constructs, cx, highlight_theme)
+                .map(|document| (document, true))
         }
     }
-
-    marks
 }
 
-fn build_ctx_open_url(file_ref: &FileRef, worktree_id: Option<&SharedString>) -> Option<SharedString> {
-    let mut params: Vec<String> = Vec::new();
-    if is_absolute_path(&file_ref.path) {
-        params.push(format!("path={}", encode_uri_component(&file_ref.path)));
-    } else {
-        let Some(worktree_id) = worktree_id else {
-            return None;
-        };
-        params.push(format!("worktreeId={}", encode_uri_component(worktree_id.as_ref())));
-        params.push(format!("file={}", encode_uri_component(&file_ref.path)));
-    }
-    if let Some(line) = file_ref.line {
-        params.push(format!("line={}", line));
-    }
-    if let Some(col) = file_ref.col {
-        params.push(format!("col={}", col));
+fn constructs() -> Constructs {
+    Constructs {
+        math_flow: true,
+        math_text: true,
+        frontmatter: true,
+        ..Constructs::gfm()
     }
-    Some(format!("ctx://open?{}", params.join("&")).into())
+}
+
+fn parse_with_constructs(
+    source: &str,
+    constructs: Constructs,
+    cx: &mut NodeContext,
+    highlight_theme: &HighlightTheme,
+) -> Result<ParsedDocument, SharedString> {
+    let options = ParseOptions {
+        constructs,
+        ..ParseOptions::gfm()
+    };
+
+    markdown::to_mdast(&source, &options)
+        .map(|n| ast_to_document(source, n, cx, highlight_theme))
+        .map_err(|e| e.to_string().into())
 }
 
 fn parse_table_row(table: &mut Table, node: &mdast::TableRow, cx: &mut NodeContext) {
@@ -144,70 +150,50 @@ fn parse_paragraph(paragraph: &mut Paragraph, node: &mdast::Node, cx: &mut NodeC
             paragraph.push_str(&val.value)
         }
         Node::Emphasis(val) => {
-            let mut child_paragraph = Paragraph::default();
-            for child in val.children.iter() {
-                text.push_str(&parse_paragraph(&mut child_paragraph, &child, cx));
-            }
-            paragraph.push(
-                InlineNode::new(&text).marks(vec![(0..text.len(), TextMark::default().italic())]),
-            );
+            text = parse_marked(paragraph, &val.children, TextMark::default().italic(), cx);
         }
         Node::Strong(val) => {
-            let mut child_paragraph = Paragraph::default();
-            for child in val.children.iter() {
-                text.push_str(&parse_paragraph(&mut child_paragraph, &child, cx));
-            }
-            paragraph.push(
-                InlineNode::new(&text).marks(vec![(0..text.len(), TextMark::default().bold())]),
-            );
+            text = parse_marked(paragraph, &val.children, TextMark::default().bold(), cx);
         }
         Node::Delete(val) => {
-            let mut child_paragraph = Paragraph::default();
-            for child in val.children.iter() {
-                text.push_str(&parse_paragraph(&mut child_paragraph, &child, cx));
-            }
-            paragraph.push(
-                InlineNode::new(&text)
-                    .marks(vec![(0..text.len(), TextMark::default().strikethrough())]),
+            text = parse_marked(
+                paragraph,
+                &val.children,
+                TextMark::default().strikethrough(),
+                cx,
             );
         }
         Node::InlineCode(val) => {
             text = val.value.clone();
-            let marks = build_inline_code_marks(&text, cx);
-            paragraph.push(InlineNode::new(&text).marks(marks));
+            paragraph.push(
+                InlineNode::new(&text).marks(vec![(0..text.len(), TextMark::default().code())]),
+            );
         }
         Node::Link(val) => {
             let is_ctx = val.url.starts_with("ctx://open?");
-            let link_mark = Some(LinkMark {
+            let link_mark = LinkMark {
                 url: val.url.clone().into(),
                 title: val.title.clone().map(|s| s.into()),
                 requires_modifiers: is_ctx,
                 decorate: !is_ctx,
                 ..Default::default()
-            });
-
-            let mut child_paragraph = Paragraph::default();
-            for child in val.children.iter() {
-                text.push_str(&parse_paragraph(&mut child_paragraph, &child, cx));
-            }
-
-            // FIXME: GPUI InteractiveText does not support inline images yet.
-            // So here we push images to the paragraph directly.
-            for child in child_paragraph.children.iter_mut() {
-                if let Some(image) = child.image.as_mut() {
-                    image.link = link_mark.clone();
-                }
+            };
 
-                child.marks.push((
-                    0..child.text.len(),
-                    TextMark {
-                        link: link_mark.clone(),
-                        ..Default::default()
-                    },
-                ));
-            }
+            text = parse_link(paragraph, &val.children, link_mark, cx);
+        }
+        Node::LinkReference(link) => {
+            // The definitions are collected before parsing, fallback to resolve by the identifier in rendering.
+            let link_mark = cx
+                .link_refs
+                .get(link.identifier.as_str())
+                .cloned()
+                .unwrap_or_else(|| LinkMark {
+                    title: link.label.clone().map(Into::into),
+                    identifier: Some(link.identifier.clone().into()),
+                    ..Default::default()
+                });
 
-            paragraph.merge(child_paragraph);
+            text = parse_link(paragraph, &link.children, link_mark, cx);
         }
         Node::Image(raw) => {
             paragraph.push_image(ImageNode {
@@ -217,11 +203,19 @@ fn parse_paragraph(paragraph: &mut Paragraph, node: &mdast::Node, cx: &mut NodeC
                 ..Default::default()
             });
         }
+        Node::ImageReference(raw) => {
+            if let Some(link) = cx.link_refs.get(raw.identifier.as_str()) {
+                paragraph.push_image(ImageNode {
+                    url: link.url.to_string().into(),
+                    title: link.title.clone(),
+                    alt: Some(raw.alt.clone().into()),
+                    ..Default::default()
+                });
+            }
+        }
         Node::InlineMath(raw) => {
             text = raw.value.clone();
-            paragraph.push(
-                InlineNode::new(&text).marks(vec![(0..text.len(), TextMark::default().code())]),
-            );
+            paragraph.push_math(Math::new(raw.value.clone(), false));
         }
         Node::MdxTextExpression(raw) => {
             text = raw.value.clone();
@@ -252,37 +246,26 @@ fn parse_paragraph(paragraph: &mut Paragraph, node: &mdast::Node, cx: &mut NodeC
                 text.push_str(&val.value);
             }
         },
-        Node::FootnoteReference(foot) => {
-            let prefix = format!("[{}]", foot.identifier);
-            paragraph.push(InlineNode::new(&prefix).marks(vec![(
-                0..prefix.len(),
-                TextMark {
-                    italic: true,
-                    ..Default::default()
-                },
-            )]));
-        }
-        Node::LinkReference(link) => {
-            let mut child_paragraph = Paragraph::default();
-            let mut child_text = String::new();
-            for child in link.children.iter() {
-                child_text.push_str(&parse_paragraph(&mut child_paragraph, child, cx));
+        Node::MdxJsxTextElement(val) => {
+            let mut children = Paragraph::default();
+            for child in val.children.iter() {
+                text.push_str(&parse_paragraph(&mut children, child, cx));
             }
-
+            let props = component_props(val.name.clone(), val.attributes.clone());
+            paragraph.push(InlineNode::component(props, children));
+        }
+        Node::FootnoteReference(foot) => {
+            let number = cx.footnote_number(&foot.identifier);
+            text = superscript_number(number);
             let link_mark = LinkMark {
-                url: "".into(),
-                title: link.label.clone().map(Into::into),
-                identifier: Some(link.identifier.clone().into()),
+                url: format!("#fn-{}", foot.identifier).into(),
+                title: foot.label.clone().map(Into::into),
                 ..Default::default()
             };
-
-            paragraph.push(InlineNode::new(&child_text).marks(vec![(
-                0..child_text.len(),
-                TextMark {
-                    link: Some(link_mark),
-                    ..Default::default()
-                },
-            )]));
+            paragraph.push(
+                InlineNode::new(&text)
+                    .marks(vec![(0..text.len(), TextMark::default().link(link_mark))]),
+            );
         }
         _ => {
             if cfg!(debug_assertions) {
@@ -294,6 +277,121 @@ fn parse_paragraph(paragraph: &mut Paragraph, node: &mdast::Node, cx: &mut NodeC
     text
 }
 
+/// Parse the children of an emphasis, strong or delete node into the `paragraph`, with the `mark` applied.
+///
+/// The child nodes are kept, so the nested marks, links and inline components are not lost.
+fn parse_marked(
+    paragraph: &mut Paragraph,
+    children: &[Node],
+    mark: TextMark,
+    cx: &mut NodeContext,
+) -> String {
+    let mut text = String::new();
+    let mut child_paragraph = Paragraph::default();
+    for child in children.iter() {
+        text.push_str(&parse_paragraph(&mut child_paragraph, child, cx));
+    }
+
+    for child in child_paragraph.children.iter_mut() {
+        child.marks.push((0..child.text.len(), mark.clone()));
+    }
+
+    paragraph.merge(child_paragraph);
+    text
+}
+
+/// Parse the children of a link into the `paragraph`, with the `link_mark` applied.
+fn parse_link(
+    paragraph: &mut Paragraph,
+    children: &[Node],
+    link_mark: LinkMark,
+    cx: &mut NodeContext,
+) -> String {
+    let mut text = String::new();
+    let mut child_paragraph = Paragraph::default();
+    for child in children.iter() {
+        text.push_str(&parse_paragraph(&mut child_paragraph, child, cx));
+    }
+
+    // FIXME: GPUI InteractiveText does not support inline images yet.
+    // So here we push images to the paragraph directly.
+    for child in child_paragraph.children.iter_mut() {
+        if let Some(image) = child.image.as_mut() {
+            image.link = Some(link_mark.clone());
+        }
+
+        child.marks.push((
+            0..child.text.len(),
+            TextMark {
+                link: Some(link_mark.clone()),
+                ..Default::default()
+            },
+        ));
+    }
+
+    paragraph.merge(child_paragraph);
+    text
+}
+
+fn definition_link(def: &mdast::Definition) -> LinkMark {
+    let is_ctx = def.url.starts_with("ctx://open?");
+    LinkMark {
+        url: def.url.clone().into(),
+        identifier: Some(def.identifier.clone().into()),
+        title: def.title.clone().map(Into::into),
+        requires_modifiers: is_ctx,
+        decorate: !is_ctx,
+        ..Default::default()
+    }
+}
+
+/// Collect the link reference definitions in the whole tree,
+/// so the reference links and images before the definitions can be resolved.
+fn collect_definitions(node: &mdast::Node, cx: &mut NodeContext) {
+    if let Node::Definition(def) = node {
+        // The first definition takes precedence, same as CommonMark.
+        if !cx.link_refs.contains_key(def.identifier.as_str()) {
+            cx.add_ref(def.identifier.clone().into(), definition_link(def));
+        }
+    }
+
+    for child in node.children().into_iter().flatten() {
+        collect_definitions(child, cx);
+    }
+}
+
+/// Take the GFM alert marker, e.g. `[!NOTE]`, from the first line of the blockquote.
+fn take_alert_marker(children: &mut Vec<Node>) -> Option<AlertKind> {
+    let Some(Node::Paragraph(paragraph)) = children.first_mut() else {
+        return None;
+    };
+    let Some(Node::Text(text)) = paragraph.children.first_mut() else {
+        return None;
+    };
+
+    let (marker, rest) = text
+        .value
+        .split_once('\n')
+        .unwrap_or((text.value.as_str(), ""));
+    let kind = AlertKind::parse(marker.trim_end())?;
+    let rest = rest.to_string();
+    if rest.is_empty() {
+        paragraph.children.remove(0);
+        // The hard break after the marker, e.g. `[!NOTE]\`.
+        if matches!(paragraph.children.first(), Some(Node::Break(_))) {
+            paragraph.children.remove(0);
+        }
+    } else {
+        text.value = rest;
+    }
+
+    if paragraph.children.is_empty() {
+        children.remove(0);
+    }
+
+    Some(kind)
+}
+
 fn ast_to_document(
     source: &str,
     root: mdast::Node,
@@ -305,14 +403,47 @@ fn ast_to_document(
         _ => panic!("expected root node"),
     };
 
+    for child in root.children.iter() {
+        collect_definitions(child, cx);
+    }
+
     let blocks = root
         .children
         .into_iter()
         .map(|c| ast_to_node(c, cx, highlight_theme))
         .collect();
-    ParsedDocument {
+    let mut document = ParsedDocument {
         source: source.to_string().into(),
         blocks,
+    };
+    document.update_footnote_section(0, vec![]);
+    document
+}
+
+fn component_props(
+    name: Option<String>,
+    attributes: Vec<mdast::AttributeContent>,
+) -> ComponentProps {
+    ComponentProps {
+        name: name.unwrap_or_default().into(),
+        attrs: attributes
+            .into_iter()
+            .filter_map(|attr| match attr {
+                mdast::AttributeContent::Property(attr) => Some((
+                    attr.name.into(),
+                    attr.value.map(|value| match value {
+                        mdast::AttributeValue::Literal(value) => {
+                            ComponentAttrValue::Literal(value.into())
+                        }
+                        mdast::AttributeValue::Expression(expr) => {
+                            ComponentAttrValue::Expression(expr.value.into())
+                        }
+                    }),
+                )),
+                // The spread attributes like `{...props}` are not supported.
+                mdast::AttributeContent::Expression(_) => None,
+            })
+            .collect(),
     }
 }
 
@@ -340,15 +471,21 @@ fn ast_to_node(
             paragraph.span = new_span(val.position, cx);
             BlockNode::Paragraph(paragraph)
         }
-        Node::Blockquote(val) => {
+        Node::Blockquote(mut val) => {
+            let alert = take_alert_marker(&mut val.children);
             let children = val
                 .children
                 .into_iter()
                 .map(|c| ast_to_node(c, cx, highlight_theme))
                 .collect();
-            BlockNode::Blockquote {
-                children,
-                span: new_span(val.position, cx),
+            let span = new_span(val.position, cx);
+            match alert {
+                Some(kind) => BlockNode::Alert {
+                    kind,
+                    children,
+                    span,
+                },
+                None => BlockNode::Blockquote { children, span },
             }
         }
         Node::List(list) => {
@@ -395,18 +532,18 @@ fn ast_to_node(
             BlockNode::Heading {
                 level: val.depth,
                 children: paragraph,
+                slug: SharedString::default(),
                 span: new_span(val.position, cx),
             }
         }
-        Node::Math(val) => BlockNode::CodeBlock(CodeBlock::new(
-            val.value.into(),
-            None,
-            highlight_theme,
-            new_span(val.position, cx),
-        )),
+        Node::Math(val) => BlockNode::Math {
+            math: Math::new(val.value, true),
+            span: new_span(val.position, cx),
+        },
         Node::Html(val) => match super::html::parse(&val.value, cx) {
             Ok(el) => BlockNode::Root {
                 children: el.blocks,
+                style: None,
                 span: new_span(val.position, cx),
             },
             Err(err) => {
@@ -423,18 +560,14 @@ fn ast_to_node(
             highlight_theme,
             new_span(val.position, cx),
         )),
-        Node::Yaml(val) => BlockNode::CodeBlock(CodeBlock::new(
-            val.value.into(),
-            Some("yml".into()),
-            highlight_theme,
-            new_span(val.position, cx),
-        )),
-        Node::Toml(val) => BlockNode::CodeBlock(CodeBlock::new(
-            val.value.into(),
-            Some("toml".into()),
-            highlight_theme,
-            new_span(val.position, cx),
-        )),
+        Node::Yaml(val) => BlockNode::FrontMatter {
+            front_matter: FrontMatter::yaml(val.value),
+            span: new_span(val.position, cx),
+        },
+        Node::Toml(val) => BlockNode::FrontMatter {
+            front_matter: FrontMatter::toml(val.value),
+            span: new_span(val.position, cx),
+        },
         Node::MdxJsxTextElement(val) => {
             let mut paragraph = Paragraph::default();
             val.children.iter().for_each(|c| {
@@ -443,14 +576,15 @@ fn ast_to_node(
             paragraph.span = new_span(val.position, cx);
             BlockNode::Paragraph(paragraph)
         }
-        Node::MdxJsxFlowElement(val) => {
-            let mut paragraph = Paragraph::default();
-            val.children.iter().for_each(|c| {
-                parse_paragraph(&mut paragraph, c, cx);
-            });
-            paragraph.span = new_span(val.position, cx);
-            BlockNode::Paragraph(paragraph)
-        }
+        Node::MdxJsxFlowElement(val) => BlockNode::Component {
+            props: component_props(val.name, val.attributes),
+            children: val
+                .children
+                .into_iter()
+                .map(|c| ast_to_node(c, cx, highlight_theme))
+                .collect(),
+            span: new_span(val.position, cx),
+        },
         Node::ThematicBreak(val) => BlockNode::Divider {
             span: new_span(val.position, cx),
         },
@@ -472,36 +606,24 @@ fn ast_to_node(
             BlockNode::Table(table)
         }
         Node::FootnoteDefinition(def) => {
-            let mut paragraph = Paragraph::default();
-            let prefix = format!("[{}]: ", def.identifier);
-            paragraph.push(InlineNode::new(&prefix).marks(vec![(
-                0..prefix.len(),
-                TextMark {
-                    italic: true,
-                    ..Default::default()
-                },
-            )]));
+            let number = cx.footnote_number(&def.identifier);
+            let span = new_span(def.position, cx);
+            let children = def
+                .children
+                .into_iter()
+                .map(|c| ast_to_node(c, cx, highlight_theme))
+                .collect();
 
-            def.children.iter().for_each(|c| {
-                parse_paragraph(&mut paragraph, c, cx);
-            });
-            paragraph.span = new_span(def.position, cx);
-            BlockNode::Paragraph(paragraph)
+            BlockNode::Footnote {
+                identifier: def.identifier.into(),
+                number,
+                children,
+                section_start: false,
+                span,
+            }
         }
         Node::Definition(def) => {
-            let is_ctx = def.url.starts_with("ctx://open?");
-            cx.add_ref(
-                def.identifier.clone().into(),
-                LinkMark {
-                    url: def.url.clone().into(),
-                    identifier: Some(def.identifier.clone().into()),
-                    title: def.title.clone().map(Into::into),
-                    requires_modifiers: is_ctx,
-                    decorate: !is_ctx,
-                    ..Default::default()
-                },
-            );
-
+            // The link reference is collected by `collect_definitions`.
             BlockNode::Definition {
                 identifier: def.identifier.clone().into(),
                 url: def.url.clone().into(),
@@ -517,3 +639,240 @@ fn ast_to_node(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use gpui::SharedUri;
+
+    use crate::{
+        highlighter::HighlightTheme,
+        text::{
+            FrontMatterKind,
+            document::ParsedDocument,
+            node::{AlertKind, BlockNode, NodeContext},
+        },
+    };
+
+    fn parse(source: &str, cx: &mut NodeContext) -> ParsedDocument {
+        super::parse(source, cx, &HighlightTheme::default_light()).unwrap()
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let mut cx = NodeContext::default();
+        let doc = parse("---\ntitle: Hello\ntags: [a, b]\n---\n\n# Heading", &mut cx);
+        assert_eq!(doc.blocks.len(), 2);
+        let front_matter = doc.front_matter().unwrap();
+        assert_eq!(front_matter.kind, FrontMatterKind::Yaml);
+        assert_eq!(
+            front_matter.get("title").and_then(|v| v.as_str()),
+            Some("Hello")
+        );
+        assert_eq!(
+            doc.blocks[0].to_markdown(),
+            "---\ntitle: Hello\ntags: [a, b]\n---"
+        );
+
+        let doc = parse("+++\ntitle = \"Hello\"\n+++\n", &mut cx);
+        let front_matter = doc.front_matter().unwrap();
+        assert_eq!(front_matter.kind, FrontMatterKind::Toml);
+        assert_eq!(
+            front_matter.get("title").and_then(|v| v.as_str()),
+            Some("Hello")
+        );
+
+        // Only at the start of the document.
+        let doc = parse("# Heading\n\n---\ntitle: Hello\n---", &mut cx);
+        assert!(doc.front_matter().is_none());
+    }
+
+    #[test]
+    fn test_mdx_components() {
+        let mut cx = NodeContext::default();
+        let doc = super::parse_mdx(
+            "<Alert kind=\"warning\">\n  Be **careful**.\n</Alert>\n\n<Badge count={3} dot />\n\nHello <Tag>new</Tag> world",
+            &mut cx,
+            &HighlightTheme::default_light(),
+        )
+        .unwrap();
+        assert_eq!(doc.blocks.len(), 3);
+
+        let BlockNode::Component {
+            props, children, ..
+        } = &doc.blocks[0]
+        else {
+            panic!("expected component, got {:?}", doc.blocks[0]);
+        };
+        assert_eq!(props.name.as_str(), "Alert");
+        assert_eq!(props.get("kind"), Some("warning"));
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].to_markdown(), "Be **careful**.");
+
+        let BlockNode::Component {
+            props, children, ..
+        } = &doc.blocks[1]
+        else {
+            panic!("expected component, got {:?}", doc.blocks[1]);
+        };
+        assert_eq!(props.name.as_str(), "Badge");
+        assert_eq!(props.parse::<usize>("count"), Some(3));
+        assert!(props.flag("dot"));
+        assert!(children.is_empty());
+        assert_eq!(doc.blocks[1].to_markdown(), "<Badge count={3} dot />");
+
+        let BlockNode::Paragraph(paragraph) = &doc.blocks[2] else {
+            panic!("expected paragraph, got {:?}", doc.blocks[2]);
+        };
+        assert_eq!(paragraph.children.len(), 3);
+        let component = paragraph.children[1].component.as_ref().unwrap();
+        assert_eq!(component.props.name.as_str(), "Tag");
+        assert_eq!(component.children.text(), "new");
+        assert_eq!(paragraph.text(), "Hello new world");
+        assert_eq!(doc.blocks[2].to_markdown(), "Hello <Tag>new</Tag> world");
+
+        let doc = super::parse_mdx(
+            "Unread <Badge count={3} />",
+            &mut cx,
+            &HighlightTheme::default_light(),
+        )
+        .unwrap();
+        let BlockNode::Paragraph(paragraph) = &doc.blocks[0] else {
+            panic!("expected paragraph, got {:?}", doc.blocks[0]);
+        };
+        let component = paragraph.children[1].component.as_ref().unwrap();
+        assert_eq!(component.props.parse::<usize>("count"), Some(3));
+        assert!(component.children.is_empty());
+        assert_eq!(doc.blocks[0].to_markdown(), "Unread <Badge count={3} />");
+
+        let doc = super::parse_mdx(
+            "Hello **<Tag>new</Tag> world**",
+            &mut cx,
+            &HighlightTheme::default_light(),
+        )
+        .unwrap();
+        let BlockNode::Paragraph(paragraph) = &doc.blocks[0] else {
+            panic!("expected paragraph, got {:?}", doc.blocks[0]);
+        };
+        assert_eq!(paragraph.children.len(), 3);
+        let node = &paragraph.children[1];
+        assert_eq!(node.component.as_ref().unwrap().props.name.as_str(), "Tag");
+        assert!(node.marks.iter().any(|(_, mark)| mark.bold));
+        assert!(
+            paragraph.children[2]
+                .marks
+                .iter()
+                .any(|(_, mark)| mark.bold)
+        );
+        assert_eq!(paragraph.text(), "Hello new world");
+    }
+
+    #[test]
+    fn test_mdx_unclosed_element() {
+        let mut cx = NodeContext::default();
+        let doc = super::parse_mdx(
+            "Intro\n\n<Alert kind=\"warning\">\nBe careful",
+            &mut cx,
+            &HighlightTheme::default_light(),
+        )
+        .unwrap();
+        assert_eq!(doc.blocks.len(), 2);
+        let BlockNode::Paragraph(paragraph) = &doc.blocks[1] else {
+            panic!("expected paragraph, got {:?}", doc.blocks[1]);
+        };
+        assert_eq!(paragraph.text(), "<Alert kind=\"warning\">\nBe careful");
+    }
+
+    #[test]
+    fn test_alert() {
+        let mut cx = NodeContext::default();
+        let doc = parse(
+            "> [!warning]\n> Be careful.\n\n> [!TODO]\n> Not an alert.",
+            &mut cx,
+        );
+
+        assert!(matches!(
+            &doc.blocks[0],
+            BlockNode::Alert {
+                kind: AlertKind::Warning,
+                children,
+                ..
+            } if children.len() == 1
+        ));
+        assert_eq!(doc.blocks[0].to_markdown(), "> [!WARNING]\n> Be careful.");
+        assert!(matches!(&doc.blocks[1], BlockNode::Blockquote { .. }));
+    }
+
+    #[test]
+    fn test_footnotes_and_references() {
+        let mut cx = NodeContext::default();
+        let doc = parse(
+            "See [**docs**][1] and ![logo].[^b][^a]\n\n[^a]: A\n\n[^b]: B\n\nMore text.\n\n[1]: https://example.com\n\n[logo]: https://example.com/logo.png",
+            &mut cx,
+        );
+
+        let BlockNode::Paragraph(paragraph) = &doc.blocks[0] else {
+            panic!("expected paragraph");
+        };
+        assert!(paragraph.has_link("https://example.com"));
+        assert!(paragraph.has_link("#fn-a"));
+        assert!(paragraph.has_link("#fn-b"));
+        let logo = SharedUri::from("https://example.com/logo.png");
+        assert!(
+            paragraph
+                .children
+                .iter()
+                .any(|node| node.image.as_ref().is_some_and(|image| image.url == logo))
+        );
+
+        // Numbered in the order of the first reference,
+        // and rendered in a trailing section sorted by the number.
+        assert_eq!(cx.footnotes, vec!["b", "a"]);
+        assert!(matches!(&doc.blocks[1], BlockNode::Paragraph(_)));
+        assert_eq!(doc.footnote_section_start(), 4);
+        assert!(matches!(
+            &doc.blocks[4],
+            BlockNode::Footnote {
+                number: 1,
+                section_start: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &doc.blocks[5],
+            BlockNode::Footnote {
+                number: 2,
+                section_start: false,
+                ..
+            }
+        ));
+
+        assert_eq!(doc.anchor_block("fn-b"), Some(4));
+        assert_eq!(doc.anchor_block("fn-a"), Some(5));
+        assert_eq!(doc.anchor_block("fnref-a"), Some(0));
+        assert_eq!(doc.anchor_block("fn-c"), None);
+    }
+
+    #[test]
+    fn test_heading_slugs() {
+        let mut cx = NodeContext::default();
+        let mut doc = parse(
+            "# Intro\n\n## Usage\n\n### Options\n\n## Usage\n\ntext\n\n# API\n\n[Go](#usage-1)",
+            &mut cx,
+        );
+        doc.update_heading_slugs();
+
+        let toc = doc.table_of_contents();
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].slug, "intro");
+        assert_eq!(toc[0].children.len(), 2);
+        assert_eq!(toc[0].children[0].slug, "usage");
+        assert_eq!(toc[0].children[0].children[0].title, "Options");
+        assert_eq!(toc[0].children[1].slug, "usage-1");
+        assert_eq!(toc[1].slug, "api");
+
+        assert_eq!(doc.anchor_block("usage-1"), Some(3));
+        assert_eq!(doc.heading_before(0), Some("intro".into()));
+        assert_eq!(doc.heading_before(4), Some("usage-1".into()));
+        assert_eq!(doc.heading_before(6), Some("api".into()));
+    }
+}
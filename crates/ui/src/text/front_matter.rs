@@ -0,0 +1,453 @@
+//! The front matter at the start of the Markdown, e.g.:
+//!
+//! ```md
+//! ---
+//! title: Hello
+//! tags: [gpui, markdown]
+//! ---
+//! ```
+//!
+//! Only a common subset of YAML and TOML is supported: the scalars, the inline and block lists,
+//! the nested mappings in YAML and the tables in TOML.
+use std::fmt::Display;
+
+use gpui::SharedString;
+
+/// The format of the [`FrontMatter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterKind {
+    /// The YAML front matter, fenced by `---`.
+    Yaml,
+    /// The TOML front matter, fenced by `+++`.
+    Toml,
+}
+
+/// A value in the [`FrontMatter`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrontMatterValue {
+    String(SharedString),
+    Number(f64),
+    Bool(bool),
+    List(Vec<FrontMatterValue>),
+    Map(Vec<(SharedString, FrontMatterValue)>),
+}
+
+impl FrontMatterValue {
+    /// Returns the string if the value is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if the value is a number.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the bool if the value is a bool.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the items if the value is a list.
+    pub fn as_list(&self) -> Option<&[FrontMatterValue]> {
+        match self {
+            Self::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the `key` if the value is a map.
+    pub fn get(&self, key: &str) -> Option<&FrontMatterValue> {
+        match self {
+            Self::Map(fields) => fields
+                .iter()
+                .find(|(k, _)| k.as_str() == key)
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Parse a scalar or an inline list, e.g. `"Hello"`, `1.5`, `true`, `[a, "b"]`.
+    fn parse_scalar(text: &str) -> Self {
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            return Self::List(
+                split_top_level(inner, ',')
+                    .into_iter()
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(Self::parse_scalar)
+                    .collect(),
+            );
+        }
+
+        if let Some(s) = unquote(text) {
+            return Self::String(s.to_string().into());
+        }
+
+        match text {
+            "true" => Self::Bool(true),
+            "false" => Self::Bool(false),
+            _ => match text.parse::<f64>() {
+                Ok(n) if n.is_finite() => Self::Number(n),
+                _ => Self::String(text.to_string().into()),
+            },
+        }
+    }
+}
+
+impl Display for FrontMatterValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "{}", s),
+            Self::Number(n) => write!(f, "{}", n),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::List(items) => {
+                for (ix, item) in items.iter().enumerate() {
+                    if ix > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                Ok(())
+            }
+            Self::Map(fields) => {
+                for (ix, (key, value)) in fields.iter().enumerate() {
+                    if ix > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The parsed front matter of the Markdown, see [`TextViewState::front_matter`].
+///
+/// [`TextViewState::front_matter`]: crate::text::TextViewState::front_matter
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatter {
+    pub kind: FrontMatterKind,
+    /// The raw text between the fences.
+    pub source: SharedString,
+    /// The top-level fields, in the order of the source.
+    pub fields: Vec<(SharedString, FrontMatterValue)>,
+}
+
+impl FrontMatter {
+    /// Parse the YAML front matter.
+    pub(crate) fn yaml(source: impl Into<SharedString>) -> Self {
+        let source = source.into();
+        let lines = source
+            .lines()
+            .filter_map(|line| {
+                let text = line.trim_start();
+                if text.is_empty() || text.starts_with('#') {
+                    return None;
+                }
+                Some(YamlLine {
+                    indent: line.len() - text.len(),
+                    text: text.trim_end(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut pos = 0;
+        let mut fields = vec![];
+        while pos < lines.len() {
+            fields.extend(parse_yaml_map(&lines, &mut pos, lines[pos].indent));
+        }
+
+        Self {
+            kind: FrontMatterKind::Yaml,
+            source,
+            fields,
+        }
+    }
+
+    /// Parse the TOML front matter.
+    pub(crate) fn toml(source: impl Into<SharedString>) -> Self {
+        let source = source.into();
+        let mut fields: Vec<(SharedString, FrontMatterValue)> = vec![];
+        let mut table: Option<SharedString> = None;
+        let mut pending = String::new();
+        for line in source.lines() {
+            let line = line.trim();
+            if pending.is_empty() && (line.is_empty() || line.starts_with('#')) {
+                continue;
+            }
+
+            if pending.is_empty() && line.starts_with('[') && line.ends_with(']') {
+                let name = line.trim_matches(|c| c == '[' || c == ']').trim();
+                let name: SharedString = unquote(name).unwrap_or(name).to_string().into();
+                if !fields.iter().any(|(key, _)| key == &name) {
+                    fields.push((name.clone(), FrontMatterValue::Map(vec![])));
+                }
+                table = Some(name);
+                continue;
+            }
+
+            // Join the lines of the multi-line arrays.
+            pending.push_str(line);
+            pending.push(' ');
+            if pending.matches('[').count() > pending.matches(']').count() {
+                continue;
+            }
+
+            let line = std::mem::take(&mut pending);
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            let key: SharedString = unquote(key).unwrap_or(key).to_string().into();
+            let value = FrontMatterValue::parse_scalar(strip_comment(value));
+
+            let target = match &table {
+                Some(name) => match fields.iter_mut().find(|(k, _)| k == name) {
+                    Some((_, FrontMatterValue::Map(table_fields))) => table_fields,
+                    _ => continue,
+                },
+                None => &mut fields,
+            };
+            target.push((key, value));
+        }
+
+        Self {
+            kind: FrontMatterKind::Toml,
+            source,
+            fields,
+        }
+    }
+
+    /// Returns the value of the top-level `key`, e.g. `title`.
+    pub fn get(&self, key: &str) -> Option<&FrontMatterValue> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k.as_str() == key)
+            .map(|(_, v)| v)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct YamlLine<'a> {
+    indent: usize,
+    text: &'a str,
+}
+
+/// Parse the `key: value` lines in the same `indent`.
+fn parse_yaml_map(
+    lines: &[YamlLine],
+    pos: &mut usize,
+    indent: usize,
+) -> Vec<(SharedString, FrontMatterValue)> {
+    let mut fields = vec![];
+    while let Some(line) = lines.get(*pos) {
+        if line.indent < indent {
+            break;
+        }
+        *pos += 1;
+
+        // Skip the unexpected indented lines.
+        if line.indent > indent {
+            continue;
+        }
+        let Some((key, value)) = split_yaml_key(line.text) else {
+            continue;
+        };
+
+        let value = match value {
+            "" => parse_yaml_block(lines, pos, indent),
+            "|" | "|-" | ">" | ">-" => {
+                let mut text = vec![];
+                while let Some(line) = lines.get(*pos).filter(|line| line.indent > indent) {
+                    text.push(line.text);
+                    *pos += 1;
+                }
+                let separator = if value.starts_with('|') { "\n" } else { " " };
+                FrontMatterValue::String(text.join(separator).into())
+            }
+            _ => FrontMatterValue::parse_scalar(strip_comment(value)),
+        };
+        fields.push((key.into(), value));
+    }
+
+    fields
+}
+
+/// Parse the nested value of a key with empty value, a block list or a mapping.
+fn parse_yaml_block(lines: &[YamlLine], pos: &mut usize, indent: usize) -> FrontMatterValue {
+    let Some(&next) = lines.get(*pos) else {
+        return FrontMatterValue::String("".into());
+    };
+
+    let is_item = |line: &YamlLine| line.text == "-" || line.text.starts_with("- ");
+    // The items of the block list can be in the same indent of the key.
+    if is_item(&next) && next.indent >= indent {
+        let mut items = vec![];
+        while let Some(line) = lines
+            .get(*pos)
+            .filter(|line| line.indent == next.indent && is_item(*line))
+        {
+            *pos += 1;
+            let item = line.text[1..].trim();
+            items.push(if item.is_empty() {
+                parse_yaml_block(lines, pos, line.indent + 1)
+            } else {
+                FrontMatterValue::parse_scalar(strip_comment(item))
+            });
+        }
+        return FrontMatterValue::List(items);
+    }
+
+    if next.indent > indent {
+        return FrontMatterValue::Map(parse_yaml_map(lines, pos, next.indent));
+    }
+
+    FrontMatterValue::String("".into())
+}
+
+/// Split the `key: value` line, the `:` must be followed by a space or the end.
+fn split_yaml_key(text: &str) -> Option<(SharedString, &str)> {
+    let key_end = if let Some(quote @ ('"' | '\'')) = text.chars().next() {
+        text[1..].find(quote)? + 2
+    } else {
+        0
+    };
+
+    let colon = text[key_end..]
+        .match_indices(':')
+        .map(|(ix, _)| key_end + ix)
+        .find(|&ix| text[ix + 1..].is_empty() || text[ix + 1..].starts_with(' '))?;
+    let key = text[..colon].trim();
+    let key = unquote(key).unwrap_or(key);
+    Some((key.to_string().into(), text[colon + 1..].trim()))
+}
+
+/// Returns the inner text of the quoted string.
+fn unquote(text: &str) -> Option<&str> {
+    ['"', '\''].into_iter().find_map(|quote| {
+        text.strip_prefix(quote)
+            .and_then(|s| s.strip_suffix(quote))
+            .filter(|_| text.len() >= 2)
+    })
+}
+
+/// Remove the trailing ` # comment` that is not in quotes.
+fn strip_comment(text: &str) -> &str {
+    let text = text.trim();
+    split_top_level(text, '#')
+        .into_iter()
+        .next()
+        .filter(|first| first.len() < text.len() && first.ends_with(' '))
+        .map_or(text, str::trim)
+}
+
+/// Split the text by the `separator` that is not in quotes or brackets.
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (ix, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth = depth.saturating_sub(1),
+            (None, c) if c == separator && depth == 0 => {
+                parts.push(&text[start..ix]);
+                start = ix + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrontMatter, FrontMatterKind, FrontMatterValue};
+
+    #[test]
+    fn test_yaml() {
+        let front_matter = FrontMatter::yaml(
+            "title: \"Hello: World\"\nversion: 1.5\ndraft: false # not published\ntags: [gpui, 'markdown']\nauthors:\n  - Alice\n  - Bob\nmeta:\n  layout: doc\n  url: https://example.com\ndescription: |\n  Line 1\n  Line 2\nempty:",
+        );
+        assert_eq!(front_matter.kind, FrontMatterKind::Yaml);
+        assert_eq!(
+            front_matter.get("title").and_then(|v| v.as_str()),
+            Some("Hello: World")
+        );
+        assert_eq!(
+            front_matter.get("version").and_then(|v| v.as_number()),
+            Some(1.5)
+        );
+        assert_eq!(
+            front_matter.get("draft").and_then(|v| v.as_bool()),
+            Some(false)
+        );
+        assert_eq!(
+            front_matter.get("tags").map(|v| v.to_string()).as_deref(),
+            Some("gpui, markdown")
+        );
+        assert_eq!(
+            front_matter.get("authors"),
+            Some(&FrontMatterValue::List(vec![
+                FrontMatterValue::String("Alice".into()),
+                FrontMatterValue::String("Bob".into()),
+            ]))
+        );
+        let meta = front_matter.get("meta").unwrap();
+        assert_eq!(meta.get("layout").and_then(|v| v.as_str()), Some("doc"));
+        assert_eq!(
+            meta.get("url").and_then(|v| v.as_str()),
+            Some("https://example.com")
+        );
+        assert_eq!(
+            front_matter.get("description").and_then(|v| v.as_str()),
+            Some("Line 1\nLine 2")
+        );
+        assert_eq!(front_matter.get("empty").and_then(|v| v.as_str()), Some(""));
+        assert_eq!(front_matter.fields.len(), 8);
+    }
+
+    #[test]
+    fn test_toml() {
+        let front_matter = FrontMatter::toml(
+            "title = \"Hello # World\"\nweight = 10 # order\ntags = [\n  \"gpui\",\n  \"markdown\",\n]\n\n[extra]\ntoc = true",
+        );
+        assert_eq!(front_matter.kind, FrontMatterKind::Toml);
+        assert_eq!(
+            front_matter.get("title").and_then(|v| v.as_str()),
+            Some("Hello # World")
+        );
+        assert_eq!(
+            front_matter.get("weight").and_then(|v| v.as_number()),
+            Some(10.)
+        );
+        assert_eq!(
+            front_matter.get("tags").map(|v| v.to_string()).as_deref(),
+            Some("gpui, markdown")
+        );
+        assert_eq!(
+            front_matter
+                .get("extra")
+                .and_then(|v| v.get("toc"))
+                .and_then(|v| v.as_bool()),
+            Some(true)
+        );
+    }
+}
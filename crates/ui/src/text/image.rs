@@ -0,0 +1,403 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use gpui::{
+    AbsoluteLength, AnyElement, App, AppContext as _, Context, DefiniteLength, ElementId, Image,
+    ImageFormat, ImageSource, ImgResourceLoader, InteractiveElement as _, IntoElement, ObjectFit,
+    ParentElement as _, Pixels, Render, RenderOnce, Resource, ScrollWheelEvent, SharedString,
+    SharedUri, Size, StatefulInteractiveElement as _, Styled, StyledImage as _, Window, div, img,
+    prelude::FluentBuilder as _, px, relative, size,
+};
+
+use crate::{
+    ActiveTheme as _, Disableable as _, Icon, IconName, Sizable as _, WindowExt as _,
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    skeleton::Skeleton,
+    text::{
+        node::{ImageNode, NodeContext},
+        utils::{
+            decode_base64, decode_uri_component, decode_uri_component_bytes, is_absolute_path,
+        },
+    },
+    tooltip::Tooltip,
+    v_flex,
+};
+
+const MIN_SCALE: f32 = 0.25;
+const MAX_SCALE: f32 = 8.;
+const SCALE_STEP: f32 = 1.25;
+
+thread_local! {
+    /// The natural sizes of the loaded images, to reserve the box before the image is loaded again,
+    /// e.g. the block is rendered again after scrolling out of the viewport.
+    static NATURAL_SIZES: RefCell<HashMap<ImageLocation, Size<Pixels>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Decode the image in the `data:` URI, e.g. `data:image/png;base64,...` or `data:image/svg+xml,%3Csvg...`.
+pub(super) fn decode_data_uri(url: &str) -> Option<Arc<Image>> {
+    let (meta, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mut params = meta.split(';');
+    let format = match params.next()?.trim().to_ascii_lowercase().as_str() {
+        "image/png" => ImageFormat::Png,
+        "image/jpeg" | "image/jpg" => ImageFormat::Jpeg,
+        "image/gif" => ImageFormat::Gif,
+        "image/webp" => ImageFormat::Webp,
+        "image/svg+xml" => ImageFormat::Svg,
+        "image/bmp" => ImageFormat::Bmp,
+        "image/tiff" => ImageFormat::Tiff,
+        _ => return None,
+    };
+
+    let data = decode_uri_component_bytes(data);
+    let bytes = if params.any(|param| param.trim().eq_ignore_ascii_case("base64")) {
+        decode_base64(std::str::from_utf8(&data).ok()?)?
+    } else {
+        data
+    };
+    Some(Arc::new(Image::from_bytes(format, bytes)))
+}
+
+/// The location of the image to load.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ImageLocation {
+    Uri(SharedUri),
+    Path(PathBuf),
+}
+
+/// Resolve the image url, the relative paths are resolved against the `base_path`.
+///
+/// Without the `base_path`, the relative paths are loaded as is.
+fn resolve_image_url(url: &str, base_path: Option<&Path>) -> ImageLocation {
+    if let Some(path) = url.strip_prefix("file://") {
+        return ImageLocation::Path(decode_uri_component(path).into());
+    }
+    if url.contains("://") || url.starts_with("data:") {
+        return ImageLocation::Uri(url.to_string().into());
+    }
+
+    // The query and fragment are not part of the file path, e.g. `image.svg#icon`.
+    let path = decode_uri_component(url.split(['?', '#']).next().unwrap_or_default());
+    if is_absolute_path(&path) {
+        return ImageLocation::Path(path.into());
+    }
+
+    match base_path {
+        Some(base_path) => ImageLocation::Path(base_path.join(path)),
+        None => ImageLocation::Uri(url.to_string().into()),
+    }
+}
+
+impl ImageNode {
+    /// Returns the location and the source to load the image,
+    /// the SVG images are rendered by [`gpui::img`] as well.
+    fn source(&self, base_path: Option<&Path>) -> (ImageLocation, ImageSource) {
+        if let Some(data) = &self.data {
+            return (
+                ImageLocation::Uri(self.url.clone()),
+                ImageSource::Image(data.clone()),
+            );
+        }
+
+        let location = resolve_image_url(&self.url.to_string(), base_path);
+        let source = match &location {
+            ImageLocation::Uri(uri) => ImageSource::Resource(Resource::Uri(uri.clone())),
+            ImageLocation::Path(path) => ImageSource::Resource(Resource::Path(path.clone().into())),
+        };
+        (location, source)
+    }
+
+    /// Returns the natural size of the image if it has been loaded, cached by the `location`.
+    fn natural_size(
+        location: &ImageLocation,
+        source: &ImageSource,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Size<Pixels>> {
+        if let Some(size) = NATURAL_SIZES.with_borrow(|sizes| sizes.get(location).copied()) {
+            return Some(size);
+        }
+
+        let image = match source {
+            ImageSource::Resource(resource) => {
+                window.use_asset::<ImgResourceLoader>(resource, cx)?.ok()?
+            }
+            ImageSource::Image(image) => image.clone().use_render_image(window, cx)?,
+            _ => return None,
+        };
+        let image_size = image.size(0);
+        let natural_size = size(
+            px(image_size.width.0 as f32),
+            px(image_size.height.0 as f32),
+        );
+        NATURAL_SIZES.with_borrow_mut(|sizes| sizes.insert(location.clone(), natural_size));
+        Some(natural_size)
+    }
+
+    /// Returns the aspect ratio if both the width and height are in pixels, e.g. `<img width="100" height="50">`.
+    fn aspect_ratio(&self) -> Option<f32> {
+        match (self.width?, self.height?) {
+            (
+                DefiniteLength::Absolute(AbsoluteLength::Pixels(width)),
+                DefiniteLength::Absolute(AbsoluteLength::Pixels(height)),
+            ) if height > px(0.) => Some(f32::from(width) / f32::from(height)),
+            _ => None,
+        }
+    }
+
+    /// Render the image, click to open the link or the zoomable preview.
+    ///
+    /// The image is loaded when rendered, it's lazy only if the blocks are virtualized, that is
+    /// the `scrollable` TextView, or the TextView clipped by a scrolling parent,
+    /// otherwise all the images are loaded at once.
+    ///
+    /// The box of the image is reserved by the size attributes, or the natural size cached
+    /// after the first load, to avoid the layout jumps.
+    pub(super) fn render(
+        &self,
+        id: impl Into<ElementId>,
+        node_cx: &NodeContext,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> AnyElement {
+        let (location, source) = self.source(node_cx.style.image_base_path.as_deref());
+        let natural_size = Self::natural_size(&location, &source, window, cx)
+            .filter(|size| size.width > px(0.) && size.height > px(0.));
+        let alt: SharedString = self
+            .alt
+            .clone()
+            .filter(|alt| !alt.is_empty())
+            .unwrap_or_else(|| self.url.to_string().into());
+
+        img(source.clone())
+            .id(id)
+            .object_fit(ObjectFit::Contain)
+            .max_w(relative(1.))
+            .when_some(self.width, |this, width| this.w(width))
+            .map(|this| match (self.width, self.height, natural_size) {
+                (Some(_), Some(height), _) => match self.aspect_ratio() {
+                    Some(ratio) => this.aspect_ratio(ratio),
+                    None => this.h(height),
+                },
+                (width, height, Some(natural_size)) => this
+                    .aspect_ratio(natural_size.width / natural_size.height)
+                    .when(width.is_none() && height.is_none(), |this| {
+                        this.w(natural_size.width)
+                    })
+                    .when_some(height, |this, height| this.h(height)),
+                (_, height, None) => this.when_some(height, |this, height| this.h(height)),
+            })
+            .with_loading(|| {
+                Skeleton::new()
+                    .size_full()
+                    .min_w(px(120.))
+                    .min_h(px(80.))
+                    .rounded_md()
+                    .into_any_element()
+            })
+            .with_fallback({
+                let alt = alt.clone();
+                move || ImagePlaceholder { alt: alt.clone() }.into_any_element()
+            })
+            .cursor_pointer()
+            .map(|this| match self.link.clone() {
+                Some(link) => {
+                    let title = self.title();
+                    let on_link_click = node_cx.style.on_link_click.clone();
+                    this.tooltip(move |window, cx| Tooltip::new(title.clone()).build(window, cx))
+                        .on_click(move |_, window, cx| {
+                            cx.stop_propagation();
+                            if let Some(on_link_click) = on_link_click.as_ref() {
+                                if on_link_click(&link.url, window, cx) {
+                                    return;
+                                }
+                            }
+                            cx.open_url(&link.url);
+                        })
+                }
+                None => this.on_click(move |_, window, cx| {
+                    cx.stop_propagation();
+                    ImagePreview::open(source.clone(), alt.clone(), window, cx);
+                }),
+            })
+            .into_any_element()
+    }
+}
+
+/// The placeholder with the alt text, shown if the image failed to load.
+#[derive(IntoElement)]
+struct ImagePlaceholder {
+    alt: SharedString,
+}
+
+impl RenderOnce for ImagePlaceholder {
+    fn render(self, _: &mut Window, cx: &mut App) -> impl IntoElement {
+        h_flex()
+            .size_full()
+            .min_h(px(80.))
+            .p_3()
+            .gap_2()
+            .justify_center()
+            .rounded(cx.theme().radius)
+            .border_1()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().muted)
+            .text_sm()
+            .text_color(cx.theme().muted_foreground)
+            .child(Icon::new(IconName::Frame).small())
+            .child(self.alt)
+    }
+}
+
+/// The zoomable preview of the image, opened in a dialog.
+struct ImagePreview {
+    source: ImageSource,
+    scale: f32,
+}
+
+impl ImagePreview {
+    fn open(source: ImageSource, alt: SharedString, window: &mut Window, cx: &mut App) {
+        let preview = cx.new(|_| Self { source, scale: 1. });
+        let width = window.viewport_size().width * 0.8;
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            dialog.title(alt.clone()).w(width).child(preview.clone())
+        });
+    }
+
+    fn zoom(&mut self, factor: f32, cx: &mut Context<Self>) {
+        self.scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        cx.notify();
+    }
+}
+
+impl Render for ImagePreview {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let height = window.viewport_size().height * 0.7;
+
+        v_flex()
+            .gap_2()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .justify_center()
+                    .child(
+                        Button::new("zoom-out")
+                            .xsmall()
+                            .ghost()
+                            .icon(IconName::Minus)
+                            .disabled(self.scale <= MIN_SCALE)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.zoom(1. / SCALE_STEP, cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("zoom-reset")
+                            .xsmall()
+                            .ghost()
+                            .label(format!("{:.0}%", self.scale * 100.))
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.scale = 1.;
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        Button::new("zoom-in")
+                            .xsmall()
+                            .ghost()
+                            .icon(IconName::Plus)
+                            .disabled(self.scale >= MAX_SCALE)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.zoom(SCALE_STEP, cx);
+                            })),
+                    ),
+            )
+            .child(
+                div()
+                    .id("image-preview")
+                    .w_full()
+                    .h(height)
+                    .overflow_scroll()
+                    .on_scroll_wheel(cx.listener(|this, event: &ScrollWheelEvent, window, cx| {
+                        // Zoom by the wheel with the secondary modifier (cmd/ctrl).
+                        if !event.modifiers.secondary() {
+                            return;
+                        }
+
+                        let delta = event.delta.pixel_delta(window.line_height()).y;
+                        if delta > px(0.) {
+                            this.zoom(SCALE_STEP, cx);
+                        } else if delta < px(0.) {
+                            this.zoom(1. / SCALE_STEP, cx);
+                        }
+                        cx.stop_propagation();
+                    }))
+                    .child(
+                        img(self.source.clone())
+                            .flex_none()
+                            .w(relative(self.scale))
+                            .object_fit(ObjectFit::Contain),
+                    ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use gpui::ImageFormat;
+
+    use super::{ImageLocation, decode_data_uri, resolve_image_url};
+
+    #[test]
+    fn test_resolve_image_url() {
+        let base_path = Some(Path::new("/docs"));
+        assert_eq!(
+            resolve_image_url("images/logo%20dark.png", base_path),
+            ImageLocation::Path(PathBuf::from("/docs/images/logo dark.png"))
+        );
+        assert_eq!(
+            resolve_image_url("./icon.svg#dark", base_path),
+            ImageLocation::Path(PathBuf::from("/docs/./icon.svg"))
+        );
+        assert_eq!(
+            resolve_image_url("/tmp/logo.png", base_path),
+            ImageLocation::Path(PathBuf::from("/tmp/logo.png"))
+        );
+        assert_eq!(
+            resolve_image_url("file:///tmp/logo.png", None),
+            ImageLocation::Path(PathBuf::from("/tmp/logo.png"))
+        );
+        assert_eq!(
+            resolve_image_url("https://example.com/logo.png", base_path),
+            ImageLocation::Uri("https://example.com/logo.png".into())
+        );
+        assert_eq!(
+            resolve_image_url("images/logo.png", None),
+            ImageLocation::Uri("images/logo.png".into())
+        );
+    }
+
+    #[test]
+    fn test_decode_data_uri() {
+        let image = decode_data_uri("data:image/png;base64,iVBORw0K").unwrap();
+        assert_eq!(image.format, ImageFormat::Png);
+        assert_eq!(image.bytes, b"\x89PNG\r\n");
+
+        let image = decode_data_uri("data:image/svg+xml,%3Csvg%2F%3E").unwrap();
+        assert_eq!(image.format, ImageFormat::Svg);
+        assert_eq!(image.bytes, b"<svg/>");
+
+        let image = decode_data_uri("data:image/png,%89PNG%0D%0A").unwrap();
+        assert_eq!(image.bytes, b"\x89PNG\r\n");
+
+        assert!(decode_data_uri("data:text/plain,hello").is_none());
+        assert!(decode_data_uri("https://example.com/logo.png").is_none());
+    }
+}
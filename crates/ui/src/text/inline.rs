@@ -5,19 +5,27 @@ use std::{
 };
 
 use gpui::{
-    point, px, quad, transparent_black, App, BorderStyle, Bounds, Corners, CursorStyle, Edges,
-    Element, ElementId, GlobalElementId, Half, HighlightStyle, Hitbox, HitboxBehavior, Hsla,
-    InspectorElementId, IntoElement, LayoutId, MouseMoveEvent, MouseUpEvent, Pixels, Point,
-    SharedString, StyledText, TextLayout, Window,
+    point, px, quad, size, transparent_black, AnyElement, App, AvailableSpace, BorderStyle, Bounds,
+    Corners, CursorStyle, Edges, Element, ElementId, GlobalElementId, Half, HighlightStyle, Hitbox,
+    HitboxBehavior, Hsla, InspectorElementId, IntoElement, LayoutId, MouseMoveEvent, MouseUpEvent,
+    Pixels, Point, SharedString, Size, StyledText, TextLayout, Window,
 };
 
 use crate::{
     global_state::GlobalState,
     input::Selection,
-    text::{node::LinkMark, style::InlineCodeStyle},
-    ActiveTheme,
+    text::{math::Math, node::LinkMark, style::InlineCodeStyle},
+    ActiveTheme, Colorize as _,
 };
 
+/// The text of the inline math or component in the paragraph, the element is laid out in place of it.
+pub(crate) const EMBED_PLACEHOLDER: &str = "\u{FFFC}";
+
+/// The character to fill the width of the embedded element in the text layout, it's painted transparent.
+///
+/// A word character, to keep the element in one line when wrapping.
+const EMBED_FILLER: &str = "i";
+
 /// A inline element used to render a inline text and support selectable.
 ///
 /// All text in TextView (including the CodeBlock) used this for text rendering.
@@ -29,10 +37,76 @@ pub(super) struct Inline {
     code_ranges: Vec<Range<usize>>,
     inline_code_style: Option<InlineCodeStyle>,
     styled_text: StyledText,
+    /// The embedded elements at the offsets of the [`EMBED_PLACEHOLDER`] in the text.
+    embeds: Vec<(usize, InlineEmbed)>,
+    embed_layouts: Vec<EmbedLayout>,
+    offset_map: Rc<OffsetMap>,
 
     state: Arc<Mutex<InlineState>>,
 }
 
+/// The element embedded in the text at the [`EMBED_PLACEHOLDER`].
+pub(super) enum InlineEmbed {
+    /// The inline math, aligned with the baseline of the text.
+    Math(Math),
+    /// The inline component, centered in the line.
+    Element(AnyElement),
+}
+
+/// The embedded element laid out in the text, placed over the fillers in the `range` of the rendered text.
+struct EmbedLayout {
+    range: Range<usize>,
+    element: AnyElement,
+    size: Size<Pixels>,
+    /// The distance from the bottom of the element to the bottom of the line.
+    bottom_margin: Pixels,
+}
+
+/// Map the offsets of the text to the rendered text, the placeholders are expanded to the fillers.
+#[derive(Debug, Default)]
+struct OffsetMap {
+    /// The offset of the placeholder in the text, and the length of the fillers.
+    fillers: Vec<(usize, usize)>,
+}
+
+impl OffsetMap {
+    /// Map the offset of the text to the rendered text.
+    fn to_display(&self, offset: usize) -> usize {
+        let mut display = offset as isize;
+        for (start, len) in self.fillers.iter() {
+            if offset > *start {
+                display += *len as isize - EMBED_PLACEHOLDER.len() as isize;
+            }
+        }
+        display as usize
+    }
+
+    fn to_display_range(&self, range: &Range<usize>) -> Range<usize> {
+        self.to_display(range.start)..self.to_display(range.end)
+    }
+
+    /// Map the offset of the rendered text to the text,
+    /// the offset in the fillers is mapped to the nearest side of the placeholder.
+    fn to_logical(&self, display: usize) -> usize {
+        let mut shift = 0isize;
+        for (start, len) in self.fillers.iter() {
+            let display_start = (*start as isize + shift) as usize;
+            if display < display_start {
+                break;
+            }
+            if display < display_start + len {
+                return if display - display_start < len / 2 {
+                    *start
+                } else {
+                    start + EMBED_PLACEHOLDER.len()
+                };
+            }
+            shift += *len as isize - EMBED_PLACEHOLDER.len() as isize;
+        }
+        (display as isize - shift) as usize
+    }
+}
+
 /// The inline text state, used RefCell to keep the selection state.
 #[derive(Debug, Default, PartialEq)]
 pub(crate) struct InlineState {
@@ -40,6 +114,10 @@ pub(crate) struct InlineState {
     /// The text that actually rendering, matched with selection.
     pub(super) text: SharedString,
     pub(super) selection: Option<Selection>,
+    /// The ranges of the search matches in the text.
+    pub(super) search_ranges: Vec<Range<usize>>,
+    /// The range of the current search match, if it's in this text.
+    pub(super) active_search: Option<Range<usize>>,
 }
 
 impl InlineState {
@@ -67,17 +145,88 @@ impl Inline {
             inline_code_style,
             text: text.clone(),
             styled_text: StyledText::new(text),
+            embeds: vec![],
+            embed_layouts: vec![],
+            offset_map: Rc::default(),
             state,
         }
     }
 
+    /// Set the embedded elements, the offsets are the positions of the [`EMBED_PLACEHOLDER`] in the text.
+    pub(super) fn embeds(mut self, embeds: Vec<(usize, InlineEmbed)>) -> Self {
+        self.embeds = embeds;
+        self
+    }
+
+    /// Layout the embedded elements, returns the text to render with the placeholders replaced by the fillers.
+    fn layout_embeds(&mut self, window: &mut Window, cx: &mut App) -> SharedString {
+        self.embed_layouts.clear();
+        if self.embeds.is_empty() {
+            self.offset_map = Rc::default();
+            return self.text.clone();
+        }
+
+        let text_style = window.text_style();
+        let font_size = text_style.font_size.to_pixels(window.rem_size());
+        let line_height = text_style.line_height_in_pixels(window.rem_size());
+        let filler_width = window
+            .text_system()
+            .shape_line(
+                EMBED_FILLER.into(),
+                font_size,
+                &[text_style.to_run(EMBED_FILLER.len())],
+                None,
+            )
+            .width;
+
+        let mut text = String::with_capacity(self.text.len());
+        let mut fillers = Vec::with_capacity(self.embeds.len());
+        let mut last_offset = 0;
+        for (offset, embed) in self.embeds.drain(..) {
+            let (mut element, math) = match embed {
+                InlineEmbed::Math(math) => (math.render(font_size, cx), Some(math)),
+                InlineEmbed::Element(element) => (element, None),
+            };
+            // Max content width to keep the text of the component in one line.
+            let available_space = size(AvailableSpace::MaxContent, AvailableSpace::MinContent);
+            let size = element.layout_as_root(available_space, window, cx);
+            let count = if filler_width > px(0.) {
+                (size.width / filler_width).ceil().max(1.) as usize
+            } else {
+                1
+            };
+
+            text.push_str(&self.text[last_offset..offset]);
+            let start = text.len();
+            text.push_str(&EMBED_FILLER.repeat(count));
+            fillers.push((offset, text.len() - start));
+            last_offset = offset + EMBED_PLACEHOLDER.len();
+
+            let bottom_margin = match math {
+                Some(math) => math.inline_bottom_margin(font_size, line_height),
+                None => (line_height - size.height).half(),
+            };
+            self.embed_layouts.push(EmbedLayout {
+                range: start..text.len(),
+                element,
+                size,
+                bottom_margin,
+            });
+        }
+        text.push_str(&self.text[last_offset..]);
+
+        self.offset_map = Rc::new(OffsetMap { fillers });
+        text.into()
+    }
+
     /// Get link at given mouse position.
     fn link_for_position(
         layout: &TextLayout,
+        offset_map: &OffsetMap,
         links: &Vec<(Range<usize>, LinkMark)>,
         position: Point<Pixels>,
     ) -> Option<LinkMark> {
-        let offset = layout.index_for_position(position).ok()?;
+        let offset = offset_map.to_logical(layout.index_for_position(position).ok()?);
         for (range, link) in links.iter() {
             if range.contains(&offset) {
                 return Some(link.clone());
@@ -126,13 +275,15 @@ impl Inline {
         let mut offset = 0;
         let mut chars = self.text.chars().peekable();
         while let Some(c) = chars.next() {
-            let Some(pos) = text_layout.position_for_index(offset) else {
+            let Some(pos) = text_layout.position_for_index(self.offset_map.to_display(offset))
+            else {
                 offset += c.len_utf8();
                 continue;
             };
 
             let mut char_width = line_height.half();
-            if let Some(next_pos) = text_layout.position_for_index(offset + 1) {
+            let next_index = self.offset_map.to_display(offset + c.len_utf8());
+            if let Some(next_pos) = text_layout.position_for_index(next_index) {
                 if next_pos.y == pos.y {
                     char_width = next_pos.x - pos.x;
                 }
@@ -156,6 +307,7 @@ impl Inline {
     /// Paint the selection background.
     fn paint_selection(
         selection: &Selection,
+        offset_map: &OffsetMap,
         text_layout: &TextLayout,
         bounds: &Bounds<Pixels>,
         window: &mut Window,
@@ -166,6 +318,46 @@ impl Inline {
         if end < start {
             std::mem::swap(&mut start, &mut end);
         }
+
+        Self::paint_range(
+            offset_map.to_display_range(&(start..end)),
+            cx.theme().selection,
+            text_layout,
+            bounds,
+            window,
+        );
+    }
+
+    /// Paint the search matches background, the active match is painted in selection color.
+    fn paint_search_matches(
+        state: &InlineState,
+        offset_map: &OffsetMap,
+        text_layout: &TextLayout,
+        bounds: &Bounds<Pixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let secondary_selection = cx.theme().selection.saturation(0.1);
+        for range in state.search_ranges.iter() {
+            let color = if state.active_search.as_ref() == Some(range) {
+                cx.theme().selection
+            } else {
+                secondary_selection
+            };
+            let range = offset_map.to_display_range(range);
+            Self::paint_range(range, color, text_layout, bounds, window);
+        }
+    }
+
+    /// Paint the background of the text `range`, may be in multiple lines.
+    fn paint_range(
+        range: Range<usize>,
+        color: Hsla,
+        text_layout: &TextLayout,
+        bounds: &Bounds<Pixels>,
+        window: &mut Window,
+    ) {
+        let (start, end) = (range.start, range.end);
         let Some(start_position) = text_layout.position_for_index(start) else {
             return;
         };
@@ -181,7 +373,7 @@ impl Inline {
                     point(end_position.x, end_position.y + line_height),
                 ),
                 px(0.),
-                cx.theme().selection,
+                color,
                 Edges::default(),
                 gpui::transparent_black(),
                 BorderStyle::default(),
@@ -193,7 +385,7 @@ impl Inline {
                     point(bounds.right(), start_position.y + line_height),
                 ),
                 px(0.),
-                cx.theme().selection,
+                color,
                 Edges::default(),
                 gpui::transparent_black(),
                 BorderStyle::default(),
@@ -206,7 +398,7 @@ impl Inline {
                         point(bounds.right(), end_position.y),
                     ),
                     px(0.),
-                    cx.theme().selection,
+                    color,
                     Edges::default(),
                     gpui::transparent_black(),
                     BorderStyle::default(),
@@ -219,7 +411,7 @@ impl Inline {
                     point(end_position.x, end_position.y + line_height),
                 ),
                 px(0.),
-                cx.theme().selection,
+                color,
                 Edges::default(),
                 gpui::transparent_black(),
                 BorderStyle::default(),
@@ -378,18 +570,41 @@ impl Element for Inline {
     ) -> (LayoutId, Self::RequestLayoutState) {
         let text_style = window.text_style();
 
-        let runs = if self.code_ranges.is_empty() || self.inline_code_style.is_none() {
+        let text = self.layout_embeds(window, cx);
+        let mut highlights = self
+            .highlights
+            .iter()
+            .map(|(range, style)| (self.offset_map.to_display_range(range), *style))
+            .collect::<Vec<_>>();
+        if !self.embed_layouts.is_empty() {
+            // Hide the fillers, the elements are painted over them.
+            for layout in self.embed_layouts.iter() {
+                let style = HighlightStyle {
+                    color: Some(transparent_black()),
+                    ..Default::default()
+                };
+                highlights.push((layout.range.clone(), style));
+            }
+            highlights.sort_by_key(|(range, _)| range.start);
+        }
+        let code_ranges = self
+            .code_ranges
+            .iter()
+            .map(|range| self.offset_map.to_display_range(range))
+            .collect::<Vec<_>>();
+
+        let runs = if code_ranges.is_empty() || self.inline_code_style.is_none() {
             let mut runs = Vec::new();
             let mut ix = 0;
-            for (range, highlight) in self.highlights.iter() {
+            for (range, highlight) in highlights.iter() {
                 if ix < range.start {
                     runs.push(text_style.clone().to_run(range.start - ix));
                 }
                 runs.push(text_style.clone().highlight(*highlight).to_run(range.len()));
                 ix = range.end;
             }
-            if ix < self.text.len() {
-                runs.push(text_style.to_run(self.text.len() - ix));
+            if ix < text.len() {
+                runs.push(text_style.to_run(text.len() - ix));
             }
             runs
         } else {
@@ -399,12 +614,12 @@ impl Element for Inline {
                 .expect("inline code style");
             let mut breakpoints = Vec::new();
             breakpoints.push(0);
-            breakpoints.push(self.text.len());
-            for (range, _) in self.highlights.iter() {
+            breakpoints.push(text.len());
+            for (range, _) in highlights.iter() {
                 breakpoints.push(range.start);
                 breakpoints.push(range.end);
             }
-            for range in self.code_ranges.iter() {
+            for range in code_ranges.iter() {
                 breakpoints.push(range.start);
                 breakpoints.push(range.end);
             }
@@ -422,14 +637,14 @@ impl Element for Inline {
                     continue;
                 }
 
-                while highlight_index < self.highlights.len()
-                    && self.highlights[highlight_index].0.end <= start
+                while highlight_index < highlights.len()
+                    && highlights[highlight_index].0.end <= start
                 {
                     highlight_index += 1;
                 }
 
-                let highlight = if highlight_index < self.highlights.len() {
-                    let (range, style) = &self.highlights[highlight_index];
+                let highlight = if highlight_index < highlights.len() {
+                    let (range, style) = &highlights[highlight_index];
                     if range.start <= start && range.end >= end {
                         Some(*style)
                     } else {
@@ -439,14 +654,12 @@ impl Element for Inline {
                     None
                 };
 
-                while code_index < self.code_ranges.len()
-                    && self.code_ranges[code_index].end <= start
-                {
+                while code_index < code_ranges.len() && code_ranges[code_index].end <= start {
                     code_index += 1;
                 }
 
-                let is_code = if code_index < self.code_ranges.len() {
-                    let range = &self.code_ranges[code_index];
+                let is_code = if code_index < code_ranges.len() {
+                    let range = &code_ranges[code_index];
                     range.start <= start && range.end >= end
                 } else {
                     false
@@ -474,7 +687,7 @@ impl Element for Inline {
             runs
         };
 
-        self.styled_text = StyledText::new(self.text.clone()).with_runs(runs);
+        self.styled_text = StyledText::new(text).with_runs(runs);
         let (layout_id, _) =
             self.styled_text
                 .request_layout(global_element_id, inspector_id, window, cx);
@@ -494,6 +707,26 @@ impl Element for Inline {
         self.styled_text
             .prepaint(id, inspector_id, bounds, &mut (), window, cx);
 
+        // Place the elements over the fillers.
+        let text_layout = self.styled_text.layout().clone();
+        let line_height = text_layout.line_height();
+        self.embed_layouts.retain_mut(|layout| {
+            let Some(start) = text_layout.position_for_index(layout.range.start) else {
+                return false;
+            };
+            let filler_width = match text_layout.position_for_index(layout.range.end) {
+                Some(end) if end.y == start.y => end.x - start.x,
+                _ => layout.size.width,
+            };
+
+            let origin = point(
+                start.x + (filler_width - layout.size.width).half(),
+                start.y + line_height - layout.bottom_margin - layout.size.height,
+            );
+            layout.element.prepaint_at(origin, window, cx);
+            true
+        });
+
         let hitbox = window.insert_hitbox(bounds, HitboxBehavior::Normal);
         hitbox
     }
@@ -513,11 +746,23 @@ impl Element for Inline {
         let mut state = self.state.lock().unwrap();
 
         let text_layout = self.styled_text.layout().clone();
+        let offset_map = self.offset_map.clone();
         if let Some(style) = self.inline_code_style.as_ref() {
-            Self::paint_inline_code(&self.code_ranges, style, &text_layout, &bounds, window, cx);
+            let code_ranges = self
+                .code_ranges
+                .iter()
+                .map(|range| offset_map.to_display_range(range))
+                .collect::<Vec<_>>();
+            Self::paint_inline_code(&code_ranges, style, &text_layout, &bounds, window, cx);
+        }
+        if !state.search_ranges.is_empty() {
+            Self::paint_search_matches(&state, &offset_map, &text_layout, &bounds, window, cx);
         }
         self.styled_text
             .paint(global_id, None, bounds, &mut (), &mut (), window, cx);
+        for layout in self.embed_layouts.iter_mut() {
+            layout.element.paint(window, cx);
+        }
 
         // layout selections
         let (is_selectable, is_selection, selection) =
@@ -531,23 +776,56 @@ impl Element for Inline {
 
         // link cursor pointer
         let mouse_position = window.mouse_position();
-        if let Some(link) = Self::link_for_position(&text_layout, &self.links, mouse_position) {
+        if let Some(link) =
+            Self::link_for_position(&text_layout, &offset_map, &self.links, mouse_position)
+        {
             if !link.requires_modifiers || window.modifiers().secondary() {
                 window.set_cursor_style(CursorStyle::PointingHand, &hitbox);
             }
         }
 
         if let Some(selection) = &state.selection {
-            Self::paint_selection(selection, &text_layout, &bounds, window, cx);
+            Self::paint_selection(selection, &offset_map, &text_layout, &bounds, window, cx);
         }
 
+        let text_view_state = GlobalState::global(cx).text_view_state().cloned();
+
         // mouse move, update hovered link
         window.on_mouse_event({
             let hitbox = hitbox.clone();
             let text_layout = text_layout.clone();
             let mut hovered_index = state.hovered_index;
+            let links = self.links.clone();
+            let offset_map = offset_map.clone();
+            let source = Arc::as_ptr(&self.state) as usize;
+            // Only track the hovered link if there is a link preview.
+            let preview_state = text_view_state
+                .clone()
+                .filter(|state| state.read(cx).text_view_style.link_preview.is_some());
             move |event: &MouseMoveEvent, phase, window, cx| {
-                if !phase.bubble() || !hitbox.is_hovered(window) {
+                if !phase.bubble() {
+                    return;
+                }
+
+                if let Some(preview_state) = preview_state.as_ref() {
+                    let url = hitbox
+                        .is_hovered(window)
+                        .then(|| {
+                            Self::link_for_position(
+                                &text_layout,
+                                &offset_map,
+                                &links,
+                                event.position,
+                            )
+                        })
+                        .flatten()
+                        .map(|link| link.url);
+                    preview_state.update(cx, |state, cx| {
+                        state.update_hovered_link(source, url, event.position, cx);
+                    });
+                }
+
+                if !hitbox.is_hovered(window) {
                     return;
                 }
 
@@ -566,20 +844,41 @@ impl Element for Inline {
             window.on_mouse_event({
                 let links = self.links.clone();
                 let text_layout = text_layout.clone();
+                let offset_map = offset_map.clone();
+                let on_link_click = text_view_state
+                    .as_ref()
+                    .and_then(|state| state.read(cx).text_view_style.on_link_click.clone());
 
-                move |event: &MouseUpEvent, phase, _, cx| {
+                move |event: &MouseUpEvent, phase, window, cx| {
                     if !bounds.contains(&event.position) || !phase.bubble() {
                         return;
                     }
 
                     if let Some(link) =
-                        Self::link_for_position(&text_layout, &links, event.position)
+                        Self::link_for_position(&text_layout, &offset_map, &links, event.position)
                     {
                         if link.requires_modifiers && !event.modifiers.secondary() {
                             return;
                         }
                         cx.stop_propagation();
-                        cx.open_url(&link.url);
+
+                        // The anchor link in the document, e.g. footnote reference.
+                        let anchor = link.url.strip_prefix('#');
+                        if let Some((anchor, state)) = anchor.zip(text_view_state.as_ref()) {
+                            if state.update(cx, |state, cx| state.scroll_to_anchor(anchor, cx)) {
+                                return;
+                            }
+                        }
+
+                        if let Some(on_link_click) = on_link_click.as_ref() {
+                            if on_link_click(&link.url, window, cx) {
+                                return;
+                            }
+                        }
+                        // Not scrolled, e.g. the TextView is not `scrollable`, leave it to `on_link_click`.
+                        if anchor.is_none() {
+                            cx.open_url(&link.url);
+                        }
                     }
                 }
             });
@@ -624,9 +923,32 @@ fn point_in_text_selection(
 
 #[cfg(test)]
 mod tests {
-    use super::point_in_text_selection;
+    use super::{point_in_text_selection, OffsetMap};
     use gpui::{point, px, size, Bounds};
 
+    #[test]
+    fn test_offset_map() {
+        // "a\u{FFFC}b\u{FFFC}c", the first math is expanded to 5 bytes, the second to 1 byte.
+        let map = OffsetMap {
+            fillers: vec![(1, 5), (5, 1)],
+        };
+        assert_eq!(map.to_display(0), 0);
+        assert_eq!(map.to_display(1), 1);
+        assert_eq!(map.to_display(4), 6);
+        assert_eq!(map.to_display(5), 7);
+        assert_eq!(map.to_display(8), 8);
+        assert_eq!(map.to_display(9), 9);
+
+        assert_eq!(map.to_logical(0), 0);
+        assert_eq!(map.to_logical(1), 1);
+        assert_eq!(map.to_logical(2), 1);
+        assert_eq!(map.to_logical(5), 4);
+        assert_eq!(map.to_logical(6), 4);
+        assert_eq!(map.to_logical(7), 8);
+        assert_eq!(map.to_logical(8), 8);
+        assert_eq!(map.to_logical(9), 9);
+    }
+
     #[test]
     fn test_point_in_text_selection() {
         let line_height = px(20.);
@@ -0,0 +1,682 @@
+use gpui::{
+    AnyElement, FontWeight, Hsla, IntoElement, ParentElement as _, Pixels, Styled as _, div,
+    prelude::FluentBuilder as _,
+};
+
+use crate::{
+    h_flex,
+    text::math::parser::{MathNode, MatrixAlign, SymbolKind},
+    v_flex,
+};
+
+/// The glyph ink height above and below the baseline, in em.
+const GLYPH_ASCENT: f32 = 0.75;
+const GLYPH_DESCENT: f32 = 0.22;
+/// The baseline of the text in a line box with `LINE_HEIGHT`, in em.
+const LINE_BASELINE: f32 = 0.95;
+const LINE_HEIGHT: f32 = 1.2;
+/// The height of the math axis (the center of `+` and the fraction rule) above the baseline.
+const AXIS_HEIGHT: f32 = 0.25;
+const RULE_THICKNESS: f32 = 0.05;
+const SCRIPT_SCALE: f32 = 0.7;
+const MIN_SCALE: f32 = 0.5;
+
+/// A laid out box of the math expression.
+///
+/// All the metrics are in em of the font size, the `ascent` and `descent` are the
+/// distances above and below the baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MathBox {
+    kind: BoxKind,
+    pub(crate) ascent: f32,
+    pub(crate) descent: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BoxKind {
+    Glyph {
+        text: String,
+        /// The font size scale, and also the ink height of the glyph.
+        size: f32,
+        italic: bool,
+        bold: bool,
+        /// The horizontal spaces before and after the glyph.
+        space: (f32, f32),
+    },
+    Space(f32),
+    /// The children are aligned by the baseline horizontally.
+    Row(Vec<MathBox>),
+    /// The children are stacked vertically, each with the gap before it.
+    Stack {
+        children: Vec<(f32, MathBox)>,
+        center: bool,
+    },
+    /// A horizontal rule with the thickness, fills the width of the parent stack.
+    Rule(f32),
+    /// The columns of a matrix, each row has the (ascent, descent).
+    Grid {
+        columns: Vec<Vec<MathBox>>,
+        rows: Vec<(f32, f32)>,
+        row_gap: f32,
+        column_gap: f32,
+        align: MatrixAlign,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct Style {
+    scale: f32,
+    display: bool,
+    bold: bool,
+}
+
+impl Style {
+    /// The style for the scripts, numerator and denominator in inline mode.
+    fn script(self) -> Self {
+        Self {
+            scale: (self.scale * SCRIPT_SCALE).max(MIN_SCALE),
+            display: false,
+            ..self
+        }
+    }
+}
+
+impl MathBox {
+    /// Layout the math node, `display` for the display (block) mode.
+    pub(crate) fn layout(node: &MathNode, display: bool) -> Self {
+        layout(
+            node,
+            Style {
+                scale: 1.,
+                display,
+                bold: false,
+            },
+        )
+    }
+
+    fn new(kind: BoxKind, ascent: f32, descent: f32) -> Self {
+        Self {
+            kind,
+            ascent,
+            descent,
+        }
+    }
+
+    fn height(&self) -> f32 {
+        self.ascent + self.descent
+    }
+
+    fn glyph(text: impl Into<String>, style: Style, italic: bool) -> Self {
+        Self::scaled_glyph(text, style, style.scale, italic, (0., 0.))
+    }
+
+    fn scaled_glyph(
+        text: impl Into<String>,
+        style: Style,
+        size: f32,
+        italic: bool,
+        space: (f32, f32),
+    ) -> Self {
+        Self::new(
+            BoxKind::Glyph {
+                text: text.into(),
+                size,
+                italic,
+                bold: style.bold,
+                space,
+            },
+            GLYPH_ASCENT * size,
+            GLYPH_DESCENT * size,
+        )
+    }
+
+    /// A delimiter glyph stretched to cover the `ascent` and `descent`, centered on the axis.
+    fn delimiter(text: &str, style: Style, ascent: f32, descent: f32) -> Self {
+        let axis = AXIS_HEIGHT * style.scale;
+        let half = (ascent - axis).max(descent + axis);
+        let size = (half * 2. / (GLYPH_ASCENT + GLYPH_DESCENT)).max(style.scale);
+        let height = size * (GLYPH_ASCENT + GLYPH_DESCENT);
+        Self {
+            kind: BoxKind::Glyph {
+                text: text.into(),
+                size,
+                italic: false,
+                bold: style.bold,
+                space: (0., 0.),
+            },
+            ascent: axis + height / 2.,
+            descent: height / 2. - axis,
+        }
+    }
+
+    fn row(children: Vec<MathBox>) -> Self {
+        let ascent = children.iter().map(|c| c.ascent).fold(0., f32::max);
+        let descent = children.iter().map(|c| c.descent).fold(0., f32::max);
+        Self::new(BoxKind::Row(children), ascent, descent)
+    }
+
+    /// Stack the children with the `ascent` as the baseline from the top.
+    fn stack(children: Vec<(f32, MathBox)>, center: bool, ascent: f32) -> Self {
+        let height = children
+            .iter()
+            .map(|(gap, c)| gap + c.height())
+            .sum::<f32>();
+        Self::new(BoxKind::Stack { children, center }, ascent, height - ascent)
+    }
+}
+
+fn layout(node: &MathNode, style: Style) -> MathBox {
+    let s = style.scale;
+    match node {
+        MathNode::Row(nodes) => layout_row(nodes, style),
+        MathNode::Variable(text) => MathBox::glyph(text, style, true),
+        MathNode::Text(text) => MathBox::glyph(text, style, false),
+        MathNode::Symbol(text, kind) => {
+            MathBox::scaled_glyph(text, style, s, false, spacing(*kind, s))
+        }
+        MathNode::Function { name, .. } => {
+            MathBox::scaled_glyph(name, style, s, false, (0.1 * s, 0.1 * s))
+        }
+        MathNode::BigOperator { symbol, .. } => {
+            let size = if style.display { 1.6 * s } else { 1.2 * s };
+            let mut glyph = MathBox::scaled_glyph(symbol, style, size, false, (0.1 * s, 0.1 * s));
+            // Center the operator on the axis.
+            let shift = (glyph.ascent - glyph.descent) / 2. - AXIS_HEIGHT * s;
+            glyph.ascent -= shift;
+            glyph.descent += shift;
+            glyph
+        }
+        MathNode::Fraction {
+            numerator,
+            denominator,
+            rule,
+        } => {
+            let child_style = if style.display {
+                Style {
+                    display: false,
+                    ..style
+                }
+            } else {
+                style.script()
+            };
+            let numerator = layout(numerator, child_style);
+            let denominator = layout(denominator, child_style);
+            let thickness = if *rule { RULE_THICKNESS * s } else { 0. };
+            let gap = 0.12 * s;
+            let ascent = numerator.height() + gap + thickness / 2. + AXIS_HEIGHT * s;
+            let fraction = MathBox::stack(
+                vec![
+                    (0., numerator),
+                    (gap, MathBox::new(BoxKind::Rule(thickness), thickness, 0.)),
+                    (gap, denominator),
+                ],
+                true,
+                ascent,
+            );
+            with_space(fraction, 0.1 * s)
+        }
+        MathNode::Root { index, body } => {
+            let body = layout(body, style);
+            let thickness = RULE_THICKNESS * s;
+            let clearance = 0.1 * s;
+            let ascent = thickness + clearance + body.ascent;
+            let inner = MathBox::stack(
+                vec![
+                    (0., MathBox::new(BoxKind::Rule(thickness), thickness, 0.)),
+                    (clearance, body),
+                ],
+                false,
+                ascent,
+            );
+            let radical = MathBox {
+                kind: BoxKind::Glyph {
+                    text: "√".into(),
+                    size: inner.height() / (GLYPH_ASCENT + GLYPH_DESCENT),
+                    italic: false,
+                    bold: style.bold,
+                    space: (0., 0.),
+                },
+                ascent: inner.ascent,
+                descent: inner.descent,
+            };
+
+            let mut children = vec![];
+            if let Some(index) = index {
+                let index = layout(
+                    index,
+                    Style {
+                        scale: (s * 0.5).max(MIN_SCALE * 0.7),
+                        display: false,
+                        ..style
+                    },
+                );
+                // Raise the index to the upper part of the radical, and overlap it.
+                let raise = inner.ascent * 0.5;
+                let index_ascent = index.ascent + raise;
+                children.push(MathBox::stack(vec![(0., index)], false, index_ascent));
+                children.push(MathBox::new(BoxKind::Space(-0.25 * s), 0., 0.));
+            }
+            children.push(radical);
+            children.push(inner);
+            MathBox::row(children)
+        }
+        MathNode::Scripts { base, sub, sup } => {
+            layout_scripts(base, sub.as_deref(), sup.as_deref(), style)
+        }
+        MathNode::Fenced { open, body, close } => {
+            let body = layout(body, style);
+            fenced(open.as_deref(), body, close.as_deref(), style)
+        }
+        MathNode::Matrix {
+            rows,
+            open,
+            close,
+            align,
+        } => {
+            let cell_style = Style {
+                display: false,
+                ..style
+            };
+            let columns_len = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+            let mut columns: Vec<Vec<MathBox>> = vec![vec![]; columns_len];
+            let mut row_metrics = vec![];
+            for row in rows {
+                let mut ascent = GLYPH_ASCENT * s;
+                let mut descent = GLYPH_DESCENT * s;
+                for (ix, column) in columns.iter_mut().enumerate() {
+                    let cell = row
+                        .get(ix)
+                        .map(|cell| layout(cell, cell_style))
+                        .unwrap_or_else(|| MathBox::row(vec![]));
+                    ascent = ascent.max(cell.ascent);
+                    descent = descent.max(cell.descent);
+                    column.push(cell);
+                }
+                row_metrics.push((ascent, descent));
+            }
+
+            let row_gap = 0.3 * s;
+            let height = row_metrics.iter().map(|(a, d)| a + d).sum::<f32>()
+                + row_gap * row_metrics.len().saturating_sub(1) as f32;
+            let axis = AXIS_HEIGHT * s;
+            let grid = MathBox::new(
+                BoxKind::Grid {
+                    columns,
+                    rows: row_metrics,
+                    row_gap,
+                    column_gap: if *align == MatrixAlign::RightLeft {
+                        0.
+                    } else {
+                        1. * s
+                    },
+                    align: *align,
+                },
+                height / 2. + axis,
+                height / 2. - axis,
+            );
+            fenced(open.as_deref(), grid, close.as_deref(), style)
+        }
+        MathNode::Accent { base, accent } => {
+            // Use the combining character for the single char, e.g. `x̂`.
+            match base.as_ref() {
+                MathNode::Variable(text) | MathNode::Text(text) if text.chars().count() == 1 => {
+                    MathBox::glyph(
+                        format!("{}{}", text, accent),
+                        style,
+                        matches!(base.as_ref(), MathNode::Variable(_)),
+                    )
+                }
+                _ => {
+                    let base = layout(base, style);
+                    // The combining character on a no-break space to draw the accent only.
+                    let mark = MathBox::glyph(format!("\u{00A0}{}", accent), style, false);
+                    let gap = -0.55 * s;
+                    let ascent = mark.height() + gap + base.ascent;
+                    MathBox::stack(vec![(0., mark), (gap, base)], true, ascent)
+                }
+            }
+        }
+        MathNode::Overline(body) => {
+            let body = layout(body, style);
+            let thickness = RULE_THICKNESS * s;
+            let gap = 0.1 * s;
+            let ascent = thickness + gap + body.ascent;
+            MathBox::stack(
+                vec![
+                    (0., MathBox::new(BoxKind::Rule(thickness), thickness, 0.)),
+                    (gap, body),
+                ],
+                false,
+                ascent,
+            )
+        }
+        MathNode::Underline(body) => {
+            let body = layout(body, style);
+            let thickness = RULE_THICKNESS * s;
+            let ascent = body.ascent;
+            MathBox::stack(
+                vec![
+                    (0., body),
+                    (
+                        0.1 * s,
+                        MathBox::new(BoxKind::Rule(thickness), thickness, 0.),
+                    ),
+                ],
+                false,
+                ascent,
+            )
+        }
+        MathNode::Bold(body) => layout(
+            body,
+            Style {
+                bold: true,
+                ..style
+            },
+        ),
+        MathNode::Space(em) => MathBox::new(BoxKind::Space(em * s), 0., 0.),
+    }
+}
+
+fn layout_row(nodes: &[MathNode], style: Style) -> MathBox {
+    let mut children = Vec::with_capacity(nodes.len());
+    let mut prev_kind: Option<SymbolKind> = None;
+    for node in nodes {
+        let child = match node {
+            // The binary operator at the start or after another operator is unary, e.g. `-x`.
+            MathNode::Symbol(text, SymbolKind::Binary)
+                if matches!(
+                    prev_kind,
+                    None | Some(
+                        SymbolKind::Binary
+                            | SymbolKind::Relation
+                            | SymbolKind::Open
+                            | SymbolKind::Punctuation
+                    )
+                ) =>
+            {
+                prev_kind = Some(SymbolKind::Ordinary);
+                children.push(MathBox::glyph(text, style, false));
+                continue;
+            }
+            _ => layout(node, style),
+        };
+        prev_kind = Some(match node {
+            MathNode::Symbol(_, kind) => *kind,
+            _ => SymbolKind::Ordinary,
+        });
+        children.push(child);
+    }
+    MathBox::row(children)
+}
+
+fn layout_scripts(
+    base: &MathNode,
+    sub: Option<&MathNode>,
+    sup: Option<&MathNode>,
+    style: Style,
+) -> MathBox {
+    let s = style.scale;
+    let limits = style.display
+        && matches!(
+            base,
+            MathNode::BigOperator { limits: true, .. } | MathNode::Function { limits: true, .. }
+        );
+
+    let base = layout(base, style);
+    let script_style = style.script();
+    let sub = sub.map(|node| layout(node, script_style));
+    let sup = sup.map(|node| layout(node, script_style));
+
+    if limits {
+        let gap = 0.1 * s;
+        let mut children = vec![];
+        let mut ascent = base.ascent;
+        if let Some(sup) = sup {
+            ascent += sup.height() + gap;
+            children.push((0., sup));
+        }
+        let base_gap = if children.is_empty() { 0. } else { gap };
+        children.push((base_gap, base));
+        if let Some(sub) = sub {
+            children.push((gap, sub));
+        }
+        return MathBox::stack(children, true, ascent);
+    }
+
+    let shift_up = (0.4 * s).max(base.ascent - 0.3 * s);
+    let mut shift_down = (0.2 * s).max(base.descent - 0.1 * s);
+    if let (Some(sup), Some(sub)) = (&sup, &sub) {
+        // Keep a gap between the superscript and subscript.
+        let gap = (shift_up - sup.descent) - (sub.ascent - shift_down);
+        let min_gap = 0.05 * s;
+        if gap < min_gap {
+            shift_down += min_gap - gap;
+        }
+    }
+
+    let ascent = [
+        Some(base.ascent),
+        sup.as_ref().map(|sup| shift_up + sup.ascent),
+        sub.as_ref().map(|sub| sub.ascent - shift_down),
+    ]
+    .into_iter()
+    .flatten()
+    .fold(0., f32::max);
+
+    let mut scripts = vec![];
+    let mut bottom = 0.;
+    if let Some(sup) = sup {
+        let gap = ascent - shift_up - sup.ascent;
+        bottom = gap + sup.height();
+        scripts.push((gap, sup));
+    }
+    if let Some(sub) = sub {
+        let gap = ascent + shift_down - sub.ascent - bottom;
+        scripts.push((gap, sub));
+    }
+
+    MathBox::row(vec![
+        base,
+        MathBox::stack(scripts, false, ascent),
+        MathBox::new(BoxKind::Space(0.05 * s), 0., 0.),
+    ])
+}
+
+fn fenced(open: Option<&str>, body: MathBox, close: Option<&str>, style: Style) -> MathBox {
+    let (ascent, descent) = (body.ascent, body.descent);
+    let mut children = vec![];
+    if let Some(open) = open {
+        children.push(MathBox::delimiter(open, style, ascent, descent));
+    }
+    children.push(body);
+    if let Some(close) = close {
+        children.push(MathBox::delimiter(close, style, ascent, descent));
+    }
+    MathBox::row(children)
+}
+
+fn with_space(child: MathBox, space: f32) -> MathBox {
+    MathBox::row(vec![
+        MathBox::new(BoxKind::Space(space), 0., 0.),
+        child,
+        MathBox::new(BoxKind::Space(space), 0., 0.),
+    ])
+}
+
+/// Returns the spaces before and after the symbol, in em.
+fn spacing(kind: SymbolKind, scale: f32) -> (f32, f32) {
+    // The spaces are omitted in the scripts like TeX.
+    if scale < 1. {
+        return (0., 0.);
+    }
+
+    match kind {
+        SymbolKind::Binary => (4. / 18., 4. / 18.),
+        SymbolKind::Relation => (5. / 18., 5. / 18.),
+        SymbolKind::Punctuation => (0., 3. / 18.),
+        _ => (0., 0.),
+    }
+}
+
+impl MathBox {
+    /// Render the box with the `font_size`, the `top` is the margin from the parent in em.
+    pub(crate) fn render(&self, top: f32, font_size: Pixels, color: Hsla) -> AnyElement {
+        let em = |value: f32| font_size * value;
+        let height = em(self.height());
+
+        match &self.kind {
+            BoxKind::Glyph {
+                text,
+                size,
+                italic,
+                bold,
+                space,
+            } => div()
+                .flex_none()
+                .mt(em(top))
+                .h(height)
+                .pl(em(space.0))
+                .pr(em(space.1))
+                .child(
+                    div()
+                        // Move up the line box to place the baseline at the glyph ascent.
+                        .mt(em(-(LINE_BASELINE - GLYPH_ASCENT) * size))
+                        .text_size(em(*size))
+                        .line_height(em(LINE_HEIGHT * size))
+                        .whitespace_nowrap()
+                        .when(*italic, |this| this.italic())
+                        .when(*bold, |this| this.font_weight(FontWeight::BOLD))
+                        .child(text.clone()),
+                )
+                .into_any_element(),
+            BoxKind::Space(width) => div()
+                .flex_none()
+                .mt(em(top))
+                .ml(em(*width))
+                .into_any_element(),
+            BoxKind::Row(children) => h_flex()
+                .flex_none()
+                .items_start()
+                .mt(em(top))
+                .h(height)
+                .children(
+                    children
+                        .iter()
+                        .map(|child| child.render(self.ascent - child.ascent, font_size, color)),
+                )
+                .into_any_element(),
+            BoxKind::Stack { children, center } => v_flex()
+                .flex_none()
+                .mt(em(top))
+                .h(height)
+                .when(*center, |this| this.items_center())
+                .children(
+                    children
+                        .iter()
+                        .map(|(gap, child)| child.render(*gap, font_size, color)),
+                )
+                .into_any_element(),
+            BoxKind::Rule(thickness) => div()
+                .flex_none()
+                .mt(em(top))
+                .w_full()
+                .h(em(*thickness))
+                .bg(color)
+                .into_any_element(),
+            BoxKind::Grid {
+                columns,
+                rows,
+                row_gap,
+                column_gap,
+                align,
+            } => h_flex()
+                .flex_none()
+                .items_start()
+                .mt(em(top))
+                .h(height)
+                .px(em(0.1))
+                .gap(em(*column_gap))
+                .children(columns.iter().enumerate().map(|(col_ix, cells)| {
+                    let right = *align == MatrixAlign::RightLeft && col_ix % 2 == 0;
+                    v_flex().flex_none().gap(em(*row_gap)).children(
+                        cells
+                            .iter()
+                            .zip(rows.iter())
+                            .map(|(cell, (ascent, descent))| {
+                                h_flex()
+                                    .h(em(ascent + descent))
+                                    .items_start()
+                                    .map(|this| match align {
+                                        MatrixAlign::Center => this.justify_center(),
+                                        MatrixAlign::Left => this.justify_start(),
+                                        MatrixAlign::RightLeft if right => this.justify_end(),
+                                        MatrixAlign::RightLeft => this.justify_start(),
+                                    })
+                                    .child(cell.render(ascent - cell.ascent, font_size, color))
+                            }),
+                    )
+                }))
+                .into_any_element(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AXIS_HEIGHT, GLYPH_ASCENT, GLYPH_DESCENT, MathBox};
+    use crate::text::math::parser::parse;
+
+    fn layout(source: &str, display: bool) -> MathBox {
+        MathBox::layout(&parse(source).unwrap(), display)
+    }
+
+    #[test]
+    fn test_layout_glyph() {
+        let math = layout("x", false);
+        assert_eq!(math.ascent, GLYPH_ASCENT);
+        assert_eq!(math.descent, GLYPH_DESCENT);
+    }
+
+    #[test]
+    fn test_layout_fraction() {
+        let inline = layout("\\frac{a}{b}", false);
+        let display = layout("\\frac{a}{b}", true);
+        assert!(inline.ascent > GLYPH_ASCENT);
+        assert!(inline.descent > GLYPH_DESCENT);
+        assert!(display.ascent > inline.ascent);
+        assert!(display.descent > inline.descent);
+
+        // The fraction rule is on the axis, so the numerator and denominator are balanced.
+        let diff = (display.ascent - AXIS_HEIGHT) - (display.descent + AXIS_HEIGHT);
+        assert!(diff.abs() < 0.001);
+    }
+
+    #[test]
+    fn test_layout_scripts() {
+        let sup = layout("x^2", false);
+        let sub = layout("x_2", false);
+        let both = layout("x_i^2", false);
+        assert!(sup.ascent > GLYPH_ASCENT);
+        assert_eq!(sup.descent, GLYPH_DESCENT);
+        assert!(sub.descent > GLYPH_DESCENT);
+        assert!(both.ascent >= sup.ascent);
+        assert!(both.descent >= sub.descent);
+
+        // The limits are placed above and below in display mode.
+        let inline = layout("\\sum_{i=0}^n i", false);
+        let display = layout("\\sum_{i=0}^n i", true);
+        assert!(display.ascent > inline.ascent);
+        assert!(display.descent > inline.descent);
+    }
+
+    #[test]
+    fn test_layout_matrix() {
+        let one = layout("\\begin{pmatrix} a \\end{pmatrix}", false);
+        let two = layout("\\begin{pmatrix} a \\\\ b \\end{pmatrix}", false);
+        assert!(two.ascent > one.ascent);
+        assert!(two.descent > one.descent);
+        // The matrix is centered on the axis.
+        let diff = (two.ascent - AXIS_HEIGHT) - (two.descent + AXIS_HEIGHT);
+        assert!(diff.abs() < 0.001);
+    }
+}
@@ -0,0 +1,81 @@
+mod layout;
+mod parser;
+
+use gpui::{
+    AnyElement, App, IntoElement, ParentElement as _, Pixels, SharedString, Styled as _, div,
+};
+
+use crate::{ActiveTheme as _, text::math::layout::MathBox};
+
+/// The baseline of the text in a line is assumed at `(line_height - TEXT_HEIGHT) / 2 + TEXT_ASCENT`,
+/// in em of the font size, used to align the inline math with the text.
+const TEXT_HEIGHT: f32 = 1.2;
+const TEXT_DESCENT: f32 = 0.25;
+
+/// A TeX math expression in markdown, `$..$` for inline and `$$..$$` for display.
+///
+/// The supported TeX subset: fractions, sub/superscripts, roots, Greek letters,
+/// common operators and symbols, `\left..\right` delimiters and matrices.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Math {
+    pub(crate) source: SharedString,
+    layout: Result<MathBox, SharedString>,
+}
+
+impl Math {
+    /// Create a new math, `display` for the display (block) mode.
+    pub(crate) fn new(source: impl Into<SharedString>, display: bool) -> Self {
+        let source = source.into();
+        let layout = parser::parse(&source).map(|node| MathBox::layout(&node, display));
+        if let Err(err) = &layout {
+            if cfg!(debug_assertions) {
+                tracing::warn!("failed to parse math {:?}: {}", source, err);
+            }
+        }
+
+        Self { source, layout }
+    }
+
+    /// Returns the distance from the bottom of the rendered math to the baseline.
+    fn descent(&self, font_size: Pixels) -> Pixels {
+        match &self.layout {
+            Ok(layout) => font_size * layout.descent,
+            Err(_) => font_size * TEXT_DESCENT,
+        }
+    }
+
+    /// Render the math, the `font_size` is the font size of the surrounding text.
+    pub(crate) fn render(&self, font_size: Pixels, cx: &App) -> AnyElement {
+        match &self.layout {
+            Ok(layout) => layout.render(0., font_size, cx.theme().foreground),
+            // Show the source as code if the math is invalid.
+            Err(_) => div()
+                .text_size(font_size)
+                .font_family(cx.theme().mono_font_family.clone())
+                .text_color(cx.theme().muted_foreground)
+                .child(self.source.clone())
+                .into_any_element(),
+        }
+    }
+
+    /// Returns the distance from the bottom of the inline math to the bottom of the line,
+    /// to align the baseline with the text in the line.
+    pub(crate) fn inline_bottom_margin(&self, font_size: Pixels, line_height: Pixels) -> Pixels {
+        let text_descent = (line_height - font_size * TEXT_HEIGHT) / 2. + font_size * TEXT_DESCENT;
+        text_descent - self.descent(font_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Math;
+
+    #[test]
+    fn test_math() {
+        let math = Math::new("\\frac{1}{2}", false);
+        assert!(math.layout.is_ok());
+        let math = Math::new("\\frac{1}", true);
+        assert!(math.layout.is_err());
+        assert_eq!(math.source, "\\frac{1}");
+    }
+}
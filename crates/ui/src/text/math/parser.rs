@@ -0,0 +1,875 @@
+use gpui::SharedString;
+
+/// The parsed TeX math expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum MathNode {
+    Row(Vec<MathNode>),
+    /// A italic variable, e.g. `x`.
+    Variable(String),
+    /// A upright text, e.g. numbers, `\text{..}`.
+    Text(String),
+    /// A symbol with the spacing class, e.g. `+`, `=`, `\alpha`.
+    Symbol(String, SymbolKind),
+    /// A large operator, e.g. `\sum`, `\int`.
+    ///
+    /// If `limits` is true, the scripts are placed above and below in display mode.
+    BigOperator {
+        symbol: String,
+        limits: bool,
+    },
+    /// A function name, e.g. `\sin`, `\lim`.
+    Function {
+        name: String,
+        limits: bool,
+    },
+    Fraction {
+        numerator: Box<MathNode>,
+        denominator: Box<MathNode>,
+        /// Whether to draw the fraction rule, false for `\binom`.
+        rule: bool,
+    },
+    Root {
+        index: Option<Box<MathNode>>,
+        body: Box<MathNode>,
+    },
+    Scripts {
+        base: Box<MathNode>,
+        sub: Option<Box<MathNode>>,
+        sup: Option<Box<MathNode>>,
+    },
+    /// The `\left( .. \right)`, the delimiters are stretched to the body height.
+    Fenced {
+        open: Option<String>,
+        body: Box<MathNode>,
+        close: Option<String>,
+    },
+    Matrix {
+        rows: Vec<Vec<MathNode>>,
+        open: Option<String>,
+        close: Option<String>,
+        align: MatrixAlign,
+    },
+    Accent {
+        base: Box<MathNode>,
+        accent: char,
+    },
+    Overline(Box<MathNode>),
+    Underline(Box<MathNode>),
+    Bold(Box<MathNode>),
+    /// A horizontal space in em.
+    Space(f32),
+}
+
+/// The symbol class, used to decide the spacing around the symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SymbolKind {
+    Ordinary,
+    Binary,
+    Relation,
+    Punctuation,
+    Open,
+    Close,
+}
+
+/// The column alignment of a matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MatrixAlign {
+    /// All columns are centered, e.g. `matrix`, `pmatrix`.
+    Center,
+    /// All columns are left aligned, e.g. `cases`.
+    Left,
+    /// The columns are right and left aligned alternately, e.g. `aligned`.
+    RightLeft,
+}
+
+/// The max depth of the nested groups and commands, to avoid the stack overflow.
+const MAX_DEPTH: usize = 64;
+
+/// Parse a TeX math expression.
+pub(crate) fn parse(source: &str) -> Result<MathNode, SharedString> {
+    parse_at_depth(source, 0)
+}
+
+fn parse_at_depth(source: &str, depth: usize) -> Result<MathNode, SharedString> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        pos: 0,
+        depth,
+    };
+
+    let row = parser.parse_row()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(format!("unexpected `{}`", c).into());
+    }
+
+    Ok(MathNode::Row(row))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// The depth of the nested atoms.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SharedString> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{}`, found `{}`", expected, c).into()),
+            None => Err(format!("expected `{}`", expected).into()),
+        }
+    }
+
+    /// Peek the command name at the current position without consuming it.
+    fn peek_command(&self) -> Option<String> {
+        if self.peek() != Some('\\') {
+            return None;
+        }
+
+        let mut name = String::new();
+        for c in self.chars[self.pos + 1..].iter() {
+            if c.is_ascii_alphabetic() {
+                name.push(*c);
+            } else {
+                if name.is_empty() {
+                    name.push(*c);
+                }
+                break;
+            }
+        }
+        Some(name)
+    }
+
+    /// Read the command name after the `\`.
+    fn read_command(&mut self) -> Result<String, SharedString> {
+        self.expect('\\')?;
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            name.push(c);
+            self.pos += 1;
+        }
+        if name.is_empty() {
+            match self.next() {
+                Some(c) => name.push(c),
+                None => return Err("unexpected end after `\\`".into()),
+            }
+        }
+        Ok(name)
+    }
+
+    /// Read the raw text of a `{..}` group, the nested braces are kept.
+    fn read_raw_group(&mut self) -> Result<String, SharedString> {
+        self.expect('{')?;
+        self.read_raw_until('{', '}')
+    }
+
+    /// Read the raw text until the `close` char that matches the already consumed `open`.
+    fn read_raw_until(&mut self, open: char, close: char) -> Result<String, SharedString> {
+        let mut depth = 0;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                None => return Err(format!("missing `{}`", close).into()),
+                Some(c) if c == close && depth == 0 => return Ok(text),
+                Some(c) => {
+                    if c == open {
+                        depth += 1;
+                    } else if c == close {
+                        depth -= 1;
+                    }
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    /// Parse the nodes until the end of the group, cell, row or `\right`.
+    fn parse_row(&mut self) -> Result<Vec<MathNode>, SharedString> {
+        let mut nodes = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some('}') | Some('&') => break,
+                Some('\\') => {
+                    if matches!(
+                        self.peek_command().as_deref(),
+                        Some("\\" | "right" | "end" | "cr")
+                    ) {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+
+            let atom = self.parse_atom()?;
+            let node = self.parse_scripts(atom)?;
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    /// Parse the argument of a command, a `{..}` group or a single token.
+    fn parse_argument(&mut self) -> Result<MathNode, SharedString> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("missing argument".into()),
+            Some('{') | Some('\\') => self.parse_atom(),
+            Some(c) => {
+                self.pos += 1;
+                Ok(char_atom(c))
+            }
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<MathNode, SharedString> {
+        if self.depth >= MAX_DEPTH {
+            return Err("too deeply nested".into());
+        }
+
+        self.depth += 1;
+        let atom = self.parse_atom_inner();
+        self.depth -= 1;
+        atom
+    }
+
+    fn parse_atom_inner(&mut self) -> Result<MathNode, SharedString> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("unexpected end".into()),
+            Some('{') => {
+                self.pos += 1;
+                let row = self.parse_row()?;
+                self.expect('}')?;
+                Ok(MathNode::Row(row))
+            }
+            Some('}') => Err("unexpected `}`".into()),
+            // The scripts without base, e.g. `^2`.
+            Some('^') | Some('_') => Ok(MathNode::Row(vec![])),
+            Some('\\') => self.parse_command(),
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = self.peek() {
+                    if !(c.is_ascii_digit() || c == '.') {
+                        break;
+                    }
+                    number.push(c);
+                    self.pos += 1;
+                }
+                Ok(MathNode::Text(number))
+            }
+            Some(c) => {
+                self.pos += 1;
+                Ok(char_atom(c))
+            }
+        }
+    }
+
+    fn parse_scripts(&mut self, mut base: MathNode) -> Result<MathNode, SharedString> {
+        let mut sub = None;
+        let mut sup = None;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('^') => {
+                    self.pos += 1;
+                    if sup.is_some() {
+                        return Err("double superscript".into());
+                    }
+                    sup = Some(Box::new(self.parse_argument()?));
+                }
+                Some('_') => {
+                    self.pos += 1;
+                    if sub.is_some() {
+                        return Err("double subscript".into());
+                    }
+                    sub = Some(Box::new(self.parse_argument()?));
+                }
+                Some('\'') => {
+                    self.pos += 1;
+                    let prime = MathNode::Symbol("′".into(), SymbolKind::Ordinary);
+                    match sup.as_deref_mut() {
+                        Some(MathNode::Row(nodes)) => nodes.push(prime),
+                        Some(_) => return Err("double superscript".into()),
+                        None => sup = Some(Box::new(MathNode::Row(vec![prime]))),
+                    }
+                }
+                Some('\\') => match self.peek_command().as_deref() {
+                    Some(name @ ("limits" | "nolimits")) => {
+                        let value = name == "limits";
+                        self.read_command()?;
+                        match &mut base {
+                            MathNode::BigOperator { limits, .. }
+                            | MathNode::Function { limits, .. } => *limits = value,
+                            _ => return Err(format!("`\\{}` without operator", name).into()),
+                        }
+                    }
+                    _ => break,
+                },
+                _ => break,
+            }
+        }
+
+        if sub.is_none() && sup.is_none() {
+            return Ok(base);
+        }
+
+        Ok(MathNode::Scripts {
+            base: Box::new(base),
+            sub,
+            sup,
+        })
+    }
+
+    fn parse_command(&mut self) -> Result<MathNode, SharedString> {
+        let name = self.read_command()?;
+        let node = match name.as_str() {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => MathNode::Fraction {
+                numerator: Box::new(self.parse_argument()?),
+                denominator: Box::new(self.parse_argument()?),
+                rule: true,
+            },
+            "binom" | "dbinom" | "tbinom" => MathNode::Fenced {
+                open: Some("(".into()),
+                body: Box::new(MathNode::Fraction {
+                    numerator: Box::new(self.parse_argument()?),
+                    denominator: Box::new(self.parse_argument()?),
+                    rule: false,
+                }),
+                close: Some(")".into()),
+            },
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let raw = self.read_raw_until('[', ']')?;
+                    Some(Box::new(parse_at_depth(&raw, self.depth + 1)?))
+                } else {
+                    None
+                };
+                MathNode::Root {
+                    index,
+                    body: Box::new(self.parse_argument()?),
+                }
+            }
+            "left" => {
+                let open = self.read_delimiter()?;
+                let body = self.parse_row()?;
+                self.skip_whitespace();
+                if self.peek_command().as_deref() != Some("right") {
+                    return Err("missing `\\right`".into());
+                }
+                self.read_command()?;
+                let close = self.read_delimiter()?;
+                MathNode::Fenced {
+                    open,
+                    body: Box::new(MathNode::Row(body)),
+                    close,
+                }
+            }
+            "right" => return Err("unexpected `\\right`".into()),
+            "begin" => self.parse_environment()?,
+            "text" | "textrm" | "textup" | "textnormal" | "mbox" | "mathrm" | "operatorname"
+            | "mathsf" | "mathtt" => MathNode::Text(self.read_raw_group()?),
+            "textit" | "mathit" => MathNode::Variable(self.read_raw_group()?),
+            "mathbf" | "textbf" | "boldsymbol" | "bm" => {
+                MathNode::Bold(Box::new(self.parse_argument()?))
+            }
+            "mathbb" => MathNode::Text(map_letters(&self.read_raw_group()?, double_struck)),
+            "mathcal" | "mathscr" => MathNode::Text(map_letters(&self.read_raw_group()?, script)),
+            "overline" => MathNode::Overline(Box::new(self.parse_argument()?)),
+            "underline" => MathNode::Underline(Box::new(self.parse_argument()?)),
+            "," | "thinspace" => MathNode::Space(3. / 18.),
+            ":" | ">" | "medspace" => MathNode::Space(4. / 18.),
+            ";" | "thickspace" => MathNode::Space(5. / 18.),
+            "!" => MathNode::Space(-3. / 18.),
+            " " => MathNode::Space(0.25),
+            "quad" => MathNode::Space(1.),
+            "qquad" => MathNode::Space(2.),
+            _ => {
+                if let Some(accent) = accent(&name) {
+                    MathNode::Accent {
+                        base: Box::new(self.parse_argument()?),
+                        accent,
+                    }
+                } else if let Some((symbol, limits)) = big_operator(&name) {
+                    MathNode::BigOperator {
+                        symbol: symbol.into(),
+                        limits,
+                    }
+                } else if let Some(limits) = function(&name) {
+                    MathNode::Function { name, limits }
+                } else if let Some((symbol, kind)) = symbol(&name) {
+                    MathNode::Symbol(symbol.into(), kind)
+                } else {
+                    return Err(format!("unsupported command `\\{}`", name).into());
+                }
+            }
+        };
+
+        Ok(node)
+    }
+
+    /// Read the delimiter after `\left` or `\right`, `.` means no delimiter.
+    fn read_delimiter(&mut self) -> Result<Option<String>, SharedString> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("missing delimiter".into()),
+            Some('.') => {
+                self.pos += 1;
+                Ok(None)
+            }
+            Some('\\') => {
+                let name = self.read_command()?;
+                match symbol(&name) {
+                    Some((symbol, SymbolKind::Open | SymbolKind::Close | SymbolKind::Ordinary)) => {
+                        Ok(Some(symbol.into()))
+                    }
+                    _ => Err(format!("invalid delimiter `\\{}`", name).into()),
+                }
+            }
+            Some(c) => {
+                self.pos += 1;
+                Ok(Some(c.to_string()))
+            }
+        }
+    }
+
+    fn parse_environment(&mut self) -> Result<MathNode, SharedString> {
+        let env = self.read_raw_group()?;
+        let (open, close, align) = match env.as_str() {
+            "matrix" | "smallmatrix" => (None, None, MatrixAlign::Center),
+            "pmatrix" => (Some("("), Some(")"), MatrixAlign::Center),
+            "bmatrix" => (Some("["), Some("]"), MatrixAlign::Center),
+            "Bmatrix" => (Some("{"), Some("}"), MatrixAlign::Center),
+            "vmatrix" => (Some("|"), Some("|"), MatrixAlign::Center),
+            "Vmatrix" => (Some("‖"), Some("‖"), MatrixAlign::Center),
+            "cases" => (Some("{"), None, MatrixAlign::Left),
+            "aligned" | "align" | "align*" | "split" => (None, None, MatrixAlign::RightLeft),
+            "gathered" | "gather" | "gather*" => (None, None, MatrixAlign::Center),
+            "array" => {
+                // The column spec is ignored.
+                self.read_raw_group()?;
+                (None, None, MatrixAlign::Center)
+            }
+            _ => return Err(format!("unsupported environment `{}`", env).into()),
+        };
+
+        let mut rows: Vec<Vec<MathNode>> = vec![vec![]];
+        loop {
+            let cell = self.parse_row()?;
+            rows.last_mut().unwrap().push(MathNode::Row(cell));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some('&') => {
+                    self.pos += 1;
+                }
+                Some('\\') => match self.read_command()?.as_str() {
+                    "\\" | "cr" => rows.push(vec![]),
+                    "end" => {
+                        let end = self.read_raw_group()?;
+                        if end != env {
+                            return Err(format!("`\\begin{{{}}}` ended by `{}`", env, end).into());
+                        }
+                        break;
+                    }
+                    name => return Err(format!("unexpected `\\{}`", name).into()),
+                },
+                _ => return Err(format!("missing `\\end{{{}}}`", env).into()),
+            }
+        }
+
+        // Remove the empty last row, e.g. ends with `\\`.
+        if rows.len() > 1
+            && rows
+                .last()
+                .is_some_and(|row| row.len() == 1 && row[0] == MathNode::Row(vec![]))
+        {
+            rows.pop();
+        }
+
+        Ok(MathNode::Matrix {
+            rows,
+            open: open.map(Into::into),
+            close: close.map(Into::into),
+            align,
+        })
+    }
+}
+
+fn char_atom(c: char) -> MathNode {
+    match c {
+        c if c.is_alphabetic() => MathNode::Variable(c.to_string()),
+        c if c.is_ascii_digit() => MathNode::Text(c.to_string()),
+        '+' => MathNode::Symbol("+".into(), SymbolKind::Binary),
+        '-' => MathNode::Symbol("−".into(), SymbolKind::Binary),
+        '*' => MathNode::Symbol("∗".into(), SymbolKind::Binary),
+        '=' | '<' | '>' | ':' => MathNode::Symbol(c.to_string(), SymbolKind::Relation),
+        ',' | ';' => MathNode::Symbol(c.to_string(), SymbolKind::Punctuation),
+        '(' | '[' => MathNode::Symbol(c.to_string(), SymbolKind::Open),
+        ')' | ']' | '!' | '?' => MathNode::Symbol(c.to_string(), SymbolKind::Close),
+        _ => MathNode::Symbol(c.to_string(), SymbolKind::Ordinary),
+    }
+}
+
+fn map_letters(text: &str, f: fn(char) -> char) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).map(f).collect()
+}
+
+/// Map the letter to the Mathematical Double-Struck, e.g. `R` to `ℝ`.
+fn double_struck(c: char) -> char {
+    let special = match c {
+        'C' => Some('ℂ'),
+        'H' => Some('ℍ'),
+        'N' => Some('ℕ'),
+        'P' => Some('ℙ'),
+        'Q' => Some('ℚ'),
+        'R' => Some('ℝ'),
+        'Z' => Some('ℤ'),
+        _ => None,
+    };
+    special
+        .or_else(|| offset_letter(c, 0x1D538, 0x1D552))
+        .unwrap_or(c)
+}
+
+/// Map the letter to the Mathematical Script, e.g. `L` to `ℒ`.
+fn script(c: char) -> char {
+    let special = match c {
+        'B' => Some('ℬ'),
+        'E' => Some('ℰ'),
+        'F' => Some('ℱ'),
+        'H' => Some('ℋ'),
+        'I' => Some('ℐ'),
+        'L' => Some('ℒ'),
+        'M' => Some('ℳ'),
+        'R' => Some('ℛ'),
+        'e' => Some('ℯ'),
+        'g' => Some('ℊ'),
+        'o' => Some('ℴ'),
+        _ => None,
+    };
+    special
+        .or_else(|| offset_letter(c, 0x1D49C, 0x1D4B6))
+        .unwrap_or(c)
+}
+
+fn offset_letter(c: char, upper: u32, lower: u32) -> Option<char> {
+    match c {
+        'A'..='Z' => char::from_u32(upper + (c as u32 - 'A' as u32)),
+        'a'..='z' => char::from_u32(lower + (c as u32 - 'a' as u32)),
+        _ => None,
+    }
+}
+
+fn accent(name: &str) -> Option<char> {
+    Some(match name {
+        "hat" | "widehat" => '\u{0302}',
+        "tilde" | "widetilde" => '\u{0303}',
+        "bar" => '\u{0304}',
+        "breve" => '\u{0306}',
+        "dot" => '\u{0307}',
+        "ddot" => '\u{0308}',
+        "check" => '\u{030C}',
+        "acute" => '\u{0301}',
+        "grave" => '\u{0300}',
+        "vec" | "overrightarrow" => '\u{20D7}',
+        _ => return None,
+    })
+}
+
+fn big_operator(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "sum" => ("∑", true),
+        "prod" => ("∏", true),
+        "coprod" => ("∐", true),
+        "bigcup" => ("⋃", true),
+        "bigcap" => ("⋂", true),
+        "bigvee" => ("⋁", true),
+        "bigwedge" => ("⋀", true),
+        "bigoplus" => ("⨁", true),
+        "bigotimes" => ("⨂", true),
+        "int" => ("∫", false),
+        "iint" => ("∬", false),
+        "iiint" => ("∭", false),
+        "oint" => ("∮", false),
+        _ => return None,
+    })
+}
+
+/// Returns whether the function name takes limits in display mode.
+fn function(name: &str) -> Option<bool> {
+    Some(match name {
+        "lim" | "liminf" | "limsup" | "max" | "min" | "sup" | "inf" | "det" | "gcd" | "Pr"
+        | "argmax" | "argmin" => true,
+        "sin" | "cos" | "tan" | "cot" | "sec" | "csc" | "arcsin" | "arccos" | "arctan" | "sinh"
+        | "cosh" | "tanh" | "coth" | "log" | "ln" | "lg" | "exp" | "deg" | "dim" | "ker"
+        | "arg" | "hom" | "mod" | "bmod" => false,
+        _ => return None,
+    })
+}
+
+fn symbol(name: &str) -> Option<(&'static str, SymbolKind)> {
+    use SymbolKind::*;
+
+    Some(match name {
+        // Greek letters
+        "alpha" => ("α", Ordinary),
+        "beta" => ("β", Ordinary),
+        "gamma" => ("γ", Ordinary),
+        "delta" => ("δ", Ordinary),
+        "epsilon" => ("ϵ", Ordinary),
+        "varepsilon" => ("ε", Ordinary),
+        "zeta" => ("ζ", Ordinary),
+        "eta" => ("η", Ordinary),
+        "theta" => ("θ", Ordinary),
+        "vartheta" => ("ϑ", Ordinary),
+        "iota" => ("ι", Ordinary),
+        "kappa" => ("κ", Ordinary),
+        "lambda" => ("λ", Ordinary),
+        "mu" => ("μ", Ordinary),
+        "nu" => ("ν", Ordinary),
+        "xi" => ("ξ", Ordinary),
+        "pi" => ("π", Ordinary),
+        "varpi" => ("ϖ", Ordinary),
+        "rho" => ("ρ", Ordinary),
+        "varrho" => ("ϱ", Ordinary),
+        "sigma" => ("σ", Ordinary),
+        "varsigma" => ("ς", Ordinary),
+        "tau" => ("τ", Ordinary),
+        "upsilon" => ("υ", Ordinary),
+        "phi" => ("ϕ", Ordinary),
+        "varphi" => ("φ", Ordinary),
+        "chi" => ("χ", Ordinary),
+        "psi" => ("ψ", Ordinary),
+        "omega" => ("ω", Ordinary),
+        "Gamma" => ("Γ", Ordinary),
+        "Delta" => ("Δ", Ordinary),
+        "Theta" => ("Θ", Ordinary),
+        "Lambda" => ("Λ", Ordinary),
+        "Xi" => ("Ξ", Ordinary),
+        "Pi" => ("Π", Ordinary),
+        "Sigma" => ("Σ", Ordinary),
+        "Upsilon" => ("Υ", Ordinary),
+        "Phi" => ("Φ", Ordinary),
+        "Psi" => ("Ψ", Ordinary),
+        "Omega" => ("Ω", Ordinary),
+        // Binary operators
+        "pm" => ("±", Binary),
+        "mp" => ("∓", Binary),
+        "times" => ("×", Binary),
+        "div" => ("÷", Binary),
+        "cdot" => ("⋅", Binary),
+        "ast" => ("∗", Binary),
+        "star" => ("⋆", Binary),
+        "circ" => ("∘", Binary),
+        "bullet" => ("∙", Binary),
+        "oplus" => ("⊕", Binary),
+        "ominus" => ("⊖", Binary),
+        "otimes" => ("⊗", Binary),
+        "cup" => ("∪", Binary),
+        "cap" => ("∩", Binary),
+        "setminus" => ("∖", Binary),
+        "wedge" | "land" => ("∧", Binary),
+        "vee" | "lor" => ("∨", Binary),
+        // Relations
+        "leq" | "le" => ("≤", Relation),
+        "geq" | "ge" => ("≥", Relation),
+        "neq" | "ne" => ("≠", Relation),
+        "approx" => ("≈", Relation),
+        "equiv" => ("≡", Relation),
+        "sim" => ("∼", Relation),
+        "simeq" => ("≃", Relation),
+        "cong" => ("≅", Relation),
+        "propto" => ("∝", Relation),
+        "ll" => ("≪", Relation),
+        "gg" => ("≫", Relation),
+        "in" => ("∈", Relation),
+        "notin" => ("∉", Relation),
+        "ni" => ("∋", Relation),
+        "subset" => ("⊂", Relation),
+        "supset" => ("⊃", Relation),
+        "subseteq" => ("⊆", Relation),
+        "supseteq" => ("⊇", Relation),
+        "mid" => ("∣", Relation),
+        "parallel" => ("∥", Relation),
+        "perp" => ("⊥", Relation),
+        "to" | "rightarrow" => ("→", Relation),
+        "leftarrow" | "gets" => ("←", Relation),
+        "leftrightarrow" => ("↔", Relation),
+        "Rightarrow" | "implies" => ("⇒", Relation),
+        "Leftarrow" => ("⇐", Relation),
+        "Leftrightarrow" | "iff" => ("⇔", Relation),
+        "mapsto" => ("↦", Relation),
+        "uparrow" => ("↑", Relation),
+        "downarrow" => ("↓", Relation),
+        // Delimiters
+        "{" | "lbrace" => ("{", Open),
+        "}" | "rbrace" => ("}", Close),
+        "langle" => ("⟨", Open),
+        "rangle" => ("⟩", Close),
+        "lfloor" => ("⌊", Open),
+        "rfloor" => ("⌋", Close),
+        "lceil" => ("⌈", Open),
+        "rceil" => ("⌉", Close),
+        "|" | "Vert" => ("‖", Ordinary),
+        "vert" => ("|", Ordinary),
+        // Misc
+        "infty" => ("∞", Ordinary),
+        "partial" => ("∂", Ordinary),
+        "nabla" => ("∇", Ordinary),
+        "forall" => ("∀", Ordinary),
+        "exists" => ("∃", Ordinary),
+        "nexists" => ("∄", Ordinary),
+        "emptyset" | "varnothing" => ("∅", Ordinary),
+        "neg" | "lnot" => ("¬", Ordinary),
+        "angle" => ("∠", Ordinary),
+        "triangle" => ("△", Ordinary),
+        "hbar" => ("ℏ", Ordinary),
+        "ell" => ("ℓ", Ordinary),
+        "Re" => ("ℜ", Ordinary),
+        "Im" => ("ℑ", Ordinary),
+        "aleph" => ("ℵ", Ordinary),
+        "prime" => ("′", Ordinary),
+        "degree" => ("°", Ordinary),
+        "ldots" | "dots" => ("…", Ordinary),
+        "cdots" => ("⋯", Ordinary),
+        "vdots" => ("⋮", Ordinary),
+        "ddots" => ("⋱", Ordinary),
+        "%" => ("%", Ordinary),
+        "$" => ("$", Ordinary),
+        "#" => ("#", Ordinary),
+        "&" => ("&", Ordinary),
+        "_" => ("_", Ordinary),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MathNode, MatrixAlign, SymbolKind, parse};
+
+    fn var(s: &str) -> MathNode {
+        MathNode::Variable(s.into())
+    }
+
+    fn text(s: &str) -> MathNode {
+        MathNode::Text(s.into())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("x^2 + y_i").unwrap(),
+            MathNode::Row(vec![
+                MathNode::Scripts {
+                    base: Box::new(var("x")),
+                    sub: None,
+                    sup: Some(Box::new(text("2"))),
+                },
+                MathNode::Symbol("+".into(), SymbolKind::Binary),
+                MathNode::Scripts {
+                    base: Box::new(var("y")),
+                    sub: Some(Box::new(var("i"))),
+                    sup: None,
+                },
+            ])
+        );
+
+        assert_eq!(
+            parse("\\frac12").unwrap(),
+            MathNode::Row(vec![MathNode::Fraction {
+                numerator: Box::new(text("1")),
+                denominator: Box::new(text("2")),
+                rule: true,
+            }])
+        );
+
+        assert_eq!(
+            parse("\\sqrt[3]{\\alpha}").unwrap(),
+            MathNode::Row(vec![MathNode::Root {
+                index: Some(Box::new(MathNode::Row(vec![text("3")]))),
+                body: Box::new(MathNode::Row(vec![MathNode::Symbol(
+                    "α".into(),
+                    SymbolKind::Ordinary
+                )])),
+            }])
+        );
+
+        assert_eq!(
+            parse("\\sum\\nolimits_{i} \\mathbb{R}").unwrap(),
+            MathNode::Row(vec![
+                MathNode::Scripts {
+                    base: Box::new(MathNode::BigOperator {
+                        symbol: "∑".into(),
+                        limits: false,
+                    }),
+                    sub: Some(Box::new(MathNode::Row(vec![var("i")]))),
+                    sup: None,
+                },
+                text("ℝ"),
+            ])
+        );
+
+        assert_eq!(
+            parse("\\left( x \\right.").unwrap(),
+            MathNode::Row(vec![MathNode::Fenced {
+                open: Some("(".into()),
+                body: Box::new(MathNode::Row(vec![var("x")])),
+                close: None,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_matrix() {
+        assert_eq!(
+            parse("\\begin{pmatrix} a & b \\\\ c & d \\\\ \\end{pmatrix}").unwrap(),
+            MathNode::Row(vec![MathNode::Matrix {
+                rows: vec![
+                    vec![MathNode::Row(vec![var("a")]), MathNode::Row(vec![var("b")])],
+                    vec![MathNode::Row(vec![var("c")]), MathNode::Row(vec![var("d")])],
+                ],
+                open: Some("(".into()),
+                close: Some(")".into()),
+                align: MatrixAlign::Center,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("x^2^3").is_err());
+        assert!(parse("\\frac{1}").is_err());
+        assert!(parse("{x").is_err());
+        assert!(parse("x}").is_err());
+        assert!(parse("\\left( x").is_err());
+        assert!(parse("\\foo").is_err());
+        assert!(parse("\\begin{matrix} a \\end{pmatrix}").is_err());
+        assert!(parse(&format!("{}x{}", "{".repeat(10000), "}".repeat(10000))).is_err());
+        assert!(parse(&"\\mathbf".repeat(10000)).is_err());
+        assert!(parse(&format!("{}x{}", "{".repeat(32), "}".repeat(32))).is_ok());
+    }
+}
@@ -1,19 +1,35 @@
+mod block_list;
+mod clipboard;
+mod component;
 mod document;
+mod editor;
+mod export;
 mod format;
+mod front_matter;
+mod image;
 mod inline;
+mod math;
 mod node;
+mod search;
 mod state;
 mod style;
 mod text_view;
 mod utils;
 
+pub use component::{ComponentAttrValue, ComponentProps};
+pub use document::TocItem;
+pub use editor::{MarkdownEditor, MarkdownEditorState, MarkdownFormat, PasteImageFn};
+pub use front_matter::{FrontMatter, FrontMatterKind, FrontMatterValue};
 use gpui::{App, ElementId, IntoElement, RenderOnce, SharedString, Window};
 pub use state::*;
 pub use style::*;
 pub use text_view::*;
+pub use utils::FileRef;
 
 pub(crate) fn init(cx: &mut App) {
     state::init(cx);
+    search::init(cx);
+    editor::init(cx);
 }
 
 /// Create a new markdown text view with code location as id.
@@ -23,6 +39,13 @@ pub fn markdown(source: impl Into<SharedString>) -> TextView {
     TextView::markdown(id, source)
 }
 
+/// Create a new MDX text view with code location as id.
+#[track_caller]
+pub fn mdx(source: impl Into<SharedString>) -> TextView {
+    let id: ElementId = ElementId::CodeLocation(*std::panic::Location::caller());
+    TextView::mdx(id, source)
+}
+
 /// Create a new html text view with code location as id.
 #[track_caller]
 pub fn html(source: impl Into<SharedString>) -> TextView {
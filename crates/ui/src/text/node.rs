@@ -1,35 +1,43 @@
 use std::{
     collections::HashMap,
-    ops::Range,
+    ops::{Range, RangeInclusive},
     sync::{Arc, Mutex},
 };
 
 use gpui::{
-    AnyElement, App, DefiniteLength, Div, ElementId, FontStyle, FontWeight, Half, HighlightStyle,
-    InteractiveElement as _, IntoElement, Length, ObjectFit, ParentElement, SharedString,
-    SharedUri, StatefulInteractiveElement, Styled, StyledImage as _, Window, div, img,
+    AnyElement, App, DefiniteLength, Div, ElementId, FontStyle, FontWeight, HighlightStyle, Hsla,
+    Image, InteractiveElement as _, IntoElement, MouseButton, ParentElement, SharedString,
+    SharedUri, StatefulInteractiveElement, Styled, TextAlign, WeakEntity, Window, div,
     prelude::FluentBuilder as _, px, relative, rems,
 };
 use markdown::mdast;
 use ropey::Rope;
 
 use crate::{
-    ActiveTheme as _, Icon, IconName, StyledExt, h_flex,
+    ActiveTheme as _, Icon, IconName, Sizable as _, StyledExt,
+    checkbox::Checkbox,
+    clipboard::Clipboard,
+    description_list::{DescriptionItem, DescriptionList},
+    h_flex,
     highlighter::{HighlightTheme, SyntaxHighlighter},
     text::{
-        CodeBlockActionsFn,
+        CodeBlockActionsFn, TextViewState,
+        component::ComponentProps,
         document::NodeRenderOptions,
-        inline::{Inline, InlineState},
+        format::css::CssStyle,
+        front_matter::{FrontMatter, FrontMatterKind},
+        image::decode_data_uri,
+        inline::{EMBED_PLACEHOLDER, Inline, InlineEmbed, InlineState},
+        math::Math,
     },
-    tooltip::Tooltip,
     v_flex,
 };
 
 use super::{
-    TextViewStyle,
+    FrontMatterDisplay, LinkToken, TextViewStyle,
     utils::{
-        encode_uri_component, is_absolute_path, list_item_prefix, parse_file_ref_token,
-        parse_url_token, split_whitespace_token_ranges, FileRef,
+        list_item_prefix, parse_file_ref_token, parse_url_token, slugify,
+        split_whitespace_token_ranges,
     },
 };
 
@@ -39,18 +47,28 @@ pub(crate) enum BlockNode {
     /// Something like a Div container in HTML.
     Root {
         children: Vec<BlockNode>,
+        /// The CSS style of the HTML element.
+        style: Option<Box<CssStyle>>,
         span: Option<Span>,
     },
     Paragraph(Paragraph),
     Heading {
         level: u8,
         children: Paragraph,
+        /// The anchor slug, e.g. `hello-world` for `## Hello World`, unique in the document.
+        slug: SharedString,
         span: Option<Span>,
     },
     Blockquote {
         children: Vec<BlockNode>,
         span: Option<Span>,
     },
+    /// The GFM alert, a blockquote starts with a marker like `> [!NOTE]`.
+    Alert {
+        kind: AlertKind,
+        children: Vec<BlockNode>,
+        span: Option<Span>,
+    },
     List {
         /// Only contains ListItem, others will be ignored
         children: Vec<BlockNode>,
@@ -65,6 +83,11 @@ pub(crate) enum BlockNode {
         span: Option<Span>,
     },
     CodeBlock(CodeBlock),
+    /// The display math, e.g. `$$..$$`.
+    Math {
+        math: Math,
+        span: Option<Span>,
+    },
     Table(Table),
     Break {
         html: bool,
@@ -80,6 +103,27 @@ pub(crate) enum BlockNode {
         title: Option<SharedString>,
         span: Option<Span>,
     },
+    /// The MDX JSX element, rendered by the component registered in [`TextViewStyle::component`].
+    Component {
+        props: ComponentProps,
+        children: Vec<BlockNode>,
+        span: Option<Span>,
+    },
+    /// The front matter at the start of the Markdown, e.g. `---\ntitle: Hello\n---`.
+    FrontMatter {
+        front_matter: FrontMatter,
+        span: Option<Span>,
+    },
+    /// The footnote definition, e.g. `[^1]: ...`.
+    Footnote {
+        identifier: SharedString,
+        /// The number of the footnote, in the order of the first reference.
+        number: usize,
+        children: Vec<BlockNode>,
+        /// Whether this is the first footnote of the trailing section, to render a separator.
+        section_start: bool,
+        span: Option<Span>,
+    },
     Unknown,
 }
 
@@ -95,7 +139,11 @@ impl BlockNode {
     /// Combine all children, omitting the empt parent nodes.
     pub(super) fn compact(self) -> BlockNode {
         match self {
-            Self::Root { mut children, .. } if children.len() == 1 => children.remove(0).compact(),
+            Self::Root {
+                mut children,
+                style: None,
+                ..
+            } if children.len() == 1 => children.remove(0).compact(),
             _ => self,
         }
     }
@@ -107,24 +155,141 @@ impl BlockNode {
             BlockNode::Paragraph(paragraph) => paragraph.span,
             BlockNode::Heading { span, .. } => *span,
             BlockNode::Blockquote { span, .. } => *span,
+            BlockNode::Alert { span, .. } => *span,
             BlockNode::List { span, .. } => *span,
             BlockNode::ListItem { span, .. } => *span,
             BlockNode::CodeBlock(code_block) => code_block.span,
+            BlockNode::Math { span, .. } => *span,
             BlockNode::Table(table) => table.span,
             BlockNode::Break { span, .. } => *span,
             BlockNode::Divider { span, .. } => *span,
             BlockNode::Definition { span, .. } => *span,
+            BlockNode::FrontMatter { span, .. } => *span,
+            BlockNode::Component { span, .. } => *span,
+            BlockNode::Footnote { span, .. } => *span,
             BlockNode::Unknown { .. } => None,
         }
     }
 
-    pub(super) fn selected_text(&self) -> String {
+    /// Returns true if the node is the target of the `anchor`.
+    ///
+    /// - `{slug}` is the heading with the slug.
+    /// - `fn-{id}` is the footnote definition of `[^id]`.
+    /// - `fnref-{id}` is the block contains the first reference of `[^id]`.
+    pub(super) fn has_anchor(&self, anchor: &str) -> bool {
+        if let Some(id) = anchor.strip_prefix("fnref-") {
+            let url = format!("#fn-{}", id);
+            return self.has_link(&url);
+        }
+
+        match self {
+            BlockNode::Heading { slug, .. } => slug.as_ref() == anchor,
+            BlockNode::Footnote { identifier, .. } => {
+                anchor.strip_prefix("fn-") == Some(identifier.as_ref())
+            }
+            _ => self.children().iter().any(|c| c.has_anchor(anchor)),
+        }
+    }
+
+    /// Returns true if the node contains a link to the `url`.
+    fn has_link(&self, url: &str) -> bool {
+        match self {
+            BlockNode::Paragraph(paragraph)
+            | BlockNode::Heading {
+                children: paragraph,
+                ..
+            } => paragraph.has_link(url),
+            BlockNode::Table(table) => table
+                .children
+                .iter()
+                .any(|row| row.children.iter().any(|cell| cell.children.has_link(url))),
+            _ => self.children().iter().any(|c| c.has_link(url)),
+        }
+    }
+
+    /// Returns the block children of the node.
+    pub(super) fn children(&self) -> &[BlockNode] {
+        match self {
+            BlockNode::Root { children, .. }
+            | BlockNode::Blockquote { children, .. }
+            | BlockNode::Alert { children, .. }
+            | BlockNode::List { children, .. }
+            | BlockNode::ListItem { children, .. }
+            | BlockNode::Footnote { children, .. }
+            | BlockNode::Component { children, .. } => children,
+            _ => &[],
+        }
+    }
+
+    /// Collect the rendered inline texts in the node, with the state of each [`Inline`] element.
+    pub(super) fn text_segments(&self, segments: &mut Vec<(Arc<Mutex<InlineState>>, String)>) {
+        match self {
+            BlockNode::Paragraph(paragraph)
+            | BlockNode::Heading {
+                children: paragraph,
+                ..
+            } => paragraph.text_segments(segments),
+            BlockNode::Table(table) => {
+                for row in table.children.iter() {
+                    for cell in row.children.iter() {
+                        cell.children.text_segments(segments);
+                    }
+                }
+            }
+            BlockNode::CodeBlock(code_block) => {
+                let text = code_block.state.lock().unwrap().text.to_string();
+                if !text.is_empty() {
+                    segments.push((code_block.state.clone(), text));
+                }
+            }
+            _ => {
+                for child in self.children() {
+                    child.text_segments(segments);
+                }
+            }
+        }
+    }
+
+    /// Generate the slugs of the headings in the node, `counts` is the number of each slug
+    /// used before, to suffix the duplicates with a counter like GitHub, e.g. `hello-world-1`.
+    pub(super) fn update_heading_slugs(&mut self, counts: &mut HashMap<String, usize>) {
+        match self {
+            BlockNode::Heading { children, slug, .. } => {
+                let base = slugify(&children.text());
+                let count = counts.entry(base.clone()).or_insert(0);
+                *slug = if *count == 0 {
+                    base.into()
+                } else {
+                    format!("{}-{}", base, count).into()
+                };
+                *count += 1;
+            }
+            BlockNode::Root { children, .. }
+            | BlockNode::Blockquote { children, .. }
+            | BlockNode::Alert { children, .. }
+            | BlockNode::List { children, .. }
+            | BlockNode::ListItem { children, .. }
+            | BlockNode::Footnote { children, .. }
+            | BlockNode::Component { children, .. } => {
+                for child in children.iter_mut() {
+                    child.update_heading_slugs(counts);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the selected text of the node.
+    ///
+    /// If `all` is true, returns the whole text as it's fully selected,
+    /// this is used for the blocks that are not rendered (out of the viewport) in the selection.
+    pub(super) fn selected_text(&self, all: bool) -> String {
         let mut text = String::new();
         match self {
             BlockNode::Root { children, .. } => {
                 let mut block_text = String::new();
                 for c in children.iter() {
-                    block_text.push_str(&c.selected_text());
+                    block_text.push_str(&c.selected_text(all));
                 }
                 if !block_text.is_empty() {
                     text.push_str(&block_text);
@@ -133,7 +298,7 @@ impl BlockNode {
             }
             BlockNode::Paragraph(paragraph) => {
                 let mut block_text = String::new();
-                block_text.push_str(&paragraph.selected_text());
+                block_text.push_str(&paragraph.selected_text(all));
                 if !block_text.is_empty() {
                     text.push_str(&block_text);
                     text.push('\n');
@@ -141,7 +306,7 @@ impl BlockNode {
             }
             BlockNode::Heading { children, .. } => {
                 let mut block_text = String::new();
-                block_text.push_str(&children.selected_text());
+                block_text.push_str(&children.selected_text(all));
                 if !block_text.is_empty() {
                     text.push_str(&block_text);
                     text.push('\n');
@@ -149,18 +314,21 @@ impl BlockNode {
             }
             BlockNode::List { children, .. } => {
                 for c in children.iter() {
-                    text.push_str(&c.selected_text());
+                    text.push_str(&c.selected_text(all));
                 }
             }
             BlockNode::ListItem { children, .. } => {
                 for c in children.iter() {
-                    text.push_str(&c.selected_text());
+                    text.push_str(&c.selected_text(all));
                 }
             }
-            BlockNode::Blockquote { children, .. } => {
+            BlockNode::Blockquote { children, .. }
+            | BlockNode::Alert { children, .. }
+            | BlockNode::Footnote { children, .. }
+            | BlockNode::Component { children, .. } => {
                 let mut block_text = String::new();
                 for c in children.iter() {
-                    block_text.push_str(&c.selected_text());
+                    block_text.push_str(&c.selected_text(all));
                 }
 
                 if !block_text.is_empty() {
@@ -169,14 +337,17 @@ impl BlockNode {
                 }
             }
             BlockNode::Table(table) => {
+                // The selected cells are separated by tabs, to paste as TSV.
                 let mut block_text = String::new();
                 for row in table.children.iter() {
-                    let mut row_texts = vec![];
-                    for cell in row.children.iter() {
-                        row_texts.push(cell.children.selected_text());
-                    }
+                    let row_texts = row
+                        .children
+                        .iter()
+                        .map(|cell| cell.children.selected_text(all))
+                        .filter(|text| !text.is_empty())
+                        .collect::<Vec<_>>();
                     if !row_texts.is_empty() {
-                        block_text.push_str(&row_texts.join(" "));
+                        block_text.push_str(&row_texts.join("\t"));
                         block_text.push('\n');
                     }
                 }
@@ -187,13 +358,15 @@ impl BlockNode {
                 }
             }
             BlockNode::CodeBlock(code_block) => {
-                let block_text = code_block.selected_text();
+                let block_text = code_block.selected_text(all);
                 if !block_text.is_empty() {
                     text.push_str(&block_text);
                     text.push('\n');
                 }
             }
             BlockNode::Definition { .. }
+            | BlockNode::FrontMatter { .. }
+            | BlockNode::Math { .. }
             | BlockNode::Break { .. }
             | BlockNode::Divider { .. }
             | BlockNode::Unknown { .. } => {}
@@ -203,6 +376,71 @@ impl BlockNode {
     }
 }
 
+/// The kind of the GFM alert, e.g. `> [!NOTE]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AlertKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl AlertKind {
+    /// Parse the alert marker, e.g. `[!NOTE]`, case-insensitive.
+    pub(crate) fn parse(marker: &str) -> Option<Self> {
+        let name = marker.strip_prefix("[!")?.strip_suffix(']')?;
+        match name.to_ascii_uppercase().as_str() {
+            "NOTE" => Some(Self::Note),
+            "TIP" => Some(Self::Tip),
+            "IMPORTANT" => Some(Self::Important),
+            "WARNING" => Some(Self::Warning),
+            "CAUTION" => Some(Self::Caution),
+            _ => None,
+        }
+    }
+
+    fn marker(&self) -> &'static str {
+        match self {
+            Self::Note => "[!NOTE]",
+            Self::Tip => "[!TIP]",
+            Self::Important => "[!IMPORTANT]",
+            Self::Warning => "[!WARNING]",
+            Self::Caution => "[!CAUTION]",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Note => "Note",
+            Self::Tip => "Tip",
+            Self::Important => "Important",
+            Self::Warning => "Warning",
+            Self::Caution => "Caution",
+        }
+    }
+
+    fn icon(&self) -> IconName {
+        match self {
+            Self::Note => IconName::Info,
+            Self::Tip => IconName::CircleCheck,
+            Self::Important => IconName::Star,
+            Self::Warning => IconName::TriangleAlert,
+            Self::Caution => IconName::CircleX,
+        }
+    }
+
+    fn color(&self, cx: &App) -> Hsla {
+        match self {
+            Self::Note => cx.theme().info,
+            Self::Tip => cx.theme().success,
+            Self::Important => cx.theme().magenta,
+            Self::Warning => cx.theme().warning,
+            Self::Caution => cx.theme().danger,
+        }
+    }
+}
+
 #[allow(unused)]
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinkMark {
@@ -233,8 +471,11 @@ pub struct TextMark {
     pub bold: bool,
     pub italic: bool,
     pub strikethrough: bool,
+    pub underline: bool,
     pub code: bool,
     pub link: Option<LinkMark>,
+    pub color: Option<Hsla>,
+    pub background: Option<Hsla>,
 }
 
 impl TextMark {
@@ -253,6 +494,11 @@ impl TextMark {
         self
     }
 
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
     pub fn code(mut self) -> Self {
         self.code = true;
         self
@@ -286,6 +532,8 @@ pub struct ImageNode {
     pub alt: Option<SharedString>,
     pub width: Option<DefiniteLength>,
     pub height: Option<DefiniteLength>,
+    /// The decoded image of the `data:` URI.
+    pub(crate) data: Option<Arc<Image>>,
 }
 
 impl ImageNode {
@@ -313,6 +561,10 @@ pub(crate) struct InlineNode {
     /// The text content.
     pub(crate) text: SharedString,
     pub(crate) image: Option<ImageNode>,
+    /// The inline math, e.g. `$..$`.
+    pub(crate) math: Option<Math>,
+    /// The inline MDX JSX element, e.g. `<Tag>new</Tag>`.
+    pub(crate) component: Option<InlineComponent>,
     /// The text styles, each tuple contains the range of the text and the style.
     pub(crate) marks: Vec<(Range<usize>, TextMark)>,
 
@@ -321,7 +573,11 @@ pub(crate) struct InlineNode {
 
 impl PartialEq for InlineNode {
     fn eq(&self, other: &Self) -> bool {
-        self.text == other.text && self.image == other.image && self.marks == other.marks
+        self.text == other.text
+            && self.image == other.image
+            && self.math == other.math
+            && self.component == other.component
+            && self.marks == other.marks
     }
 }
 
@@ -330,6 +586,8 @@ impl InlineNode {
         Self {
             text: text.into(),
             image: None,
+            math: None,
+            component: None,
             marks: vec![],
             state: Arc::new(Mutex::new(InlineState::default())),
         }
@@ -341,12 +599,74 @@ impl InlineNode {
         this
     }
 
+    pub(crate) fn math(math: Math) -> Self {
+        let mut this = Self::new(EMBED_PLACEHOLDER);
+        this.math = Some(math);
+        this
+    }
+
+    pub(crate) fn component(props: ComponentProps, children: Paragraph) -> Self {
+        let mut this = Self::new(EMBED_PLACEHOLDER);
+        this.component = Some(InlineComponent { props, children });
+        this
+    }
+
+    /// Returns the plain text of the node, the inline math is returned as the TeX source,
+    /// and the inline component as the text of its children.
+    pub(crate) fn plain_text(&self) -> String {
+        if let Some(math) = &self.math {
+            return math.source.to_string();
+        }
+        if let Some(component) = &self.component {
+            return component.children.text();
+        }
+        self.text.to_string()
+    }
+
     pub(crate) fn marks(mut self, marks: Vec<(Range<usize>, TextMark)>) -> Self {
         self.marks = marks;
         self
     }
 }
 
+/// The inline MDX JSX element in the paragraph, rendered in the text flow
+/// by the component registered in [`TextViewStyle::component`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct InlineComponent {
+    pub(crate) props: ComponentProps,
+    pub(crate) children: Paragraph,
+}
+
+impl InlineComponent {
+    pub(crate) fn to_markdown(&self) -> String {
+        let tag = self.props.to_tag();
+        if self.children.is_empty() {
+            format!("<{} />", tag)
+        } else {
+            format!(
+                "<{}>{}</{}>",
+                tag,
+                self.children.to_markdown().trim_end_matches('\n'),
+                self.props.name
+            )
+        }
+    }
+
+    fn render(&self, node_cx: &NodeContext, window: &mut Window, cx: &mut App) -> AnyElement {
+        let children = if self.children.is_empty() {
+            vec![]
+        } else {
+            vec![self.children.render(node_cx, window, cx).into_any_element()]
+        };
+
+        // The unknown components are rendered as a container of the children.
+        match node_cx.style.components.get(&self.props.name) {
+            Some(render) => render(&self.props, children, window, cx),
+            None => div().children(children).into_any_element(),
+        }
+    }
+}
+
 /// The paragraph element, contains multiple text nodes.
 ///
 /// Unlike other Element, this is cloneable, because it is used in the Node AST.
@@ -381,24 +701,73 @@ impl Paragraph {
         }
     }
 
-    pub(super) fn selected_text(&self) -> String {
-        let mut text = String::new();
+    /// Returns the selected inline nodes, with the selected range in the text of each node.
+    ///
+    /// If `all` is true, returns all the nodes as they are fully selected.
+    pub(super) fn selected_nodes(&self, all: bool) -> Vec<(&InlineNode, Range<usize>)> {
+        let mut nodes = vec![];
+        // The texts are rendered in groups split by the images, see `Paragraph::render`,
+        // the selection is saved in the state of the node ends the group, or the paragraph for the last group.
+        let mut group_start = 0;
+        for (ix, node) in self.children.iter().enumerate() {
+            let is_last = ix + 1 == self.children.len();
+            let ends_group = node.image.is_some();
+            if !ends_group && !is_last {
+                continue;
+            }
 
-        for c in self.children.iter() {
-            let state = c.state.lock().unwrap();
-            if let Some(selection) = &state.selection {
-                let part_text = state.text.clone();
-                text.push_str(&part_text[selection.start..selection.end]);
+            let state = if ends_group { &node.state } else { &self.state };
+            let selection = if all {
+                Some(0..usize::MAX)
+            } else {
+                state
+                    .lock()
+                    .unwrap()
+                    .selection
+                    .as_ref()
+                    .map(|selection| selection.start..selection.end)
+            };
+
+            let group = &self.children[group_start..=ix];
+            group_start = ix + 1;
+            let Some(selection) = selection else {
+                continue;
+            };
+
+            let mut offset = 0;
+            for node in group {
+                let len = node.text.len();
+                let start = selection.start.max(offset);
+                let end = selection.end.min(offset + len);
+                // The image is selected if the selection reaches the end of the group.
+                let image_selected = node.image.is_some()
+                    && (selection.start..=selection.end).contains(&(offset + len));
+                if start < end || image_selected {
+                    nodes.push((node, start.min(end) - offset..end - offset));
+                }
+                offset += len;
             }
         }
 
-        let state = self.state.lock().unwrap();
-        if let Some(selection) = &state.selection {
-            let all_text = state.text.clone();
-            text.push_str(&all_text[selection.start..selection.end]);
+        nodes
+    }
+
+    /// Returns the selected text, or the whole text if `all` is true.
+    pub(super) fn selected_text(&self, all: bool) -> String {
+        if all {
+            return self.text();
         }
 
-        text
+        self.selected_nodes(false)
+            .into_iter()
+            .map(|(node, range)| {
+                if node.math.is_some() || node.component.is_some() {
+                    node.plain_text()
+                } else {
+                    node.text[range].to_string()
+                }
+            })
+            .collect()
     }
 }
 
@@ -413,6 +782,134 @@ impl Table {
     pub(crate) fn column_align(&self, index: usize) -> ColumnumnAlign {
         self.column_aligns.get(index).copied().unwrap_or_default()
     }
+
+    /// Returns the slots of the table grid with the `colspan` and `rowspan` applied,
+    /// all rows have the same number of columns.
+    pub(crate) fn slots(&self) -> Vec<Vec<TableSlot>> {
+        let row_count = self.children.len();
+        let mut slots: Vec<Vec<Option<TableSlot>>> = vec![vec![]; row_count];
+        for (row_ix, row) in self.children.iter().enumerate() {
+            let mut col_ix = 0;
+            for (cell_ix, cell) in row.children.iter().enumerate() {
+                // Skip the slots covered by the rowspan of the cells above.
+                while slots[row_ix].get(col_ix).is_some_and(Option::is_some) {
+                    col_ix += 1;
+                }
+
+                let colspan = cell.colspan.clamp(1, MAX_SPAN);
+                let rowspan = cell.rowspan.clamp(1, row_count - row_ix);
+                for r in 0..rowspan {
+                    let row_slots = &mut slots[row_ix + r];
+                    if row_slots.len() < col_ix + colspan {
+                        row_slots.resize(col_ix + colspan, None);
+                    }
+                    for c in 0..colspan {
+                        row_slots[col_ix + c] = Some(match (r, c) {
+                            (0, 0) => TableSlot::Cell {
+                                row: row_ix,
+                                cell: cell_ix,
+                            },
+                            (_, 0) => TableSlot::SpannedRow {
+                                colspan,
+                                last: r + 1 == rowspan,
+                            },
+                            _ => TableSlot::SpannedCol,
+                        });
+                    }
+                }
+                col_ix += colspan;
+            }
+        }
+
+        let col_count = slots.iter().map(Vec::len).max().unwrap_or(0);
+        slots
+            .into_iter()
+            .map(|row| {
+                let mut row = row
+                    .into_iter()
+                    .map(|slot| slot.unwrap_or(TableSlot::Empty))
+                    .collect::<Vec<_>>();
+                row.resize(col_count, TableSlot::Empty);
+                row
+            })
+            .collect()
+    }
+
+    /// Returns the table in TSV (tab-separated values), the merged cells are filled with empty values.
+    pub(crate) fn to_tsv(&self) -> String {
+        self.cells_to_tsv(0..=usize::MAX, 0..=usize::MAX)
+    }
+
+    /// Returns the cells in the `rows` and `cols` of the grid in TSV, see [`Table::to_tsv`].
+    pub(crate) fn cells_to_tsv(
+        &self,
+        rows: RangeInclusive<usize>,
+        cols: RangeInclusive<usize>,
+    ) -> String {
+        self.slots()
+            .iter()
+            .enumerate()
+            .filter(|(row_ix, _)| rows.contains(row_ix))
+            .map(|(_, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(col_ix, _)| cols.contains(col_ix))
+                    .map(|(_, slot)| match *slot {
+                        TableSlot::Cell { row, cell } => self.children[row].children[cell]
+                            .children
+                            .text()
+                            .replace(['\t', '\n'], " "),
+                        _ => String::new(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The selected cells of a table, alt-click a cell to select it, and alt-shift-click to select the range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TableSelection {
+    /// The span of the table in the source.
+    pub(crate) table: Span,
+    /// The (row, column) in the grid of [`Table::slots`] where the selection starts.
+    pub(crate) anchor: (usize, usize),
+    /// The (row, column) in the grid where the selection ends.
+    pub(crate) head: (usize, usize),
+}
+
+impl TableSelection {
+    pub(crate) fn rows(&self) -> RangeInclusive<usize> {
+        self.anchor.0.min(self.head.0)..=self.anchor.0.max(self.head.0)
+    }
+
+    pub(crate) fn cols(&self) -> RangeInclusive<usize> {
+        self.anchor.1.min(self.head.1)..=self.anchor.1.max(self.head.1)
+    }
+
+    fn contains(&self, table: Option<Span>, row_ix: usize, col_ix: usize) -> bool {
+        table == Some(self.table) && self.rows().contains(&row_ix) && self.cols().contains(&col_ix)
+    }
+}
+
+/// The max `colspan` of a table cell, same as HTML.
+const MAX_SPAN: usize = 1000;
+
+/// A slot in the table grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TableSlot {
+    /// The slot of a cell starts, `cell` is the index in the `row` of [`Table::children`].
+    Cell { row: usize, cell: usize },
+    /// Covered by the `rowspan` of a cell above, in the first column of the cell.
+    ///
+    /// The `last` is true if this is the last row of the cell.
+    SpannedRow { colspan: usize, last: bool },
+    /// Covered by the `colspan` of a cell, except the first column.
+    SpannedCol,
+    /// No cell, the row is shorter than the others.
+    Empty,
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
@@ -423,6 +920,16 @@ pub(crate) enum ColumnumnAlign {
     Right,
 }
 
+impl From<TextAlign> for ColumnumnAlign {
+    fn from(value: TextAlign) -> Self {
+        match value {
+            TextAlign::Left => ColumnumnAlign::Left,
+            TextAlign::Center => ColumnumnAlign::Center,
+            TextAlign::Right => ColumnumnAlign::Right,
+        }
+    }
+}
+
 impl From<mdast::AlignKind> for ColumnumnAlign {
     fn from(value: mdast::AlignKind) -> Self {
         match value {
@@ -439,10 +946,28 @@ pub(crate) struct TableRow {
     pub children: Vec<TableCell>,
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct TableCell {
     pub children: Paragraph,
     pub width: Option<DefiniteLength>,
+    /// The number of columns the cell spans, at least 1.
+    pub colspan: usize,
+    /// The number of rows the cell spans, at least 1.
+    pub rowspan: usize,
+    /// The alignment of the cell, overrides the column alignment.
+    pub align: Option<ColumnumnAlign>,
+}
+
+impl Default for TableCell {
+    fn default() -> Self {
+        Self {
+            children: Paragraph::default(),
+            width: None,
+            colspan: 1,
+            rowspan: 1,
+            align: None,
+        }
+    }
 }
 
 impl Paragraph {
@@ -476,16 +1001,23 @@ impl Paragraph {
         self.children.push(text);
     }
 
-    pub(crate) fn push_image(&mut self, image: ImageNode) {
+    pub(crate) fn push_image(&mut self, mut image: ImageNode) {
+        if image.data.is_none() {
+            image.data = decode_data_uri(&image.url.to_string());
+        }
         self.children.push(InlineNode::image(image));
     }
 
+    pub(crate) fn push_math(&mut self, math: Math) {
+        self.children.push(InlineNode::math(math));
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         self.children.is_empty()
             || self
                 .children
                 .iter()
-                .all(|node| node.text.is_empty() && node.image.is_none())
+                .all(|node| node.text.is_empty() && node.image.is_none() && node.math.is_none())
     }
 
     /// Return length of children text.
@@ -499,6 +1031,36 @@ impl Paragraph {
     pub(crate) fn merge(&mut self, other: Self) {
         self.children.extend(other.children);
     }
+
+    /// Returns the plain text of the paragraph, see [`InlineNode::plain_text`].
+    pub(crate) fn text(&self) -> String {
+        self.children.iter().map(|node| node.plain_text()).collect()
+    }
+
+    /// Collect the inline texts split by the images, same as [`Paragraph::render`].
+    fn text_segments(&self, segments: &mut Vec<(Arc<Mutex<InlineState>>, String)>) {
+        let mut text = String::new();
+        for inline_node in self.children.iter() {
+            text.push_str(&inline_node.text);
+            let is_split = inline_node.image.is_some();
+            if is_split && !text.is_empty() {
+                segments.push((inline_node.state.clone(), std::mem::take(&mut text)));
+            }
+        }
+
+        if !text.is_empty() {
+            segments.push((self.state.clone(), text));
+        }
+    }
+
+    /// Returns true if the paragraph contains a link to the `url`.
+    pub(crate) fn has_link(&self, url: &str) -> bool {
+        self.children.iter().any(|node| {
+            node.marks
+                .iter()
+                .any(|(_, mark)| mark.link.as_ref().is_some_and(|link| link.url == url))
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -550,10 +1112,13 @@ impl CodeBlock {
         }
     }
 
-    pub(super) fn selected_text(&self) -> String {
+    /// Returns the selected text, or the whole code if `all` is true.
+    pub(super) fn selected_text(&self, all: bool) -> String {
         let mut text = String::new();
         let state = self.state.lock().unwrap();
-        if let Some(selection) = &state.selection {
+        if all {
+            text.push_str(&state.text);
+        } else if let Some(selection) = &state.selection {
             let part_text = state.text.clone();
             text.push_str(&part_text[selection.start..selection.end]);
         }
@@ -570,26 +1135,37 @@ impl CodeBlock {
         let style = &node_cx.style;
         let code_links = build_code_token_links(self.state.lock().unwrap().text.as_ref(), style);
 
+        let renderer = self
+            .lang
+            .as_ref()
+            .and_then(|lang| style.code_block_renderer_for(lang))
+            .cloned();
+
         div()
             .when(!options.is_last, |this| this.pb(style.paragraph_gap))
             .child(
                 div()
                     .id(("codeblock", options.ix))
-                    .p_3()
-                    .rounded(cx.theme().radius)
-                    .bg(cx.theme().muted)
-                    .font_family(cx.theme().mono_font_family.clone())
-                    .text_size(cx.theme().mono_font_size)
                     .relative()
-                    .refine_style(&style.code_block)
-                    .child(Inline::new(
-                        "code",
-                        self.state.clone(),
-                        code_links.clone(),
-                        self.styles.clone(),
-                        Vec::new(),
-                        None,
-                    ))
+                    .map(|this| match renderer {
+                        // The custom renderer replaces the highlighted code.
+                        Some(renderer) => this.child(renderer(self, window, cx)),
+                        None => this
+                            .p_3()
+                            .rounded(cx.theme().radius)
+                            .bg(cx.theme().muted)
+                            .font_family(cx.theme().mono_font_family.clone())
+                            .text_size(cx.theme().mono_font_size)
+                            .refine_style(&style.code_block)
+                            .child(Inline::new(
+                                "code",
+                                self.state.clone(),
+                                code_links.clone(),
+                                self.styles.clone(),
+                                Vec::new(),
+                                None,
+                            )),
+                    })
                     .when_some(node_cx.code_block_actions.clone(), |this, actions| {
                         this.child(
                             div()
@@ -607,70 +1183,43 @@ impl CodeBlock {
     }
 }
 
-
+/// Build the links of the tokens in code by the [`TextViewStyle::link_resolver`].
 fn build_code_token_links(text: &str, style: &TextViewStyle) -> Vec<(Range<usize>, LinkMark)> {
-    let options = &style.code_token_links;
-    if !options.enabled || text.is_empty() {
+    if !style.code_links_enabled() || text.is_empty() {
         return Vec::new();
     }
 
     let mut links = Vec::new();
     for range in split_whitespace_token_ranges(text) {
-        if range.start >= range.end {
-            continue;
-        }
         let token = &text[range.clone()];
         if token.trim().is_empty() {
             continue;
         }
 
-        if let Some(url) = parse_url_token(token) {
-            let mut link = LinkMark::default();
-            link.url = url.into();
-            link.requires_modifiers = true;
-            link.decorate = false;
-            links.push((range.clone(), link));
-            continue;
-        }
+        let token = if let Some(url) = parse_url_token(token) {
+            LinkToken::Url(url.into())
+        } else if let Some(file_ref) = parse_file_ref_token(token) {
+            LinkToken::File(file_ref)
+        } else {
+            LinkToken::Code(token.to_string().into())
+        };
 
-        if let Some(file_ref) = parse_file_ref_token(token) {
-            let can_link = options.worktree_id.is_some() || is_absolute_path(&file_ref.path);
-            if !can_link {
-                continue;
-            }
-            if let Some(url) = build_ctx_open_url(&file_ref, options.worktree_id.as_ref()) {
-                let mut link = LinkMark::default();
-                link.url = url;
-                link.requires_modifiers = true;
-                link.decorate = false;
-                links.push((range.clone(), link));
-            }
+        if let Some(url) = style.resolve_link(&token) {
+            links.push((
+                range,
+                LinkMark {
+                    url,
+                    requires_modifiers: true,
+                    decorate: false,
+                    ..Default::default()
+                },
+            ));
         }
     }
 
     links
 }
 
-fn build_ctx_open_url(file_ref: &FileRef, worktree_id: Option<&SharedString>) -> Option<SharedString> {
-    let mut params: Vec<String> = Vec::new();
-    if is_absolute_path(&file_ref.path) {
-        params.push(format!("path={}", encode_uri_component(&file_ref.path)));
-    } else {
-        let Some(worktree_id) = worktree_id else {
-            return None;
-        };
-        params.push(format!("worktreeId={}", encode_uri_component(worktree_id.as_ref())));
-        params.push(format!("file={}", encode_uri_component(&file_ref.path)));
-    }
-    if let Some(line) = file_ref.line {
-        params.push(format!("line={}", line));
-    }
-    if let Some(col) = file_ref.col {
-        params.push(format!("col={}", col));
-    }
-    Some(format!("ctx://open?{}", params.join("&")).into())
-}
-
 /// A context for rendering nodes, contains link references.
 #[derive(Default, Clone)]
 pub(crate) struct NodeContext {
@@ -680,28 +1229,41 @@ pub(crate) struct NodeContext {
     pub(crate) link_refs: HashMap<SharedString, LinkMark>,
     pub(crate) style: TextViewStyle,
     pub(crate) code_block_actions: Option<Arc<CodeBlockActionsFn>>,
+    /// The state of the TextView, used to emit events from nodes.
+    pub(crate) state: Option<WeakEntity<TextViewState>>,
+    /// The footnote identifiers, in the order of the first reference.
+    pub(crate) footnotes: Vec<SharedString>,
+    /// The selected table cells of the TextView.
+    pub(crate) table_selection: Option<TableSelection>,
 }
 
 impl NodeContext {
     pub(super) fn add_ref(&mut self, identifier: SharedString, link: LinkMark) {
         self.link_refs.insert(identifier, link);
     }
+
+    /// Returns the number (starts from 1) of the footnote, numbered in the order of the first reference.
+    pub(super) fn footnote_number(&mut self, identifier: &str) -> usize {
+        if let Some(ix) = self.footnotes.iter().position(|id| id == identifier) {
+            return ix + 1;
+        }
+
+        self.footnotes.push(identifier.to_string().into());
+        self.footnotes.len()
+    }
 }
 
 impl PartialEq for NodeContext {
     fn eq(&self, other: &Self) -> bool {
-        self.link_refs == other.link_refs && self.style == other.style
+        self.link_refs == other.link_refs
+            && self.footnotes == other.footnotes
+            && self.style == other.style
         // Note: code_block_buttons is intentionally not compared (closures can't be compared)
     }
 }
 
 impl Paragraph {
-    fn render(
-        &self,
-        node_cx: &NodeContext,
-        _window: &mut Window,
-        cx: &mut App,
-    ) -> impl IntoElement {
+    fn render(&self, node_cx: &NodeContext, window: &mut Window, cx: &mut App) -> impl IntoElement {
         let span = self.span;
         let children = &self.children;
 
@@ -715,6 +1277,7 @@ impl Paragraph {
         let inline_code_style_opt = inline_code_enabled.then(|| inline_code_style.clone());
         let mut code_ranges: Vec<Range<usize>> = vec![];
         let mut offset = 0;
+        let mut embeds = vec![];
 
         let mut ix = 0;
         for inline_node in children {
@@ -741,28 +1304,11 @@ impl Paragraph {
                             code_ranges.clone(),
                             inline_code_style_opt.clone(),
                         )
+                        .embeds(std::mem::take(&mut embeds))
                         .into_any_element(),
                     );
                 }
-                child_nodes.push(
-                    img(image.url.clone())
-                        .id(ix)
-                        .object_fit(ObjectFit::Contain)
-                        .max_w(relative(1.))
-                        .when_some(image.width, |this, width| this.w(width))
-                        .when_some(image.link.clone(), |this, link| {
-                            let title = image.title();
-                            this.cursor_pointer()
-                                .tooltip(move |window, cx| {
-                                    Tooltip::new(title.clone()).build(window, cx)
-                                })
-                                .on_click(move |_, _, cx| {
-                                    cx.stop_propagation();
-                                    cx.open_url(&link.url);
-                                })
-                        })
-                        .into_any_element(),
-                );
+                child_nodes.push(image.render(ix, node_cx, window, cx));
 
                 text.clear();
                 links.clear();
@@ -770,6 +1316,14 @@ impl Paragraph {
                 code_ranges.clear();
                 offset = 0;
             } else {
+                if let Some(math) = &inline_node.math {
+                    embeds.push((offset, InlineEmbed::Math(math.clone())));
+                }
+                if let Some(component) = &inline_node.component {
+                    let element = component.render(node_cx, window, cx);
+                    embeds.push((offset, InlineEmbed::Element(element)));
+                }
+
                 let mut node_highlights = vec![];
                 for (range, style) in &inline_node.marks {
                     let inner_range = (offset + range.start)..(offset + range.end);
@@ -787,6 +1341,18 @@ impl Paragraph {
                             ..Default::default()
                         });
                     }
+                    if style.underline {
+                        highlight.underline = Some(gpui::UnderlineStyle {
+                            thickness: gpui::px(1.),
+                            ..Default::default()
+                        });
+                    }
+                    if let Some(color) = style.color {
+                        highlight.color = Some(color);
+                    }
+                    if let Some(background) = style.background {
+                        highlight.background_color = Some(background);
+                    }
                     if style.code {
                         if inline_code_enabled {
                             code_ranges.push(inner_range.clone());
@@ -817,6 +1383,18 @@ impl Paragraph {
                     node_highlights.push((inner_range, highlight));
                 }
 
+                // Link the tokens in the inline code, unless the code is in a link.
+                let is_code = inline_node.marks.iter().any(|(_, mark)| mark.code);
+                let is_link = inline_node
+                    .marks
+                    .iter()
+                    .any(|(_, mark)| mark.link.is_some());
+                if is_code && !is_link {
+                    for (range, link) in build_code_token_links(&inline_node.text, &node_cx.style) {
+                        links.push(((offset + range.start)..(offset + range.end), link));
+                    }
+                }
+
                 highlights = gpui::combine_highlights(highlights, node_highlights).collect();
                 offset += text_len;
             }
@@ -839,6 +1417,7 @@ impl Paragraph {
                     code_ranges,
                     inline_code_style_opt,
                 )
+                .embeds(embeds)
                 .into_any_element(),
             );
         }
@@ -853,6 +1432,13 @@ impl Paragraph {
             .children
             .iter()
             .map(|text_node| {
+                if let Some(math) = &text_node.math {
+                    return format!("${}$", math.source);
+                }
+                if let Some(component) = &text_node.component {
+                    return component.to_markdown();
+                }
+
                 let mut text = text_node.text.to_string();
                 for (range, style) in &text_node.marks {
                     if style.bold {
@@ -923,6 +1509,19 @@ impl BlockNode {
                     .collect::<Vec<_>>()
                     .join("\n")
             }
+            BlockNode::Alert { kind, children, .. } => {
+                let content = children
+                    .iter()
+                    .map(|child| child.to_markdown())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                std::iter::once(kind.marker())
+                    .chain(content.lines())
+                    .map(|line| format!("> {}", line))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
             BlockNode::List {
                 children, ordered, ..
             } => children
@@ -963,6 +1562,7 @@ impl BlockNode {
                     code_block.code()
                 )
             }
+            BlockNode::Math { math, .. } => format!("$$\n{}\n$$", math.source),
             BlockNode::Table(table) => {
                 let header = table
                     .children
@@ -1023,6 +1623,40 @@ impl BlockNode {
                     format!("[{}]: {}", identifier, url)
                 }
             }
+            BlockNode::Component {
+                props, children, ..
+            } => {
+                let tag = props.to_tag();
+                if children.is_empty() {
+                    format!("<{} />", tag)
+                } else {
+                    let content = children
+                        .iter()
+                        .map(|child| child.to_markdown())
+                        .collect::<Vec<_>>()
+                        .join("\n\n");
+                    format!("<{}>\n{}\n</{}>", tag, content, props.name)
+                }
+            }
+            BlockNode::FrontMatter { front_matter, .. } => {
+                let fence = match front_matter.kind {
+                    FrontMatterKind::Yaml => "---",
+                    FrontMatterKind::Toml => "+++",
+                };
+                format!("{}\n{}\n{}", fence, front_matter.source, fence)
+            }
+            BlockNode::Footnote {
+                identifier,
+                children,
+                ..
+            } => {
+                let content = children
+                    .iter()
+                    .map(|child| child.to_markdown())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                format!("[^{}]: {}", identifier, content)
+            }
             BlockNode::Unknown { .. } => "".to_string(),
         }
         .trim()
@@ -1044,7 +1678,7 @@ impl BlockNode {
                 children,
                 spread,
                 checked,
-                ..
+                span,
             } => v_flex()
                 .id(("li", options.ix))
                 .when(*spread, |this| this.child(div()))
@@ -1094,24 +1728,23 @@ impl BlockNode {
                                         })
                                         .when_some(*checked, |this, checked| {
                                             // Todo list checkbox
+                                            let span = span.unwrap_or_default();
+                                            let state = node_cx.state.clone();
                                             this.child(
-                                                div()
-                                                    .flex()
-                                                    .mt(rems(0.4))
+                                                Checkbox::new(span)
+                                                    .mt(rems(0.2))
                                                     .mr_1p5()
-                                                    .size(rems(0.875))
-                                                    .items_center()
-                                                    .justify_center()
-                                                    .rounded(cx.theme().radius.half())
-                                                    .border_1()
-                                                    .border_color(cx.theme().primary)
-                                                    .text_color(cx.theme().primary_foreground)
-                                                    .when(checked, |this| {
-                                                        this.bg(cx.theme().primary).child(
-                                                            Icon::new(IconName::Check)
-                                                                .size_2()
-                                                                .text_xs(),
-                                                        )
+                                                    .xsmall()
+                                                    .tab_stop(false)
+                                                    .checked(checked)
+                                                    .on_click(move |checked, _, cx| {
+                                                        cx.stop_propagation();
+                                                        let Some(state) = state.as_ref() else {
+                                                            return;
+                                                        };
+                                                        _ = state.update(cx, |state, cx| {
+                                                            state.toggle_task(span, *checked, cx);
+                                                        });
                                                     }),
                                             )
                                         })
@@ -1148,99 +1781,148 @@ impl BlockNode {
         window: &mut Window,
         cx: &mut App,
     ) -> impl IntoElement {
-        const DEFAULT_LENGTH: usize = 5;
-        const MAX_LENGTH: usize = 150;
-        let col_lens = match item {
-            BlockNode::Table(table) => {
-                let mut col_lens = vec![];
-                for row in table.children.iter() {
-                    for (ix, cell) in row.children.iter().enumerate() {
-                        if col_lens.len() <= ix {
-                            col_lens.push(DEFAULT_LENGTH);
-                        }
+        const MIN_LENGTH: usize = 5;
+        const MAX_LENGTH: usize = 40;
+        let BlockNode::Table(table) = item else {
+            return div().into_any_element();
+        };
 
-                        let len = cell.children.text_len();
-                        if len > col_lens[ix] {
-                            col_lens[ix] = len;
-                        }
+        let slots = table.slots();
+        let row_count = slots.len();
+        let col_count = slots.first().map_or(0, |row| row.len());
+
+        // The length of each column in chars, by the cells without colspan.
+        let mut col_lens = vec![MIN_LENGTH; col_count];
+        for row in slots.iter() {
+            for (col_ix, slot) in row.iter().enumerate() {
+                if let TableSlot::Cell { row, cell } = *slot {
+                    let cell = &table.children[row].children[cell];
+                    if cell.colspan <= 1 {
+                        let len = cell.children.text_len().min(MAX_LENGTH);
+                        col_lens[col_ix] = col_lens[col_ix].max(len);
                     }
                 }
-                col_lens
             }
-            _ => vec![],
+        }
+
+        // The columns keep the width by the content and grow to fill the table,
+        // the table is scrollable horizontally if the columns are wider than it.
+        let col_width = |col_ix: usize, colspan: usize| -> DefiniteLength {
+            let width = col_lens[col_ix..col_ix + colspan]
+                .iter()
+                .map(|len| *len as f32 * 0.5 + 1.)
+                .sum::<f32>();
+            rems(width).into()
         };
 
-        match item {
-            BlockNode::Table(table) => div()
-                .pb(rems(1.))
-                .w_full()
-                .child(
+        let border_color = cx.theme().border;
+        let selection_color = cx.theme().selection;
+        let mut rows = Vec::with_capacity(row_count);
+        for (row_ix, row) in slots.iter().enumerate() {
+            let is_last_row = row_ix + 1 == row_count;
+            let mut cells = Vec::with_capacity(row.len());
+            for (col_ix, slot) in row.iter().enumerate() {
+                // The rowspan cell is rendered in the first row, and merged with the empty cells
+                // in the next rows by hiding the borders between them.
+                let (cell, colspan, border_b) = match *slot {
+                    TableSlot::Cell { row, cell } => {
+                        let cell = &table.children[row].children[cell];
+                        (Some(cell), cell.colspan, cell.rowspan <= 1)
+                    }
+                    TableSlot::SpannedRow { colspan, last } => (None, colspan, last),
+                    TableSlot::SpannedCol => continue,
+                    TableSlot::Empty => (None, 1, true),
+                };
+                let colspan = colspan.clamp(1, col_count - col_ix);
+                let is_last_col = col_ix + colspan == col_count;
+                let align = cell
+                    .and_then(|cell| cell.align)
+                    .unwrap_or(table.column_align(col_ix));
+                let selected = node_cx
+                    .table_selection
+                    .is_some_and(|selection| selection.contains(table.span, row_ix, col_ix));
+
+                cells.push(
                     div()
-                        .id(("table", options.ix))
-                        .w_full()
-                        .border_1()
-                        .border_color(cx.theme().border)
-                        .rounded(cx.theme().radius)
-                        .children({
-                            let mut rows = Vec::with_capacity(table.children.len());
-                            for (row_ix, row) in table.children.iter().enumerate() {
-                                rows.push(
-                                    div()
-                                        .id("row")
-                                        .w_full()
-                                        .when(row_ix < table.children.len() - 1, |this| {
-                                            this.border_b_1()
-                                        })
-                                        .border_color(cx.theme().border)
-                                        .flex()
-                                        .flex_row()
-                                        .children({
-                                            let mut cells = Vec::with_capacity(row.children.len());
-                                            for (ix, cell) in row.children.iter().enumerate() {
-                                                let align = table.column_align(ix);
-                                                let is_last_col = ix == row.children.len() - 1;
-                                                let len = col_lens
-                                                    .get(ix)
-                                                    .copied()
-                                                    .unwrap_or(MAX_LENGTH)
-                                                    .min(MAX_LENGTH);
-
-                                                cells.push(
-                                                    div()
-                                                        .id("cell")
-                                                        .flex()
-                                                        .when(
-                                                            align == ColumnumnAlign::Center,
-                                                            |this| this.justify_center(),
-                                                        )
-                                                        .when(
-                                                            align == ColumnumnAlign::Right,
-                                                            |this| this.justify_end(),
-                                                        )
-                                                        .w(Length::Definite(relative(len as f32)))
-                                                        .px_2()
-                                                        .py_1()
-                                                        .when(!is_last_col, |this| {
-                                                            this.border_r_1()
-                                                                .border_color(cx.theme().border)
-                                                        })
-                                                        .truncate()
-                                                        .child(
-                                                            cell.children
-                                                                .render(node_cx, window, cx),
-                                                        ),
-                                                )
-                                            }
-                                            cells
-                                        }),
-                                )
-                            }
-                            rows
+                        .id(("cell", col_ix))
+                        .flex_shrink_0()
+                        .w(col_width(col_ix, colspan))
+                        .map(|mut this| {
+                            this.style().flex_grow = Some(colspan as f32);
+                            this
+                        })
+                        .px_2()
+                        .py_1()
+                        .when(border_b && !is_last_row, |this| this.border_b_1())
+                        .when(!is_last_col, |this| this.border_r_1())
+                        .border_color(border_color)
+                        .map(|this| match align {
+                            ColumnumnAlign::Left => this,
+                            ColumnumnAlign::Center => this.text_center(),
+                            ColumnumnAlign::Right => this.text_right(),
+                        })
+                        .when(selected, |this| this.bg(selection_color))
+                        .when_some(
+                            table.span.zip(node_cx.state.clone()),
+                            |this, (span, state)| {
+                                this.on_mouse_down(MouseButton::Left, move |event, _, cx| {
+                                    if !event.modifiers.alt {
+                                        return;
+                                    }
+
+                                    cx.stop_propagation();
+                                    _ = state.update(cx, |state, cx| {
+                                        let extend = event.modifiers.shift;
+                                        state.select_table_cell(span, (row_ix, col_ix), extend, cx);
+                                    });
+                                })
+                            },
+                        )
+                        .when_some(cell, |this, cell| {
+                            this.child(cell.children.render(node_cx, window, cx))
                         }),
-                )
-                .into_any_element(),
-            _ => div().into_any_element(),
+                );
+            }
+
+            rows.push(div().id(("row", row_ix)).flex().flex_row().children(cells));
         }
+
+        div()
+            .pb(rems(1.))
+            .w_full()
+            .relative()
+            .group("table")
+            .child(
+                div()
+                    .id(("table", options.ix))
+                    .w_full()
+                    .flex()
+                    .overflow_x_scroll()
+                    .child(
+                        div()
+                            .flex_none()
+                            .min_w_full()
+                            .border_1()
+                            .border_color(border_color)
+                            .rounded(cx.theme().radius)
+                            .children(rows),
+                    ),
+            )
+            .child(
+                div()
+                    .absolute()
+                    .top_1()
+                    .right_1()
+                    .bg(cx.theme().background)
+                    .rounded(cx.theme().radius)
+                    .invisible()
+                    .group_hover("table", |this| this.visible())
+                    .child(Clipboard::new(("copy-table", options.ix)).value_fn({
+                        let table = table.clone();
+                        move |_, _| table.to_tsv().into()
+                    })),
+            )
+            .into_any_element()
     }
 
     pub(crate) fn render_block(
@@ -1258,8 +1940,11 @@ impl BlockNode {
         };
 
         match self {
-            BlockNode::Root { children, .. } => div()
+            BlockNode::Root {
+                children, style, ..
+            } => div()
                 .id(("div", ix))
+                .when_some(style.as_ref(), |this, style| style.apply_to(this))
                 .children(children.into_iter().enumerate().map(move |(ix, node)| {
                     node.render_block(NodeRenderOptions { ix, ..options }, node_cx, window, cx)
                 }))
@@ -1316,6 +2001,99 @@ impl BlockNode {
                         }),
                 )
                 .into_any_element(),
+            BlockNode::Alert { kind, children, .. } => {
+                let color = kind.color(cx);
+                div()
+                    .w_full()
+                    .pb(mb)
+                    .child(
+                        v_flex()
+                            .id(("alert", ix))
+                            .w_full()
+                            .gap_1()
+                            .px_4()
+                            .py_2p5()
+                            .bg(color.opacity(0.08))
+                            .border_1()
+                            .border_color(color)
+                            .rounded(cx.theme().radius)
+                            .child(
+                                h_flex()
+                                    .gap_2()
+                                    .text_color(color)
+                                    .font_semibold()
+                                    .child(Icon::new(kind.icon()).small())
+                                    .child(kind.label()),
+                            )
+                            .children({
+                                let children_len = children.len();
+                                children.into_iter().enumerate().map(move |(index, c)| {
+                                    let is_last = index == children_len - 1;
+                                    c.render_block(options.is_last(is_last), node_cx, window, cx)
+                                })
+                            }),
+                    )
+                    .into_any_element()
+            }
+            BlockNode::Footnote {
+                identifier,
+                number,
+                children,
+                section_start,
+                ..
+            } => {
+                let state = node_cx.state.clone();
+                let anchor = format!("fnref-{}", identifier);
+                let link_color = cx.theme().link;
+                div()
+                    .id(("footnote", ix))
+                    .w_full()
+                    .when(!options.is_last, |this| this.pb(rems(0.5)))
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .when(*section_start, |this| {
+                        this.mt_2()
+                            .pt_3()
+                            .border_t_1()
+                            .border_color(cx.theme().border)
+                    })
+                    .child(
+                        h_flex()
+                            .items_start()
+                            .gap_1()
+                            .child(
+                                div()
+                                    .flex_none()
+                                    .min_w(rems(1.25))
+                                    .child(format!("{}.", number)),
+                            )
+                            .child(v_flex().flex_1().overflow_hidden().children({
+                                let children_len = children.len();
+                                children.into_iter().enumerate().map(move |(index, c)| {
+                                    let is_last = index == children_len - 1;
+                                    c.render_block(options.is_last(is_last), node_cx, window, cx)
+                                })
+                            }))
+                            .child(
+                                div()
+                                    .id("backlink")
+                                    .flex_none()
+                                    .cursor_pointer()
+                                    .text_color(link_color)
+                                    .child("↩")
+                                    .on_click(move |_, _, cx| {
+                                        cx.stop_propagation();
+                                        let Some(state) = state.as_ref() else {
+                                            return;
+                                        };
+                                        _ = state.update(cx, |state, cx| {
+                                            state.scroll_to_anchor(&anchor, cx);
+                                        });
+                                    }),
+                            ),
+                    )
+                    .into_any_element()
+            }
             BlockNode::List {
                 children, ordered, ..
             } => v_flex()
@@ -1348,6 +2126,23 @@ impl BlockNode {
                 })
                 .into_any_element(),
             BlockNode::CodeBlock(code_block) => code_block.render(&options, node_cx, window, cx),
+            BlockNode::Math { math, .. } => {
+                let font_size = window.text_style().font_size.to_pixels(window.rem_size());
+                div()
+                    .id(("math", ix))
+                    .w_full()
+                    .pb(mb)
+                    .overflow_x_scroll()
+                    .child(
+                        div()
+                            .flex()
+                            .min_w_full()
+                            .justify_center()
+                            .py_1()
+                            .child(math.render(font_size, cx)),
+                    )
+                    .into_any_element()
+            }
             BlockNode::Table { .. } => {
                 Self::render_table(self, &options, node_cx, window, cx).into_any_element()
             }
@@ -1356,6 +2151,36 @@ impl BlockNode {
                 .child(div().id("divider").bg(cx.theme().border).h(px(2.)))
                 .into_any_element(),
             BlockNode::Break { .. } => div().id("break").into_any_element(),
+            BlockNode::Component {
+                props, children, ..
+            } => {
+                let children_len = children.len();
+                let children = children
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, c)| {
+                        c.render_block(options.is_last(ix + 1 == children_len), node_cx, window, cx)
+                    })
+                    .collect::<Vec<_>>();
+
+                // The unknown components are rendered as a container of the children.
+                let el = match node_cx.style.components.get(&props.name) {
+                    Some(render) => render(props, children, window, cx),
+                    None => v_flex().children(children).into_any_element(),
+                };
+                div().pb(mb).child(el).into_any_element()
+            }
+            BlockNode::FrontMatter { front_matter, .. } => match node_cx.style.front_matter {
+                FrontMatterDisplay::Hidden => div().into_any_element(),
+                FrontMatterDisplay::DescriptionList => div()
+                    .pb(mb)
+                    .child(DescriptionList::new().columns(1).children(
+                        front_matter.fields.iter().map(|(key, value)| {
+                            DescriptionItem::new(key.clone()).value(value.to_string())
+                        }),
+                    ))
+                    .into_any_element(),
+            },
             BlockNode::Unknown { .. } | BlockNode::Definition { .. } => div().into_any_element(),
             _ => {
                 if cfg!(debug_assertions) {
@@ -0,0 +1,518 @@
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use aho_corasick::AhoCorasick;
+use gpui::{
+    AnyElement, App, AppContext as _, Context, Entity, Half, InteractiveElement as _, IntoElement,
+    KeyBinding, ParentElement as _, Styled, Subscription, Window, div, prelude::FluentBuilder as _,
+};
+
+use crate::{
+    ActiveTheme, Disableable, IconName, Selectable, Sizable,
+    actions::SelectUp,
+    button::{Button, ButtonVariants},
+    h_flex,
+    input::{Escape, Input, InputEvent, InputState, Search},
+    label::Label,
+    text::{TextViewState, document::ParsedDocument, inline::InlineState},
+    v_flex,
+};
+
+const CONTEXT: &'static str = "TextViewSearch";
+
+pub(super) fn init(cx: &mut App) {
+    cx.bind_keys(vec![KeyBinding::new(
+        "shift-enter",
+        SelectUp,
+        Some(CONTEXT),
+    )]);
+}
+
+/// A search match in the rendered text.
+#[derive(Debug, Clone)]
+struct SearchMatch {
+    /// The index of the top-level block contains the match.
+    block_ix: usize,
+    /// The state of the inline text contains the match.
+    state: Arc<Mutex<InlineState>>,
+    /// The byte range in the inline text.
+    range: Range<usize>,
+}
+
+/// Find in the rendered text of the document, not the markdown or HTML source.
+///
+/// The matches are saved into the [`InlineState`] to be highlighted in painting.
+#[derive(Debug, Default)]
+pub(super) struct DocumentSearch {
+    query: Option<AhoCorasick>,
+    /// The query is lowercased, and the text is lowercased to match.
+    case_insensitive: bool,
+    matches: Vec<SearchMatch>,
+    current_ix: usize,
+}
+
+impl DocumentSearch {
+    /// Update the search query and re-match the document, an empty `query` to clear.
+    pub(super) fn update_query(
+        &mut self,
+        query: &str,
+        case_insensitive: bool,
+        document: &ParsedDocument,
+    ) {
+        if query.len() > 0 {
+            // The ASCII text is matched as is, the others are lowercased, see `find_ranges`.
+            let query = if case_insensitive {
+                query.to_lowercase()
+            } else {
+                query.to_string()
+            };
+            self.query = Some(
+                AhoCorasick::builder()
+                    .ascii_case_insensitive(case_insensitive)
+                    .build(&[query])
+                    .expect("failed to build AhoCorasick query in DocumentSearch"),
+            );
+        } else {
+            self.query = None;
+        }
+        self.case_insensitive = case_insensitive;
+        self.current_ix = 0;
+        self.clear_highlights();
+        self.matches.clear();
+        self.update(document, 0);
+    }
+
+    /// Re-match the blocks from `start_block`, e.g. after the content changed,
+    /// the matches in the previous blocks and the current match index are kept.
+    pub(super) fn update(&mut self, document: &ParsedDocument, start_block: usize) {
+        self.set_active(false);
+        let keep = self
+            .matches
+            .partition_point(|item| item.block_ix < start_block);
+        for item in self.matches.drain(keep..) {
+            let mut state = item.state.lock().unwrap();
+            state.search_ranges.clear();
+            state.active_search = None;
+        }
+        let Some(query) = &self.query else {
+            return;
+        };
+
+        for (block_ix, block) in document.blocks.iter().enumerate().skip(start_block) {
+            let mut segments = vec![];
+            block.text_segments(&mut segments);
+
+            for (state, text) in segments {
+                let ranges = find_ranges(query, &text, self.case_insensitive);
+                if ranges.is_empty() {
+                    continue;
+                }
+
+                state.lock().unwrap().search_ranges = ranges.clone();
+                self.matches
+                    .extend(ranges.into_iter().map(|range| SearchMatch {
+                        block_ix,
+                        state: state.clone(),
+                        range,
+                    }));
+            }
+        }
+
+        self.current_ix = self.current_ix.min(self.matches.len().saturating_sub(1));
+        self.set_active(true);
+    }
+
+    /// Clear the query and the highlights.
+    pub(super) fn clear(&mut self) {
+        self.clear_highlights();
+        self.matches.clear();
+        self.query = None;
+        self.current_ix = 0;
+    }
+
+    fn clear_highlights(&self) {
+        for item in self.matches.iter() {
+            let mut state = item.state.lock().unwrap();
+            state.search_ranges.clear();
+            state.active_search = None;
+        }
+    }
+
+    fn set_active(&self, active: bool) {
+        if let Some(item) = self.matches.get(self.current_ix) {
+            item.state.lock().unwrap().active_search = active.then(|| item.range.clone());
+        }
+    }
+
+    /// Returns the number of matches.
+    pub(super) fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Returns the block index of the current match.
+    pub(super) fn current_block(&self) -> Option<usize> {
+        self.matches.get(self.current_ix).map(|item| item.block_ix)
+    }
+
+    /// Move to the next match, returns the block index of it.
+    pub(super) fn next(&mut self) -> Option<usize> {
+        if self.matches.is_empty() {
+            return None;
+        }
+
+        self.set_active(false);
+        self.current_ix = (self.current_ix + 1) % self.matches.len();
+        self.set_active(true);
+        self.current_block()
+    }
+
+    /// Move to the previous match, returns the block index of it.
+    pub(super) fn prev(&mut self) -> Option<usize> {
+        if self.matches.is_empty() {
+            return None;
+        }
+
+        self.set_active(false);
+        self.current_ix = (self.current_ix + self.matches.len() - 1) % self.matches.len();
+        self.set_active(true);
+        self.current_block()
+    }
+
+    fn label(&self) -> String {
+        if self.len() == 0 {
+            return "0/0".to_string();
+        }
+        format!("{}/{}", self.current_ix + 1, self.len())
+    }
+}
+
+/// The search bar of the TextView, looks like the `SearchPanel` of the Input.
+pub(super) struct SearchBar {
+    input: Entity<InputState>,
+    case_insensitive: bool,
+    _subscription: Subscription,
+}
+
+impl SearchBar {
+    fn new(window: &mut Window, cx: &mut Context<TextViewState>) -> Self {
+        let input = cx.new(|cx| InputState::new(window, cx));
+        let _subscription = cx.subscribe(
+            &input,
+            |this: &mut TextViewState, input, event: &InputEvent, cx| match event {
+                InputEvent::Change => this.update_search_query(&input, cx),
+                InputEvent::PressEnter { .. } => this.search_next(cx),
+                _ => {}
+            },
+        );
+
+        Self {
+            input,
+            case_insensitive: true,
+            _subscription,
+        }
+    }
+}
+
+impl TextViewState {
+    /// Search the `query` in the rendered text (not the markdown or HTML source),
+    /// highlight all matches and scroll to the first one, an empty `query` to clear.
+    ///
+    /// Returns the number of matches.
+    pub fn search(&mut self, query: &str, case_insensitive: bool, cx: &mut Context<Self>) -> usize {
+        {
+            let content = self.parsed_content.lock().unwrap();
+            self.search
+                .update_query(query, case_insensitive, &content.document);
+        }
+
+        self.scroll_to_search_match();
+        cx.notify();
+        self.search.len()
+    }
+
+    /// Move to the next search match, scroll to it if the TextView is `scrollable`.
+    pub fn search_next(&mut self, cx: &mut Context<Self>) {
+        if self.search.next().is_some() {
+            self.scroll_to_search_match();
+            cx.notify();
+        }
+    }
+
+    /// Move to the previous search match, scroll to it if the TextView is `scrollable`.
+    pub fn search_prev(&mut self, cx: &mut Context<Self>) {
+        if self.search.prev().is_some() {
+            self.scroll_to_search_match();
+            cx.notify();
+        }
+    }
+
+    /// Clear the search and close the search bar.
+    pub fn clear_search(&mut self, cx: &mut Context<Self>) {
+        self.search.clear();
+        self.search_bar = None;
+        cx.notify();
+    }
+
+    /// Re-match the search after the content changed from the `start_block`.
+    pub(super) fn update_search(&mut self, start_block: usize) {
+        let content = self.parsed_content.lock().unwrap();
+        self.search.update(&content.document, start_block);
+    }
+
+    fn scroll_to_search_match(&self) {
+        if !self.scrollable {
+            return;
+        }
+
+        if let Some(ix) = self.search.current_block() {
+            self.list_state.scroll_to_reveal_item(ix);
+        }
+    }
+
+    fn update_search_query(&mut self, input: &Entity<InputState>, cx: &mut Context<Self>) {
+        let query = input.read(cx).value();
+        let case_insensitive = self
+            .search_bar
+            .as_ref()
+            .map_or(true, |bar| bar.case_insensitive);
+        self.search(&query, case_insensitive, cx);
+    }
+
+    pub(super) fn on_action_search(
+        &mut self,
+        _: &Search,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.searchable {
+            cx.propagate();
+            return;
+        }
+
+        let selected_text = self.selected_text().trim().to_string();
+        let input = self
+            .search_bar
+            .get_or_insert_with(|| SearchBar::new(window, cx))
+            .input
+            .clone();
+
+        let prefill = !selected_text.is_empty() && !selected_text.contains('\n');
+        input.update(cx, |state, cx| {
+            if prefill {
+                state.set_value(selected_text, window, cx);
+            }
+            state.focus(window, cx);
+        });
+
+        // The `set_value` is silent, so update the query manually.
+        if prefill {
+            self.update_search_query(&input, cx);
+        }
+        cx.notify();
+    }
+
+    fn on_action_search_prev(&mut self, _: &SelectUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.search_prev(cx);
+    }
+
+    fn on_action_search_escape(&mut self, _: &Escape, window: &mut Window, cx: &mut Context<Self>) {
+        self.clear_search(cx);
+        window.focus(&self.focus_handle);
+    }
+
+    pub(super) fn render_search_bar(&self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let bar = self.search_bar.as_ref()?;
+        let has_matches = self.search.len() > 0;
+
+        Some(
+            v_flex()
+                .id("search-panel")
+                .occlude()
+                .key_context(CONTEXT)
+                .on_action(cx.listener(Self::on_action_search_prev))
+                .on_action(cx.listener(Self::on_action_search_escape))
+                .font_family(cx.theme().font_family.clone())
+                .items_center()
+                .py_2()
+                .px_3()
+                .w_full()
+                .gap_1()
+                .bg(cx.theme().popover)
+                .border_b_1()
+                .rounded(cx.theme().radius.half())
+                .border_color(cx.theme().border)
+                .child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .child(
+                            div().flex_1().child(
+                                Input::new(&bar.input)
+                                    .focus_bordered(false)
+                                    .suffix(
+                                        Button::new("case-insensitive")
+                                            .selected(!bar.case_insensitive)
+                                            .xsmall()
+                                            .compact()
+                                            .ghost()
+                                            .icon(IconName::CaseSensitive)
+                                            .on_click(cx.listener(|this, _, _, cx| {
+                                                let Some(bar) = this.search_bar.as_mut() else {
+                                                    return;
+                                                };
+                                                bar.case_insensitive = !bar.case_insensitive;
+                                                let input = bar.input.clone();
+                                                this.update_search_query(&input, cx);
+                                            })),
+                                    )
+                                    .small()
+                                    .w_full()
+                                    .shadow_none(),
+                            ),
+                        )
+                        .child(
+                            Button::new("prev")
+                                .xsmall()
+                                .ghost()
+                                .icon(IconName::ChevronLeft)
+                                .disabled(!has_matches)
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.search_prev(cx);
+                                })),
+                        )
+                        .child(
+                            Button::new("next")
+                                .xsmall()
+                                .ghost()
+                                .icon(IconName::ChevronRight)
+                                .disabled(!has_matches)
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.search_next(cx);
+                                })),
+                        )
+                        .child(
+                            Label::new(self.search.label())
+                                .when(!has_matches, |this| {
+                                    this.text_color(cx.theme().muted_foreground)
+                                })
+                                .text_left()
+                                .min_w_16(),
+                        )
+                        .child(
+                            Button::new("close")
+                                .xsmall()
+                                .ghost()
+                                .icon(IconName::Close)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.on_action_search_escape(&Escape, window, cx);
+                                })),
+                        ),
+                )
+                .into_any_element(),
+        )
+    }
+}
+
+/// Find the `query` in the `text`, returns the byte ranges in the `text`.
+///
+/// If `case_insensitive`, the non-ASCII text is lowercased by Unicode to match the lowercased query,
+/// and the ranges are mapped back to the original text, e.g. `ÄPFEL` matches `äpfel`.
+fn find_ranges(query: &AhoCorasick, text: &str, case_insensitive: bool) -> Vec<Range<usize>> {
+    if !case_insensitive || text.is_ascii() {
+        return query.find_iter(text).map(|m| m.range()).collect();
+    }
+
+    // The offset in the `text` of each byte in the lowercased text,
+    // the lowercase of a char may be longer, e.g. `İ` is `i̇`.
+    let mut lowercase = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len());
+    for (offset, c) in text.char_indices() {
+        for c in c.to_lowercase() {
+            lowercase.push(c);
+            offsets.extend(std::iter::repeat_n(offset, c.len_utf8()));
+        }
+    }
+
+    query
+        .find_iter(lowercase.as_str())
+        .map(|m| {
+            let start = offsets[m.start()];
+            let last = offsets[m.end() - 1];
+            let end = last + text[last..].chars().next().map_or(0, char::len_utf8);
+            start..end
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        highlighter::HighlightTheme,
+        text::{format, node::NodeContext},
+    };
+
+    use super::DocumentSearch;
+
+    #[test]
+    fn test_document_search() {
+        let mut cx = NodeContext::default();
+        let document = format::markdown::parse(
+            "# Hello **world**\n\nThe [World](https://example.com) is big.\n\n```\nworld\n```",
+            &mut cx,
+            &HighlightTheme::default_light(),
+        )
+        .unwrap();
+
+        let mut search = DocumentSearch::default();
+        search.update_query("world", true, &document);
+        assert_eq!(search.len(), 3);
+        assert_eq!(search.label(), "1/3");
+        assert_eq!(search.current_block(), Some(0));
+        assert_eq!(search.next(), Some(1));
+        assert_eq!(search.next(), Some(2));
+        assert_eq!(search.next(), Some(0));
+        assert_eq!(search.prev(), Some(2));
+        assert_eq!(search.label(), "3/3");
+
+        // Match the rendered text, not the markdown syntax.
+        search.update_query("**", true, &document);
+        assert_eq!(search.len(), 0);
+        assert_eq!(search.label(), "0/0");
+
+        // Re-match from a block, the matches before are kept.
+        search.update(&document, 1);
+        assert_eq!(search.len(), 3);
+        assert_eq!(search.label(), "3/3");
+
+        search.update_query("World", false, &document);
+        assert_eq!(search.len(), 1);
+        assert_eq!(search.current_block(), Some(1));
+
+        search.clear();
+        assert_eq!(search.len(), 0);
+        assert_eq!(search.next(), None);
+    }
+
+    #[test]
+    fn test_document_search_unicode() {
+        let mut cx = NodeContext::default();
+        let document = format::markdown::parse(
+            "# ÄPFEL und Birnen\n\nDer Apfel, die Äpfel.",
+            &mut cx,
+            &HighlightTheme::default_light(),
+        )
+        .unwrap();
+
+        let mut search = DocumentSearch::default();
+        search.update_query("äpfel", true, &document);
+        assert_eq!(search.len(), 2);
+        assert_eq!(search.matches[0].range, 0.."ÄPFEL".len());
+        let start = "Der Apfel, die ".len();
+        assert_eq!(search.matches[1].range, start..start + "Äpfel".len());
+
+        search.update_query("Äpfel", false, &document);
+        assert_eq!(search.len(), 1);
+    }
+}
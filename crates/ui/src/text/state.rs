@@ -1,5 +1,8 @@
 use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
     future::Future,
+    ops::{Range, RangeInclusive},
     pin::Pin,
     sync::{Arc, Mutex},
     task::Poll,
@@ -7,28 +10,35 @@ use std::{
 };
 
 use gpui::{
-    App, AppContext as _, Bounds, ClipboardItem, Context, FocusHandle, IntoElement, KeyBinding,
-    ListState, ParentElement as _, Pixels, Point, Render, SharedString, Size, Styled as _, Task,
-    Window, prelude::FluentBuilder as _, px,
+    AnyElement, App, AppContext as _, Bounds, ClipboardItem, Context, EventEmitter, FocusHandle,
+    InteractiveElement as _, IntoElement, KeyBinding, ListOffset, ListScrollEvent, ListState,
+    ParentElement as _, Pixels, Point, Render, SharedString, Size, Styled as _, Task, Window,
+    actions, anchored, deferred, div, point, prelude::FluentBuilder as _, px,
 };
+use serde::{Deserialize, Serialize};
 use smol::{Timer, stream::StreamExt as _};
 
 use crate::{
-    ActiveTheme, ElementExt,
+    ActiveTheme, ElementExt, StyledExt as _,
     highlighter::HighlightTheme,
     input::{self, Copy},
     text::{
-        CodeBlockActionsFn, TextViewStyle,
-        document::ParsedDocument,
+        CodeBlockActionsFn, FrontMatter, TextViewStyle,
+        block_list::BlockListState,
+        clipboard,
+        document::{ParsedDocument, TocItem},
         format,
-        node::{self, NodeContext},
-        style::CodeTokenLinks,
+        node::{self, NodeContext, Span, TableSelection},
+        search::{DocumentSearch, SearchBar},
+        utils::task_marker_range,
     },
     v_flex,
 };
 
 const UPDATE_DELAY: Duration = Duration::from_millis(50);
 
+actions!(text_view, [CopyAsMarkdown, CopyAsHtml]);
+
 const CONTEXT: &'static str = "TextView";
 pub(crate) fn init(cx: &mut App) {
     cx.bind_keys(vec![
@@ -36,18 +46,54 @@ pub(crate) fn init(cx: &mut App) {
         KeyBinding::new("cmd-c", input::Copy, Some(CONTEXT)),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-c", input::Copy, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-f", input::Search, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-f", input::Search, Some(CONTEXT)),
     ]);
 }
 
+/// The JSON metadata of the clipboard item written by copy in the [`TextViewState`].
+///
+/// The copy writes the plain text and HTML formats to the system clipboard,
+/// read the Markdown and HTML of the copied selection in this app by [`TextViewClipboardMetadata::read`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextViewClipboardMetadata {
+    /// The selection as Markdown, the fully selected blocks are the slices of the source.
+    pub markdown: String,
+    /// The selection as HTML.
+    pub html: String,
+}
+
 /// The content format of the text view.
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(super) enum TextViewFormat {
     /// Markdown view
     Markdown,
+    /// MDX view, the Markdown with JSX components
+    Mdx,
     /// HTML view
     Html,
 }
 
+/// Events emitted by the [`TextViewState`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextViewEvent {
+    /// A GFM task list checkbox (`- [ ]`) was toggled.
+    ///
+    /// The `range` is the byte range of the `[ ]` or `[x]` marker in the source,
+    /// the host should rewrite the marker and call [`TextViewState::set_text`] to update.
+    TaskToggled { range: Range<usize>, checked: bool },
+    /// The heading at the top of the viewport changed, the `slug` is `None` if it's before the first heading.
+    ///
+    /// This is only emitted when the TextView is `scrollable`.
+    HeadingChanged { slug: Option<SharedString> },
+    /// The TextView was scrolled, the `offset` is the source byte offset of the top visible block.
+    ///
+    /// This is only emitted when the TextView is `scrollable`, see [`TextViewState::scroll_to_offset`].
+    Scrolled { offset: usize },
+}
+
 /// The state of a TextView.
 pub struct TextViewState {
     pub(super) focus_handle: FocusHandle,
@@ -58,15 +104,29 @@ pub struct TextViewState {
 
     pub(super) selectable: bool,
     pub(super) scrollable: bool,
+    pub(super) searchable: bool,
     pub(super) text_view_style: TextViewStyle,
     pub(super) code_block_actions: Option<Arc<CodeBlockActionsFn>>,
 
     pub(super) is_selecting: bool,
-    /// The local (in TextView) position of the selection.
-    selection_positions: (Option<Point<Pixels>>, Option<Point<Pixels>>),
+    /// The start and end position of the selection, in the top-level blocks.
+    selection_positions: (Option<BlockPoint>, Option<BlockPoint>),
+    /// The selected table cells, copied as TSV.
+    pub(super) table_selection: Option<TableSelection>,
+    /// The bounds of the rendered top-level blocks in this frame.
+    block_bounds: BTreeMap<usize, Bounds<Pixels>>,
+    /// The measured heights of the blocks when the TextView is not `scrollable`.
+    block_list_state: BlockListState,
 
     pub(super) parsed_content: Arc<Mutex<ParsedContent>>,
-    text: SharedString,
+    /// The slug of the heading at the top of the viewport.
+    current_heading: Option<SharedString>,
+    pub(super) search: DocumentSearch,
+    pub(super) search_bar: Option<SearchBar>,
+    /// The hovered link to show the preview.
+    hovered_link: Option<HoveredLink>,
+    /// The text of the last [`TextViewState::set_text`], `None` after [`TextViewState::push_str`].
+    text: Option<SharedString>,
     parsed_error: Option<SharedString>,
     tx: smol::channel::Sender<UpdateOptions>,
     _parse_task: Task<()>,
@@ -84,20 +144,30 @@ impl TextViewState {
         Self::new(TextViewFormat::Html, text, cx)
     }
 
+    /// Create a MDX TextViewState, the JSX elements are rendered by [`TextViewStyle::component`].
+    pub fn mdx(text: &str, cx: &mut Context<Self>) -> Self {
+        Self::new(TextViewFormat::Mdx, text, cx)
+    }
+
     /// Create a new TextViewState.
     fn new(format: TextViewFormat, text: &str, cx: &mut Context<Self>) -> Self {
         let focus_handle = cx.focus_handle();
 
         let (tx, rx) = smol::channel::unbounded::<UpdateOptions>();
-        let (tx_result, rx_result) = smol::channel::unbounded::<Result<(), SharedString>>();
+        let (tx_result, rx_result) = smol::channel::unbounded::<Result<usize, SharedString>>();
         let _receive_task = cx.spawn({
             async move |weak_self, cx| {
                 while let Ok(parsed_result) = rx_result.recv().await {
                     _ = weak_self.update(cx, |state, cx| {
-                        if let Err(err) = &parsed_result {
-                            state.parsed_error = Some(err.clone());
-                        }
+                        let start_block = match parsed_result {
+                            Ok(start_block) => start_block,
+                            Err(err) => {
+                                state.parsed_error = Some(err);
+                                0
+                            }
+                        };
                         state.clear_selection();
+                        state.update_search(start_block);
                         cx.notify();
                     });
                 }
@@ -110,26 +180,56 @@ impl TextViewState {
             focus_handle,
             bounds: Bounds::default(),
             selection_positions: (None, None),
+            table_selection: None,
+            block_bounds: BTreeMap::new(),
+            block_list_state: BlockListState::default(),
             selectable: false,
             scrollable: false,
+            searchable: false,
             list_state: ListState::new(0, gpui::ListAlignment::Top, px(1000.)),
             text_view_style: TextViewStyle::default(),
             code_block_actions: None,
             is_selecting: false,
             parsed_content: Default::default(),
+            current_heading: None,
+            search: DocumentSearch::default(),
+            search_bar: None,
+            hovered_link: None,
             parsed_error: None,
-            text: text.to_string().into(),
+            text: Some(text.to_string().into()),
             tx,
             _parse_task,
             _receive_task,
         };
+        this.list_state.set_scroll_handler(cx.listener(
+            |this: &mut Self, event: &ListScrollEvent, _, cx| {
+                this.update_current_heading(event.visible_range.start, cx);
+                let span = this
+                    .parsed_content
+                    .lock()
+                    .unwrap()
+                    .document
+                    .blocks
+                    .get(event.visible_range.start)
+                    .and_then(|block| block.span());
+                if let Some(span) = span {
+                    cx.emit(TextViewEvent::Scrolled { offset: span.start });
+                }
+            },
+        ));
         this.increment_update(&text, false, cx);
         this
     }
 
     /// Get the text content.
     pub(crate) fn source(&self) -> SharedString {
-        self.parsed_content.lock().unwrap().document.source.clone()
+        self.parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .source
+            .clone()
+            .into()
     }
 
     /// Set whether the text is selectable, default false.
@@ -156,39 +256,107 @@ impl TextViewState {
         cx.notify();
     }
 
+    /// Set whether the text is searchable by `cmd-f` (`ctrl-f` on other platforms), default false.
+    ///
+    /// The search bar is shown at the top, scroll to the matches only when the TextView is `scrollable`.
+    pub fn searchable(mut self, searchable: bool) -> Self {
+        self.searchable = searchable;
+        self
+    }
+
+    /// Set whether the text is searchable, default false.
+    pub fn set_searchable(&mut self, searchable: bool, cx: &mut Context<Self>) {
+        self.searchable = searchable;
+        if !searchable {
+            self.clear_search(cx);
+        }
+        cx.notify();
+    }
+
     /// Set the text view style.
     pub fn set_text_view_style(&mut self, style: TextViewStyle, cx: &mut Context<Self>) {
-        let prev_links = self.text_view_style.code_token_links.clone();
-        self.text_view_style = style;
-        let requires_reparse = prev_links != self.text_view_style.code_token_links;
-        if requires_reparse {
-            let current_text = self.text.to_string();
-            self.increment_update(current_text.as_str(), false, cx);
+        if self.text_view_style != style {
+            cx.notify();
         }
+        self.text_view_style = style;
     }
 
     /// Set the text content.
+    ///
+    /// The scroll position is kept, so this can be used to apply small edits,
+    /// e.g. after a [`TextViewEvent::TaskToggled`].
     pub fn set_text(&mut self, text: &str, cx: &mut Context<Self>) {
-        if self.text.as_str() == text {
+        if self.text.as_deref() == Some(text) {
             return;
         }
 
-        self.text = text.to_string().into();
+        self.text = Some(text.to_string().into());
         self.parsed_error = None;
         self.increment_update(text, false, cx);
     }
 
-    /// Append partial text content to the existing text.
+    /// Append partial text content to the existing text, e.g. the streaming response of LLM.
+    ///
+    /// For Markdown, only the text from the start of the last block is parsed again,
+    /// the previous blocks are kept, and the parse is throttled by [`UPDATE_DELAY`].
     pub fn push_str(&mut self, new_text: &str, cx: &mut Context<Self>) {
         if new_text.is_empty() {
             return;
         }
+        self.text = None;
         self.increment_update(new_text, true, cx);
     }
 
     /// Return the selected text.
     pub fn selected_text(&self) -> String {
-        self.parsed_content.lock().unwrap().document.selected_text()
+        let Some(range) = self.selected_block_range() else {
+            return String::new();
+        };
+
+        self.parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .selected_text(range)
+    }
+
+    /// Get the selected text as Markdown.
+    ///
+    /// The fully selected blocks are copied from the original Markdown source.
+    pub fn selected_markdown(&self) -> String {
+        let Some(range) = self.selected_block_range() else {
+            return String::new();
+        };
+
+        self.parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .selected_markdown(range)
+    }
+
+    /// Get the selected text as HTML, with the formatting of the text.
+    pub fn selected_html(&self) -> String {
+        let Some(range) = self.selected_block_range() else {
+            return String::new();
+        };
+
+        self.parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .selected_html(range)
+    }
+
+    fn selected_block_range(&self) -> Option<RangeInclusive<usize>> {
+        let (Some(start), Some(end)) = self.selection_positions else {
+            return None;
+        };
+        if start == end {
+            return None;
+        }
+
+        Some(start.ix.min(end.ix)..=start.ix.max(end.ix))
     }
 
     fn increment_update(&mut self, text: &str, append: bool, cx: &mut Context<Self>) {
@@ -197,7 +365,6 @@ impl TextViewState {
             content: self.parsed_content.clone(),
             pending_text: text.to_string(),
             highlight_theme: cx.theme().highlight_theme.clone(),
-            code_token_links: self.text_view_style.code_token_links.clone(),
         };
 
         // Parse at first time by blocking.
@@ -215,18 +382,102 @@ impl TextViewState {
     pub(super) fn clear_selection(&mut self) {
         self.selection_positions = (None, None);
         self.is_selecting = false;
+        self.table_selection = None;
+    }
+
+    /// Select the `cell` (row, column in the grid) of the table in the `table` span,
+    /// `extend` to select the range from the selected cell of the same table.
+    pub(super) fn select_table_cell(
+        &mut self,
+        table: Span,
+        cell: (usize, usize),
+        extend: bool,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.selectable {
+            return;
+        }
+
+        let anchor = match self.table_selection {
+            Some(selection) if extend && selection.table == table => selection.anchor,
+            _ => cell,
+        };
+        self.clear_selection();
+        self.table_selection = Some(TableSelection {
+            table,
+            anchor,
+            head: cell,
+        });
+        cx.notify();
+    }
+
+    /// Returns the selected table cells in TSV.
+    fn selected_table_cells(&self) -> Option<String> {
+        let selection = self.table_selection?;
+        let content = self.parsed_content.lock().unwrap();
+        let table = content.document.table(selection.table)?;
+        Some(table.cells_to_tsv(selection.rows(), selection.cols()))
     }
 
     pub(super) fn start_selection(&mut self, pos: Point<Pixels>) {
-        let pos = pos - self.bounds.origin;
-        self.selection_positions = (Some(pos), Some(pos));
+        self.table_selection = None;
+        let pos = self.block_point(pos);
+        self.selection_positions = (pos, pos);
         self.is_selecting = true;
     }
 
     pub(super) fn update_selection(&mut self, pos: Point<Pixels>) {
-        let pos = pos - self.bounds.origin;
         if let (Some(start), Some(_)) = self.selection_positions {
-            self.selection_positions = (Some(start), Some(pos))
+            if let Some(pos) = self.block_point(pos) {
+                self.selection_positions = (Some(start), Some(pos))
+            }
+        }
+    }
+
+    /// Save the bounds of the rendered top-level block.
+    pub(super) fn update_block_bounds(&mut self, ix: usize, bounds: Bounds<Pixels>) {
+        self.block_bounds.insert(ix, bounds);
+    }
+
+    /// Returns the position in the nearest rendered block of the window `position`.
+    fn block_point(&self, position: Point<Pixels>) -> Option<BlockPoint> {
+        let distance = |bounds: &Bounds<Pixels>| {
+            if position.y < bounds.top() {
+                bounds.top() - position.y
+            } else if position.y > bounds.bottom() {
+                position.y - bounds.bottom()
+            } else {
+                px(0.)
+            }
+        };
+
+        self.block_bounds
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                distance(a)
+                    .partial_cmp(&distance(b))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .map(|(ix, bounds)| BlockPoint {
+                ix: *ix,
+                offset: position - bounds.origin,
+            })
+    }
+
+    /// Returns the window position of the `block_point`.
+    ///
+    /// The blocks not rendered are out of the viewport, use a far position above or below it.
+    fn window_point(&self, block_point: BlockPoint) -> Point<Pixels> {
+        const FAR: Pixels = px(100_000.);
+
+        if let Some(bounds) = self.block_bounds.get(&block_point.ix) {
+            return bounds.origin + block_point.offset;
+        }
+
+        let x = self.bounds.left() + block_point.offset.x;
+        match self.block_bounds.keys().next() {
+            Some(first_ix) if block_point.ix < *first_ix => point(x, self.bounds.top() - FAR),
+            _ => point(x, self.bounds.bottom() + FAR),
         }
     }
 
@@ -244,27 +495,259 @@ impl TextViewState {
 
     /// Return the bounds of the selection in window coordinates.
     pub(crate) fn selection_bounds(&self) -> Bounds<Pixels> {
+        let local_point = |pos: BlockPoint| self.window_point(pos) - self.bounds.origin;
         selection_bounds(
-            self.selection_positions.0,
-            self.selection_positions.1,
+            self.selection_positions.0.map(local_point),
+            self.selection_positions.1.map(local_point),
             self.bounds,
         )
     }
 
     pub(super) fn on_action_copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(tsv) = self.selected_table_cells() {
+            cx.write_to_clipboard(ClipboardItem::new_string(tsv));
+            return;
+        }
+
         let selected_text = self.selected_text().trim().to_string();
         if selected_text.is_empty() {
             return;
         }
 
-        cx.write_to_clipboard(ClipboardItem::new_string(selected_text));
+        let metadata = TextViewClipboardMetadata {
+            markdown: self.selected_markdown(),
+            html: self.selected_html(),
+        };
+        clipboard::write_selection(selected_text, metadata, cx);
+    }
+
+    pub(super) fn on_action_copy_as_markdown(
+        &mut self,
+        _: &CopyAsMarkdown,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let markdown = self.selected_markdown();
+        if markdown.is_empty() {
+            return;
+        }
+
+        cx.write_to_clipboard(ClipboardItem::new_string(markdown));
+    }
+
+    pub(super) fn on_action_copy_as_html(
+        &mut self,
+        _: &CopyAsHtml,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let html = self.selected_html();
+        if html.is_empty() {
+            return;
+        }
+
+        clipboard::write_html(html, cx);
     }
 
     pub(crate) fn is_selectable(&self) -> bool {
         self.selectable
     }
+
+    /// Returns the table of contents of the headings.
+    ///
+    /// The content is parsed in background, observe the state to update when the content changed.
+    pub fn table_of_contents(&self) -> Vec<TocItem> {
+        self.parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .table_of_contents()
+    }
+
+    /// Returns the front matter of the Markdown, e.g. to read the `title` and `tags`.
+    ///
+    /// The content is parsed in background, observe the state to update when the content changed.
+    pub fn front_matter(&self) -> Option<FrontMatter> {
+        self.parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .front_matter()
+            .cloned()
+    }
+
+    /// Returns the slug of the heading at the top of the viewport.
+    pub fn current_heading(&self) -> Option<SharedString> {
+        self.current_heading.clone()
+    }
+
+    /// Scroll to the `anchor`, the leading `#` is optional.
+    ///
+    /// - The heading slug, see [`TocItem::slug`].
+    /// - `fn-1` for the footnote `[^1]`.
+    ///
+    /// Returns false if the anchor is not found, or the TextView is not `scrollable`,
+    /// the scroll position is owned by the parent in this case.
+    pub fn scroll_to_anchor(&mut self, anchor: &str, cx: &mut Context<Self>) -> bool {
+        if !self.scrollable {
+            return false;
+        }
+
+        let anchor = anchor.trim_start_matches('#');
+        let Some(item_ix) = self
+            .parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .anchor_block(anchor)
+        else {
+            return false;
+        };
+
+        self.list_state.scroll_to(ListOffset {
+            item_ix,
+            offset_in_item: px(0.),
+        });
+        self.update_current_heading(item_ix, cx);
+        cx.notify();
+        true
+    }
+
+    /// Scroll the top-level block contains the source byte `offset` to the top,
+    /// used to sync the scroll position with the source editor.
+    ///
+    /// NOTE: This only works when the TextView is `scrollable`.
+    pub fn scroll_to_offset(&mut self, offset: usize, cx: &mut Context<Self>) {
+        if !self.scrollable {
+            return;
+        }
+
+        let item_ix = self
+            .parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .block_at_offset(offset)
+            .unwrap_or(0);
+
+        self.list_state.scroll_to(ListOffset {
+            item_ix,
+            offset_in_item: px(0.),
+        });
+        self.update_current_heading(item_ix, cx);
+        cx.notify();
+    }
+
+    /// Returns the source byte offset of the top-level block contains the `offset`.
+    pub(crate) fn block_start(&self, offset: usize) -> Option<usize> {
+        let parsed_content = self.parsed_content.lock().unwrap();
+        let document = &parsed_content.document;
+        let ix = document.block_at_offset(offset)?;
+        document.blocks[ix].span().map(|span| span.start)
+    }
+
+    /// Update the current heading by the top visible block, emit [`TextViewEvent::HeadingChanged`] if changed.
+    fn update_current_heading(&mut self, ix: usize, cx: &mut Context<Self>) {
+        let slug = self
+            .parsed_content
+            .lock()
+            .unwrap()
+            .document
+            .heading_before(ix);
+        if self.current_heading == slug {
+            return;
+        }
+
+        self.current_heading = slug.clone();
+        cx.emit(TextViewEvent::HeadingChanged { slug });
+    }
+
+    /// Emit [`TextViewEvent::TaskToggled`] for the task list item in the `span`.
+    pub(super) fn toggle_task(&mut self, span: Span, checked: bool, cx: &mut Context<Self>) {
+        let source = self.source();
+        let Some(range) = task_marker_range(&source, span.start..span.end) else {
+            return;
+        };
+
+        cx.emit(TextViewEvent::TaskToggled { range, checked });
+    }
+
+    /// Update the hovered link of the [`Inline`](super::inline::Inline) element identified by `source`,
+    /// the `url` is `None` if no link is hovered in the element.
+    pub(super) fn update_hovered_link(
+        &mut self,
+        source: usize,
+        url: Option<SharedString>,
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) {
+        match url {
+            Some(url) => {
+                // Keep the position while moving in the same link.
+                if self
+                    .hovered_link
+                    .as_ref()
+                    .is_some_and(|link| link.source == source && link.url == url)
+                {
+                    return;
+                }
+
+                self.hovered_link = Some(HoveredLink {
+                    source,
+                    url,
+                    position,
+                });
+                cx.notify();
+            }
+            None => {
+                if self
+                    .hovered_link
+                    .as_ref()
+                    .is_some_and(|link| link.source == source)
+                {
+                    self.hovered_link = None;
+                    cx.notify();
+                }
+            }
+        }
+    }
+
+    fn render_link_preview(&self, window: &mut Window, cx: &mut App) -> Option<AnyElement> {
+        let link = self.hovered_link.as_ref()?;
+        let preview = self.text_view_style.link_preview.as_ref()?;
+        let content = preview(&link.url, window, cx)?;
+
+        Some(
+            deferred(
+                anchored()
+                    .snap_to_window_with_margin(px(8.))
+                    .position(link.position + point(px(0.), px(16.)))
+                    .child(
+                        div()
+                            .id("link-preview")
+                            .occlude()
+                            .p_2()
+                            .max_w(px(480.))
+                            .popover_style(cx)
+                            .child(content),
+                    ),
+            )
+            .with_priority(1)
+            .into_any_element(),
+        )
+    }
+}
+
+struct HoveredLink {
+    /// The identity of the [`Inline`](super::inline::Inline) element that the link is in.
+    source: usize,
+    url: SharedString,
+    /// The mouse position in window when start hovering.
+    position: Point<Pixels>,
 }
 
+impl EventEmitter<TextViewEvent> for TextViewState {}
+
 impl Render for TextViewState {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let state = cx.entity();
@@ -272,12 +755,18 @@ impl Render for TextViewState {
             let content = self.parsed_content.lock().unwrap();
             (content.document.clone(), content.node_cx.clone())
         };
+        // The bounds are saved again when the blocks are rendered.
+        self.block_bounds.clear();
 
         node_cx.style = self.text_view_style.clone();
         node_cx.code_block_actions = self.code_block_actions.clone();
+        node_cx.state = Some(state.downgrade());
+        node_cx.table_selection = self.table_selection;
 
         v_flex()
             .size_full()
+            .children(self.render_search_bar(cx))
+            .children(self.render_link_preview(window, cx))
             .map(|this| match &mut self.parsed_error {
                 None => this.child(document.render_root(
                     if self.scrollable {
@@ -285,9 +774,8 @@ impl Render for TextViewState {
                     } else {
                         None
                     },
+                    &self.block_list_state,
                     &node_cx,
-                    window,
-                    cx,
                 )),
                 Some(err) => this.child(
                     v_flex()
@@ -306,17 +794,38 @@ impl Render for TextViewState {
 
 #[derive(PartialEq, Default)]
 pub(crate) struct ParsedContent {
-    pub(crate) document: ParsedDocument,
+    pub(crate) document: Arc<ParsedDocument>,
     pub(crate) node_cx: node::NodeContext,
+    /// The source offset to parse the appended text from, None to parse in full.
+    reparse_offset: Option<usize>,
+    /// The source starts with a front matter fence not closed yet, parsed in full until it's closed.
+    front_matter_pending: bool,
+    /// The counts of the heading slugs in the first `slugged_len` blocks, those are not parsed again.
+    slug_counts: HashMap<String, usize>,
+    slugged_len: usize,
+}
+
+/// A position in the top-level block, to keep the selection when scrolling,
+/// even the block is out of the viewport and not rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BlockPoint {
+    /// The index of the top-level block.
+    ix: usize,
+    /// The offset to the origin of the block.
+    offset: Point<Pixels>,
 }
 
 struct UpdateFuture {
     format: TextViewFormat,
     options: UpdateOptions,
     pending_text: String,
+    /// Whether the `pending_text` replaces the content, instead of appending to it.
+    reset: bool,
+    /// Whether the `timer` is set to parse the `pending_text`.
+    scheduled: bool,
     timer: Timer,
     rx: Pin<Box<smol::channel::Receiver<UpdateOptions>>>,
-    tx_result: smol::channel::Sender<Result<(), SharedString>>,
+    tx_result: smol::channel::Sender<Result<usize, SharedString>>,
     delay: Duration,
 }
 
@@ -324,18 +833,19 @@ impl UpdateFuture {
     fn new(
         format: TextViewFormat,
         rx: smol::channel::Receiver<UpdateOptions>,
-        tx_result: smol::channel::Sender<Result<(), SharedString>>,
+        tx_result: smol::channel::Sender<Result<usize, SharedString>>,
         cx: &App,
     ) -> Self {
         Self {
             format,
             pending_text: String::new(),
+            reset: false,
+            scheduled: false,
             options: UpdateOptions {
                 append: false,
                 pending_text: String::new(),
                 content: Default::default(),
                 highlight_theme: cx.theme().highlight_theme.clone(),
-                code_token_links: CodeTokenLinks::default(),
             },
             timer: Timer::never(),
             rx: Box::pin(rx),
@@ -357,9 +867,14 @@ impl Future for UpdateFuture {
                         self.pending_text.push_str(options.pending_text.as_str());
                     } else {
                         self.pending_text = options.pending_text.clone();
+                        self.reset = true;
                     }
                     self.options = options;
-                    self.timer.set_after(delay);
+                    // Throttle instead of debounce, the streaming text may never pause in the delay.
+                    if !self.scheduled {
+                        self.scheduled = true;
+                        self.timer.set_after(delay);
+                    }
                     continue;
                 }
                 Poll::Ready(None) => return Poll::Ready(()),
@@ -368,12 +883,15 @@ impl Future for UpdateFuture {
 
             match self.timer.poll_next(cx) {
                 Poll::Ready(Some(_)) => {
+                    self.scheduled = false;
                     let pending_text = std::mem::take(&mut self.pending_text);
+                    let append = !std::mem::take(&mut self.reset);
 
                     let res = parse_content(
                         self.format,
                         &UpdateOptions {
                             pending_text,
+                            append,
                             ..self.options.clone()
                         },
                     );
@@ -392,52 +910,148 @@ struct UpdateOptions {
     pending_text: String,
     append: bool,
     highlight_theme: Arc<HighlightTheme>,
-    code_token_links: CodeTokenLinks,
 }
 
-fn parse_content(format: TextViewFormat, options: &UpdateOptions) -> Result<(), SharedString> {
+/// Parse the content, returns the index of the first block changed.
+fn parse_content(format: TextViewFormat, options: &UpdateOptions) -> Result<usize, SharedString> {
     let mut node_cx = NodeContext {
         ..NodeContext::default()
     };
-    node_cx.style.code_token_links = options.code_token_links.clone();
 
     let mut content = options.content.lock().unwrap();
-    let mut source = String::new();
-    if options.append {
-        if let Some(last_block) = content.document.blocks.pop() {
-            if let Some(span) = last_block.span() {
-                node_cx.offset = span.start;
-                let last_source = &content.document.source[span.start..];
-                source.push_str(last_source);
-                source.push_str(&options.pending_text);
-            } else {
-                source = options.pending_text.to_string();
-            }
-        } else {
-            source = options.pending_text.to_string();
+    let content = &mut *content;
+    // The document is shared with the rendering, only cloned if it's still rendering.
+    let document = Arc::make_mut(&mut content.document);
+
+    // The Markdown is parsed again from the start of the last block, which may be continued by the new text,
+    // the previous blocks are finished and kept.
+    //
+    // The HTML is always parsed in full, the unclosed tags may wrap the following text.
+    let stable_offset = match format {
+        TextViewFormat::Markdown | TextViewFormat::Mdx
+            if options.append && !content.front_matter_pending =>
+        {
+            content.reparse_offset
         }
-    } else {
-        source = options.pending_text.to_string();
-    }
+        _ => None,
+    };
 
-    let new_content = match format {
-        TextViewFormat::Markdown => {
-            format::markdown::parse(&source, &mut node_cx, &options.highlight_theme)
+    let source = match stable_offset {
+        Some(offset) => {
+            // Keep the references and the footnote numbers of the previous blocks.
+            node_cx.link_refs = content.node_cx.link_refs.clone();
+            node_cx.footnotes = content.node_cx.footnotes.clone();
+            node_cx.offset = offset;
+            format!("{}{}", &document.source[offset..], options.pending_text)
         }
-        TextViewFormat::Html => format::html::parse(&source, &mut node_cx),
-    }?;
+        None if options.append => format!("{}{}", document.source, options.pending_text),
+        None => options.pending_text.clone(),
+    };
+
+    let (new_content, mdx_fallback) = match format {
+        TextViewFormat::Markdown => (
+            format::markdown::parse(&source, &mut node_cx, &options.highlight_theme)?,
+            false,
+        ),
+        TextViewFormat::Mdx => format::markdown::parse_mdx_with_fallback(
+            &source,
+            &mut node_cx,
+            &options.highlight_theme,
+        )?,
+        TextViewFormat::Html => (format::html::parse(&source, &mut node_cx)?, false),
+    };
 
-    if options.append {
-        content.document.source =
-            format!("{}{}", content.document.source, options.pending_text).into();
-        content.document.blocks.extend(new_content.blocks);
+    // The next parse starts from the last block in the source (the footnotes are moved to the end).
+    //
+    // If the MDX is invalid (e.g. an element with blank lines is not closed yet), it's parsed as Markdown,
+    // so parse again from the same offset until it's valid, otherwise the opening tag is kept as text.
+    let parse_offset = stable_offset.unwrap_or(0);
+    content.reparse_offset = if mdx_fallback || new_content.blocks.is_empty() {
+        Some(parse_offset)
     } else {
-        content.document = new_content;
+        new_content
+            .blocks
+            .iter()
+            .filter_map(|block| block.span().map(|span| span.start))
+            .max()
+    };
+
+    let start = if let Some(offset) = stable_offset {
+        document.source.push_str(&options.pending_text);
+        let is_stable =
+            |block: &node::BlockNode| block.span().is_some_and(|span| span.start < offset);
+        let section_start = document.footnote_section_start();
+        let footnotes = document
+            .blocks
+            .split_off(section_start)
+            .into_iter()
+            .filter(is_stable)
+            .collect();
+        let keep = document
+            .blocks
+            .iter()
+            .position(|block| !is_stable(block))
+            .unwrap_or(document.blocks.len());
+        document.blocks.truncate(keep);
+        document.blocks.extend(new_content.blocks);
+        document.update_footnote_section(keep, footnotes);
+        keep
+    } else {
+        *document = new_content;
+        0
+    };
+
+    // The slugs of the kept blocks are counted once, only the changed blocks are updated.
+    if start < content.slugged_len {
+        content.slug_counts.clear();
+        content.slugged_len = 0;
     }
+    document.update_heading_slugs_in(content.slugged_len..start, &mut content.slug_counts);
+    content.slugged_len = start;
+    let len = document.blocks.len();
+    document.update_heading_slugs_in(start..len, &mut content.slug_counts.clone());
 
+    let new_text_len = if options.append {
+        options.pending_text.len()
+    } else {
+        document.source.len()
+    };
+    content.front_matter_pending = (!options.append || content.front_matter_pending)
+        && is_front_matter_pending(document, new_text_len);
     content.node_cx = node_cx;
 
-    Ok(())
+    Ok(start)
+}
+
+/// The max size of the source to wait for the front matter to be closed.
+const FRONT_MATTER_MAX_LEN: usize = 16 * 1024;
+
+/// Returns true if the source may start with a front matter not closed yet,
+/// `new_text_len` is the length of the text appended in the last update.
+fn is_front_matter_pending(document: &ParsedDocument, new_text_len: usize) -> bool {
+    const FENCES: [&str; 2] = ["---", "+++"];
+
+    let source = document.source.as_str();
+    if document.front_matter().is_some() || source.len() > FRONT_MATTER_MAX_LEN {
+        return false;
+    }
+    if !FENCES
+        .iter()
+        .any(|fence| source.starts_with(fence) || fence.starts_with(source))
+    {
+        return false;
+    }
+
+    // Only check the new text (with the previous fence chars), if a closing fence is found
+    // and still no front matter, it's not a front matter, e.g. a thematic break.
+    let mut start = source.len().saturating_sub(new_text_len + 4);
+    while !source.is_char_boundary(start) {
+        start -= 1;
+    }
+    let new_text = &source[start..];
+    !FENCES
+        .iter()
+        .any(|fence| new_text.contains(&format!("\n{}\n", fence)))
 }
 
 fn selection_bounds(
@@ -549,4 +1163,105 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_parse_content_append() {
+        let source = "# Title\n\nHello **world**, see [docs](https://example.com).\n\n- one\n- two\n\n```rust\nfn main() {}\n```\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+        let highlight_theme = HighlightTheme::default_light().clone();
+        let parse = |content: &Arc<Mutex<ParsedContent>>, text: &str, append: bool| {
+            parse_content(
+                TextViewFormat::Markdown,
+                &UpdateOptions {
+                    content: content.clone(),
+                    pending_text: text.to_string(),
+                    append,
+                    highlight_theme: highlight_theme.clone(),
+                },
+            )
+            .unwrap();
+        };
+
+        let full: Arc<Mutex<ParsedContent>> = Default::default();
+        parse(&full, source, false);
+
+        for chunk_size in [1, 3, 7, 16] {
+            let content: Arc<Mutex<ParsedContent>> = Default::default();
+            parse(&content, "", false);
+            let mut first_block = None;
+            for (ix, chunk) in source.as_bytes().chunks(chunk_size).enumerate() {
+                parse(&content, std::str::from_utf8(chunk).unwrap(), true);
+
+                // The finished blocks are kept, not parsed again.
+                let content = content.lock().unwrap();
+                if content.document.blocks.len() > 1 {
+                    let state = match &content.document.blocks[0] {
+                        node::BlockNode::Heading { children, .. } => children.state.clone(),
+                        _ => unreachable!("the first block must be a heading"),
+                    };
+                    if let Some(first_block) = &first_block {
+                        assert!(Arc::ptr_eq(first_block, &state), "chunk {}", ix);
+                    }
+                    first_block = Some(state);
+                }
+            }
+
+            let content = content.lock().unwrap();
+            let full = full.lock().unwrap();
+            assert_eq!(content.document.source, full.document.source);
+            assert_eq!(
+                content.document.blocks, full.document.blocks,
+                "chunk size {}",
+                chunk_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_content_append_mdx_and_front_matter() {
+        let highlight_theme = HighlightTheme::default_light().clone();
+        let parse = |format: TextViewFormat, source: &str, chunk_size: usize| {
+            let content: Arc<Mutex<ParsedContent>> = Default::default();
+            for chunk in source.as_bytes().chunks(chunk_size) {
+                parse_content(
+                    format,
+                    &UpdateOptions {
+                        content: content.clone(),
+                        pending_text: std::str::from_utf8(chunk).unwrap().to_string(),
+                        append: true,
+                        highlight_theme: highlight_theme.clone(),
+                    },
+                )
+                .unwrap();
+            }
+            Arc::into_inner(content).unwrap().into_inner().unwrap()
+        };
+
+        for (format, source) in [
+            // The element is not closed until the end, the blank lines are parsed as Markdown before.
+            (
+                TextViewFormat::Mdx,
+                "Intro\n\n<Alert>\n\nPara 1\n\nPara 2\n</Alert>\n\nAfter\n",
+            ),
+            (
+                TextViewFormat::Markdown,
+                "---\ntitle: Hello\n---\n\n# Title\n\nText\n",
+            ),
+            (TextViewFormat::Markdown, "---\n\nText\n\n---\n\nMore\n"),
+        ] {
+            let full = parse(format, source, source.len());
+            for chunk_size in [1, 3, 7] {
+                let content = parse(format, source, chunk_size);
+                assert_eq!(content.document.source, source);
+                assert_eq!(
+                    content.document.blocks, full.document.blocks,
+                    "{:?} chunk size {}",
+                    source, chunk_size
+                );
+                assert_eq!(
+                    content.document.front_matter(),
+                    full.document.front_matter()
+                );
+            }
+        }
+    }
 }
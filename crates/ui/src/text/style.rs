@@ -1,8 +1,19 @@
-use std::sync::Arc;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
-use gpui::{Hsla, IsZero, Pixels, Rems, SharedString, StyleRefinement, px, rems};
+use gpui::{
+    AnyElement, App, Hsla, IntoElement, IsZero, Pixels, Rems, SharedString, StyleRefinement,
+    Window, px, rems,
+};
 
-use crate::highlighter::HighlightTheme;
+use crate::{
+    highlighter::HighlightTheme,
+    text::{
+        CodeBlockRendererFn, ComponentProps, ComponentRendererFn, FileRef, LinkClickFn,
+        LinkPreviewFn, LinkResolverFn,
+        node::CodeBlock,
+        utils::{encode_uri_component, is_absolute_path},
+    },
+};
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct InlineCodeStyle {
@@ -44,6 +55,60 @@ impl CodeTokenLinks {
             worktree_id,
         }
     }
+
+    /// The default link target of the token, used if no [`TextViewStyle::link_resolver`] is set.
+    ///
+    /// - The URLs are linked as is.
+    /// - The file references are linked as `ctx://open?...`, the relative paths require the `worktree_id`.
+    fn resolve(&self, token: &LinkToken) -> Option<SharedString> {
+        match token {
+            LinkToken::Url(url) => Some(url.clone()),
+            LinkToken::File(file_ref) => ctx_open_url(file_ref, self.worktree_id.as_ref()),
+            LinkToken::Code(_) => None,
+        }
+    }
+}
+
+fn ctx_open_url(file_ref: &FileRef, worktree_id: Option<&SharedString>) -> Option<SharedString> {
+    let mut params: Vec<String> = Vec::new();
+    if is_absolute_path(&file_ref.path) {
+        params.push(format!("path={}", encode_uri_component(&file_ref.path)));
+    } else {
+        let worktree_id = worktree_id?;
+        params.push(format!(
+            "worktreeId={}",
+            encode_uri_component(worktree_id.as_ref())
+        ));
+        params.push(format!("file={}", encode_uri_component(&file_ref.path)));
+    }
+    if let Some(line) = file_ref.line {
+        params.push(format!("line={}", line));
+    }
+    if let Some(col) = file_ref.col {
+        params.push(format!("col={}", col));
+    }
+    Some(format!("ctx://open?{}", params.join("&")).into())
+}
+
+/// A token detected in the inline code or code blocks, see [`TextViewStyle::link_resolver`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkToken {
+    /// A `http://` or `https://` URL.
+    Url(SharedString),
+    /// A file reference, e.g. `src/main.rs:10:5` or `./README.md#L3`.
+    File(FileRef),
+    /// Any other token, e.g. a symbol name `Foo::bar`.
+    Code(SharedString),
+}
+
+/// How to display the front matter of the Markdown, see [`TextViewStyle::front_matter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrontMatterDisplay {
+    /// Not rendered, the default.
+    #[default]
+    Hidden,
+    /// Render the fields as a [`DescriptionList`](crate::description_list::DescriptionList) header.
+    DescriptionList,
 }
 
 /// TextViewStyle used to customize the style for [`TextView`].
@@ -66,6 +131,20 @@ pub struct TextViewStyle {
     pub inline_code: InlineCodeStyle,
     /// Token-linkification settings for inline code.
     pub code_token_links: CodeTokenLinks,
+    /// The custom renderers for code blocks, the key is the lowercase language.
+    pub code_block_renderers: HashMap<SharedString, Arc<CodeBlockRendererFn>>,
+    /// The components of the MDX JSX elements, the key is the element name.
+    pub components: HashMap<SharedString, Arc<ComponentRendererFn>>,
+    /// Resolve the link target of the tokens in code, see [`TextViewStyle::link_resolver`].
+    pub link_resolver: Option<Arc<LinkResolverFn>>,
+    /// Handle the link click, see [`TextViewStyle::on_link_click`].
+    pub on_link_click: Option<Arc<LinkClickFn>>,
+    /// Build the preview of the hovered link, see [`TextViewStyle::link_preview`].
+    pub link_preview: Option<Arc<LinkPreviewFn>>,
+    /// How to display the front matter, default is [`FrontMatterDisplay::Hidden`].
+    pub front_matter: FrontMatterDisplay,
+    /// The base directory to resolve the relative image paths, see [`TextViewStyle::image_base_path`].
+    pub image_base_path: Option<PathBuf>,
     pub is_dark: bool,
 }
 
@@ -77,6 +156,8 @@ impl PartialEq for TextViewStyle {
             && self.code_block == other.code_block
             && self.inline_code == other.inline_code
             && self.code_token_links == other.code_token_links
+            && self.front_matter == other.front_matter
+            && self.image_base_path == other.image_base_path
             && self.is_dark == other.is_dark
     }
 }
@@ -91,6 +172,13 @@ impl Default for TextViewStyle {
             code_block: StyleRefinement::default(),
             inline_code: InlineCodeStyle::default(),
             code_token_links: CodeTokenLinks::default(),
+            code_block_renderers: HashMap::new(),
+            components: HashMap::new(),
+            link_resolver: None,
+            on_link_click: None,
+            link_preview: None,
+            front_matter: FrontMatterDisplay::default(),
+            image_base_path: None,
             is_dark: false,
         }
     }
@@ -128,4 +216,174 @@ impl TextViewStyle {
         self.code_token_links = options;
         self
     }
+
+    /// Register a renderer for the code blocks in `lang` (case-insensitive),
+    /// the rendered element replaces the highlighted code, e.g. render `csv` as a table.
+    ///
+    /// The code blocks in other languages are rendered as highlighted code.
+    pub fn code_block_renderer<F, E>(mut self, lang: impl Into<SharedString>, f: F) -> Self
+    where
+        F: Fn(&CodeBlock, &mut Window, &mut App) -> E + Send + Sync + 'static,
+        E: IntoElement,
+    {
+        let lang = lang.into().to_lowercase();
+        self.code_block_renderers.insert(
+            lang.into(),
+            Arc::new(move |code_block, window, cx| f(code_block, window, cx).into_any_element()),
+        );
+        self
+    }
+
+    /// Register a component for the MDX JSX element `name` (case-sensitive), e.g. `Alert` for `<Alert kind="warning">…</Alert>`.
+    ///
+    /// The component is called with the attributes and the rendered children of the element,
+    /// the elements in a paragraph like `<Badge count={3} />` are rendered inline in the text.
+    /// The elements without registered component are rendered as a container of the children.
+    ///
+    /// This only works for [`TextView::mdx`](crate::text::TextView::mdx).
+    pub fn component<F, E>(mut self, name: impl Into<SharedString>, f: F) -> Self
+    where
+        F: Fn(&ComponentProps, Vec<AnyElement>, &mut Window, &mut App) -> E + Send + Sync + 'static,
+        E: IntoElement,
+    {
+        self.components.insert(
+            name.into(),
+            Arc::new(move |props, children, window, cx| {
+                f(props, children, window, cx).into_any_element()
+            }),
+        );
+        self
+    }
+
+    /// Set the resolver of the links in code, this also enables the links without [`CodeTokenLinks::enabled`].
+    ///
+    /// The resolver is called with each token (split by whitespace) in the inline code and code blocks,
+    /// returns the link target of the token, or `None` to not link it.
+    ///
+    /// If not set, the URLs are linked as is and the file references are linked as `ctx://open?...`.
+    pub fn link_resolver<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&LinkToken) -> Option<SharedString> + Send + Sync + 'static,
+    {
+        self.link_resolver = Some(Arc::new(f));
+        self
+    }
+
+    /// Set the handler of the link click, the argument is the link target.
+    ///
+    /// Returns `true` if the click is handled, otherwise the link is opened by [`App::open_url`].
+    /// The anchor links (e.g. `#heading`) are scrolled in the `scrollable` TextView,
+    /// otherwise they are passed here to scroll the parent, and never opened.
+    pub fn on_link_click<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&SharedString, &mut Window, &mut App) -> bool + Send + Sync + 'static,
+    {
+        self.on_link_click = Some(Arc::new(f));
+        self
+    }
+
+    /// Set the preview of the hovered link, the argument is the link target.
+    ///
+    /// The preview is shown in a popover under the mouse, return `None` to show nothing.
+    pub fn link_preview<F, E>(mut self, f: F) -> Self
+    where
+        F: Fn(&SharedString, &mut Window, &mut App) -> Option<E> + Send + Sync + 'static,
+        E: IntoElement,
+    {
+        self.link_preview = Some(Arc::new(move |url, window, cx| {
+            f(url, window, cx).map(|el| el.into_any_element())
+        }));
+        self
+    }
+
+    /// Set how to display the front matter, default is [`FrontMatterDisplay::Hidden`].
+    ///
+    /// The parsed front matter is always available by [`TextViewState::front_matter`].
+    ///
+    /// [`TextViewState::front_matter`]: crate::text::TextViewState::front_matter
+    pub fn front_matter(mut self, display: FrontMatterDisplay) -> Self {
+        self.front_matter = display;
+        self
+    }
+
+    /// Set the base directory to resolve the relative image paths, e.g. the directory of the Markdown file.
+    ///
+    /// The `file://` URLs and absolute paths are loaded from the file system as well,
+    /// the `data:` URIs (including SVG) are decoded in place.
+    /// Without the base directory, the relative paths are loaded as is.
+    pub fn image_base_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.image_base_path = Some(path.into());
+        self
+    }
+
+    /// Returns true if the tokens in code should be linked.
+    pub(crate) fn code_links_enabled(&self) -> bool {
+        self.code_token_links.enabled || self.link_resolver.is_some()
+    }
+
+    /// Returns the link target of the token in code.
+    pub(crate) fn resolve_link(&self, token: &LinkToken) -> Option<SharedString> {
+        match &self.link_resolver {
+            Some(resolver) => resolver(token),
+            None => self.code_token_links.resolve(token),
+        }
+    }
+
+    /// Returns the renderer of the code blocks in `lang`.
+    pub(crate) fn code_block_renderer_for(&self, lang: &str) -> Option<&Arc<CodeBlockRendererFn>> {
+        self.code_block_renderers.get(lang.to_lowercase().as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodeTokenLinks, LinkToken, TextViewStyle};
+    use crate::text::FileRef;
+
+    #[test]
+    fn test_resolve_link() {
+        let file = LinkToken::File(FileRef {
+            path: "src/main.rs".to_string(),
+            line: Some(10),
+            col: Some(5),
+        });
+
+        let style = TextViewStyle::default();
+        assert!(!style.code_links_enabled());
+
+        let style = style.code_token_links(CodeTokenLinks::enabled(Some("1".into())));
+        assert!(style.code_links_enabled());
+        assert_eq!(
+            style.resolve_link(&file).as_deref(),
+            Some("ctx://open?worktreeId=1&file=src%2Fmain.rs&line=10&col=5")
+        );
+        assert_eq!(
+            style
+                .resolve_link(&LinkToken::Url("https://example.com".into()))
+                .as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(style.resolve_link(&LinkToken::Code("Foo".into())), None);
+
+        let style = TextViewStyle::default().link_resolver(|token| match token {
+            LinkToken::File(file) => Some(format!("editor://{}", file.path).into()),
+            LinkToken::Code(name) => Some(format!("docs://{}", name).into()),
+            LinkToken::Url(_) => None,
+        });
+        assert!(style.code_links_enabled());
+        assert_eq!(
+            style.resolve_link(&file).as_deref(),
+            Some("editor://src/main.rs")
+        );
+        assert_eq!(
+            style
+                .resolve_link(&LinkToken::Code("Foo".into()))
+                .as_deref(),
+            Some("docs://Foo")
+        );
+        assert_eq!(
+            style.resolve_link(&LinkToken::Url("https://example.com".into())),
+            None
+        );
+    }
 }
@@ -3,21 +3,44 @@ use std::sync::Arc;
 use gpui::prelude::FluentBuilder as _;
 use gpui::{
     AnyElement, App, Bounds, Element, ElementId, Entity, GlobalElementId, InspectorElementId,
-    InteractiveElement, IntoElement, LayoutId, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
-    ParentElement, Pixels, SharedString, StyleRefinement, Styled, Window, div,
+    InteractiveElement, IntoElement, LayoutId, MouseButton, MouseDownEvent, MouseMoveEvent,
+    MouseUpEvent, ParentElement, Pixels, SharedString, StyleRefinement, Styled, Window, div,
 };
 
 use crate::StyledExt;
+use crate::input::Copy;
+use crate::menu::ContextMenuExt as _;
 use crate::scroll::ScrollableElement;
 use crate::text::TextViewFormat;
 use crate::text::node::CodeBlock;
-use crate::text::state::TextViewState;
-use crate::{global_state::GlobalState, text::TextViewStyle};
+use crate::text::state::{CopyAsHtml, CopyAsMarkdown, TextViewState};
+use crate::{
+    global_state::GlobalState,
+    text::{ComponentProps, LinkToken, TextViewStyle},
+};
 
 /// Type for code block actions generator function.
 pub(crate) type CodeBlockActionsFn =
     dyn Fn(&CodeBlock, &mut Window, &mut App) -> AnyElement + Send + Sync;
 
+/// Type for code block renderer function, see [`TextViewStyle::code_block_renderer`].
+pub type CodeBlockRendererFn =
+    dyn Fn(&CodeBlock, &mut Window, &mut App) -> AnyElement + Send + Sync;
+
+/// Type for component renderer function, see [`TextViewStyle::component`].
+pub type ComponentRendererFn =
+    dyn Fn(&ComponentProps, Vec<AnyElement>, &mut Window, &mut App) -> AnyElement + Send + Sync;
+
+/// Type for link resolver function, see [`TextViewStyle::link_resolver`].
+pub type LinkResolverFn = dyn Fn(&LinkToken) -> Option<SharedString> + Send + Sync;
+
+/// Type for link click handler, see [`TextViewStyle::on_link_click`].
+pub type LinkClickFn = dyn Fn(&SharedString, &mut Window, &mut App) -> bool + Send + Sync;
+
+/// Type for link preview function, see [`TextViewStyle::link_preview`].
+pub type LinkPreviewFn =
+    dyn Fn(&SharedString, &mut Window, &mut App) -> Option<AnyElement> + Send + Sync;
+
 /// A text view that can render Markdown or HTML.
 ///
 /// ## Goals
@@ -44,6 +67,7 @@ pub struct TextView {
     style: StyleRefinement,
     selectable: bool,
     scrollable: bool,
+    searchable: bool,
     code_block_actions: Option<Arc<CodeBlockActionsFn>>,
 }
 
@@ -65,6 +89,7 @@ impl TextView {
             style: StyleRefinement::default(),
             selectable: false,
             scrollable: false,
+            searchable: false,
             code_block_actions: None,
         }
     }
@@ -80,6 +105,7 @@ impl TextView {
             state: None,
             selectable: false,
             scrollable: false,
+            searchable: false,
             code_block_actions: None,
         }
     }
@@ -95,6 +121,23 @@ impl TextView {
             state: None,
             selectable: false,
             scrollable: false,
+            searchable: false,
+            code_block_actions: None,
+        }
+    }
+
+    /// Create a new MDX text view, the JSX elements are rendered by [`TextViewStyle::component`].
+    pub fn mdx(id: impl Into<ElementId>, mdx: impl Into<SharedString>) -> Self {
+        Self {
+            id: id.into(),
+            format: Some(TextViewFormat::Mdx),
+            text: Some(mdx.into()),
+            text_view_style: TextViewStyle::default(),
+            style: StyleRefinement::default(),
+            state: None,
+            selectable: false,
+            scrollable: false,
+            searchable: false,
             code_block_actions: None,
         }
     }
@@ -123,11 +166,23 @@ impl TextView {
     ///
     /// The TextView will expand to fit all content, no scrollbar.
     /// This mode is suitable for small content, such as a few lines of text, a label, etc.
+    ///
+    /// For long content in a scrollable parent, only the blocks in the visible area are rendered,
+    /// the heights of others are estimated until they are scrolled into view.
     pub fn scrollable(mut self, scrollable: bool) -> Self {
         self.scrollable = scrollable;
         self
     }
 
+    /// Set the text view to be searchable, default is false.
+    ///
+    /// Press `cmd-f` (`ctrl-f` on other platforms) to open a search bar to find in the rendered text,
+    /// the matches are scrolled into view only when the TextView is `scrollable`.
+    pub fn searchable(mut self, searchable: bool) -> Self {
+        self.searchable = searchable;
+        self
+    }
+
     /// Set custom block actions for code blocks.
     ///
     /// The closure receives the [`CodeBlock`],
@@ -185,12 +240,10 @@ impl Element for TextView {
             let state = window.use_keyed_state(
                 SharedString::from(format!("{}/state", self.id)),
                 cx,
-                move |_, cx| {
-                    if default_format == TextViewFormat::Markdown {
-                        TextViewState::markdown(default_text.as_str(), cx)
-                    } else {
-                        TextViewState::html(default_text.as_str(), cx)
-                    }
+                move |_, cx| match default_format {
+                    TextViewFormat::Markdown => TextViewState::markdown(default_text.as_str(), cx),
+                    TextViewFormat::Mdx => TextViewState::mdx(default_text.as_str(), cx),
+                    TextViewFormat::Html => TextViewState::html(default_text.as_str(), cx),
                 },
             );
             self.state = Some(state.clone());
@@ -201,6 +254,9 @@ impl Element for TextView {
             state.code_block_actions = self.code_block_actions.clone();
             state.selectable = self.selectable;
             state.scrollable = self.scrollable;
+            if state.searchable != self.searchable {
+                state.set_searchable(self.searchable, cx);
+            }
             state.set_text_view_style(self.text_view_style.clone(), cx);
 
             if let Some(text) = self.text.clone() {
@@ -211,7 +267,7 @@ impl Element for TextView {
         let focus_handle = state.read(cx).focus_handle.clone();
         let list_state = state.read(cx).list_state.clone();
 
-        let mut el = div()
+        let el = div()
             .key_context("TextView")
             .track_focus(&focus_handle)
             .when(self.scrollable, |this| {
@@ -219,9 +275,30 @@ impl Element for TextView {
             })
             .relative()
             .on_action(window.listener_for(&state, TextViewState::on_action_copy))
+            .on_action(window.listener_for(&state, TextViewState::on_action_copy_as_markdown))
+            .on_action(window.listener_for(&state, TextViewState::on_action_copy_as_html))
+            .on_action(window.listener_for(&state, TextViewState::on_action_search))
             .child(state.clone())
-            .refine_style(&self.style)
-            .into_any_element();
+            .refine_style(&self.style);
+        let mut el = if self.selectable {
+            el.context_menu({
+                let state = state.clone();
+                move |menu, _, cx| {
+                    let has_selection = state.read(cx).has_selection();
+                    menu.action_context(focus_handle.clone())
+                        .menu_with_disabled("Copy", Box::new(Copy), !has_selection)
+                        .menu_with_disabled(
+                            "Copy as Markdown",
+                            Box::new(CopyAsMarkdown),
+                            !has_selection,
+                        )
+                        .menu_with_disabled("Copy as HTML", Box::new(CopyAsHtml), !has_selection)
+                }
+            })
+            .into_any_element()
+        } else {
+            el.into_any_element()
+        };
         let layout_id = el.request_layout(window, cx);
         (layout_id, TextViewLayoutState { state, element: el })
     }
@@ -257,14 +334,18 @@ impl Element for TextView {
 
         if self.selectable {
             let is_selecting = state.read(cx).is_selecting;
-            let has_selection = state.read(cx).has_selection();
+            let has_selection =
+                state.read(cx).has_selection() || state.read(cx).table_selection.is_some();
             let parent_view_id = window.current_view();
 
             window.on_mouse_event({
                 let state = state.clone();
 
                 move |event: &MouseDownEvent, phase, _, cx| {
-                    if !bounds.contains(&event.position) || !phase.bubble() {
+                    if event.button != MouseButton::Left
+                        || !bounds.contains(&event.position)
+                        || !phase.bubble()
+                    {
                         return;
                     }
 
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 const NUMBERED_PREFIXES_1: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const NUMBERED_PREFIXES_2: &str = "abcdefghijklmnopqrstuvwxyz";
 
@@ -34,12 +36,51 @@ pub(super) fn list_item_prefix(ix: usize, ordered: bool, depth: usize) -> String
     }
 }
 
+/// Returns the byte range of the GFM task list marker (`[ ]`, `[x]` or `[X]`)
+/// in the list item `range` of the `source`.
+pub(super) fn task_marker_range(source: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let text = source.get(range.clone())?;
+    let start = text.find('[')?;
+    match text.as_bytes().get(start..start + 3)? {
+        [b'[', b' ' | b'x' | b'X', b']'] => Some(range.start + start..range.start + start + 3),
+        _ => None,
+    }
+}
 
-#[derive(Debug, Clone)]
-pub(crate) struct FileRef {
-    pub(crate) path: String,
-    pub(crate) line: Option<u32>,
-    pub(crate) col: Option<u32>,
+/// Returns the number in Unicode superscript digits, e.g. `12` to `¹²`, used for footnote references.
+pub(super) fn superscript_number(n: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    n.to_string()
+        .bytes()
+        .map(|b| DIGITS[(b - b'0') as usize])
+        .collect()
+}
+
+/// Returns the anchor slug of the heading text like GitHub, e.g. `Hello, World!` to `hello-world`.
+pub(super) fn slugify(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c.to_lowercase().next().unwrap_or(c))
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A file reference detected in code, e.g. `src/main.rs:10:5`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRef {
+    /// The path as written, may be relative.
+    pub path: String,
+    /// The 1-based line number.
+    pub line: Option<u32>,
+    /// The 1-based column number.
+    pub col: Option<u32>,
 }
 
 pub(crate) fn split_whitespace_token_ranges(text: &str) -> Vec<std::ops::Range<usize>> {
@@ -205,9 +246,66 @@ pub(crate) fn encode_uri_component(value: &str) -> String {
     out
 }
 
+/// Decode the `%XX` escapes in the URI component, the invalid escapes are kept as is.
+pub(super) fn decode_uri_component(value: &str) -> String {
+    String::from_utf8_lossy(&decode_uri_component_bytes(value)).into_owned()
+}
+
+/// Decode the `%XX` escapes in the URI component to bytes, used for the binary data.
+pub(super) fn decode_uri_component_bytes(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut ix = 0;
+    while ix < bytes.len() {
+        if bytes[ix] == b'%' {
+            // Check the digits first, `from_str_radix` accepts a leading `+`.
+            let hex = value
+                .get(ix + 1..ix + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+            if let Some(b) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(b);
+                ix += 3;
+                continue;
+            }
+        }
+        out.push(bytes[ix]);
+        ix += 1;
+    }
+    out
+}
+
+/// Decode the standard or URL-safe Base64, the whitespace is ignored.
+pub(super) fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(value.len() * 3 / 4);
+    let mut buf = 0u32;
+    let mut bits = 0;
+    for b in value.bytes() {
+        let n = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b if b.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        buf = (buf << 6) | n as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::text::utils::list_item_prefix;
+    use crate::text::utils::{
+        decode_base64, decode_uri_component, decode_uri_component_bytes, list_item_prefix, slugify,
+        superscript_number, task_marker_range,
+    };
 
     #[test]
     fn test_list_item_prefix() {
@@ -229,4 +327,50 @@ mod tests {
         assert_eq!(list_item_prefix(0, false, 3), "‣ ");
         assert_eq!(list_item_prefix(0, false, 4), "⁃ ");
     }
+
+    #[test]
+    fn test_task_marker_range() {
+        let source = "# Todo\n\n- [ ] foo\n- [x] bar\n  1. [X] baz\n- qux [a]";
+        assert_eq!(task_marker_range(source, 8..17), Some(10..13));
+        assert_eq!(&source[10..13], "[ ]");
+        assert_eq!(task_marker_range(source, 18..27), Some(20..23));
+        assert_eq!(task_marker_range(source, 30..40), Some(33..36));
+        assert_eq!(&source[33..36], "[X]");
+        assert_eq!(task_marker_range(source, 41..50), None);
+        assert_eq!(task_marker_range(source, 41..100), None);
+    }
+
+    #[test]
+    fn test_superscript_number() {
+        assert_eq!(superscript_number(0), "⁰");
+        assert_eq!(superscript_number(3), "³");
+        assert_eq!(superscript_number(128), "¹²⁸");
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify(" Getting Started "), "getting-started");
+        assert_eq!(slugify("foo_bar-baz 1.0"), "foo_bar-baz-10");
+        assert_eq!(slugify("中文 标题"), "中文-标题");
+    }
+
+    #[test]
+    fn test_decode_uri_component() {
+        assert_eq!(decode_uri_component("my%20image.png"), "my image.png");
+        assert_eq!(decode_uri_component("%E4%BD%A0%E5%A5%BD"), "你好");
+        assert_eq!(decode_uri_component("100%"), "100%");
+        assert_eq!(decode_uri_component("%zz"), "%zz");
+        assert_eq!(decode_uri_component("%+1"), "%+1");
+        assert_eq!(decode_uri_component_bytes("%89PNG%0d"), b"\x89PNG\r");
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("SGVsbG8=").unwrap(), b"Hello");
+        assert_eq!(decode_base64("SGVs\nbG8h").unwrap(), b"Hello!");
+        assert_eq!(decode_base64("-_8=").unwrap(), vec![0xfb, 0xff]);
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert!(decode_base64("SGV*").is_none());
+    }
 }
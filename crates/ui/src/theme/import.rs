@@ -0,0 +1,832 @@
+use std::cmp::Reverse;
+
+use anyhow::{Context as _, Result, anyhow};
+use serde_json::{Map, Value, json};
+
+use crate::{ThemeConfig, ThemeMode, try_parse_color};
+
+/// The VS Code `colors` keys mapped to the [`ThemeConfigColors`](crate::ThemeConfigColors) keys.
+const VSCODE_COLORS: &[(&str, &str)] = &[
+    ("foreground", "foreground"),
+    ("editor.background", "background"),
+    ("focusBorder", "ring"),
+    ("panel.border", "border"),
+    ("descriptionForeground", "muted.foreground"),
+    ("editorCursor.foreground", "caret"),
+    ("editor.selectionBackground", "selection.background"),
+    ("button.background", "primary.background"),
+    ("button.foreground", "primary.foreground"),
+    ("button.hoverBackground", "primary.hover.background"),
+    ("button.secondaryBackground", "secondary.background"),
+    ("button.secondaryForeground", "secondary.foreground"),
+    (
+        "button.secondaryHoverBackground",
+        "secondary.hover.background",
+    ),
+    ("input.border", "input.border"),
+    ("textLink.foreground", "link"),
+    ("textLink.activeForeground", "link.hover"),
+    ("list.activeSelectionBackground", "list.active.background"),
+    ("list.hoverBackground", "list.hover.background"),
+    ("editorWidget.background", "popover.background"),
+    ("editorWidget.foreground", "popover.foreground"),
+    ("progressBar.background", "progress.bar.background"),
+    ("scrollbarSlider.background", "scrollbar.thumb.background"),
+    (
+        "scrollbarSlider.hoverBackground",
+        "scrollbar.thumb.hover.background",
+    ),
+    ("sideBar.background", "sidebar.background"),
+    ("sideBar.foreground", "sidebar.foreground"),
+    ("sideBar.border", "sidebar.border"),
+    ("titleBar.activeBackground", "title_bar.background"),
+    ("titleBar.border", "title_bar.border"),
+    ("editorGroupHeader.tabsBackground", "tab_bar.background"),
+    ("tab.inactiveBackground", "tab.background"),
+    ("tab.inactiveForeground", "tab.foreground"),
+    ("tab.activeBackground", "tab.active.background"),
+    ("tab.activeForeground", "tab.active.foreground"),
+    ("terminal.ansiBlue", "base.blue"),
+    ("terminal.ansiBrightBlue", "base.blue.light"),
+    ("terminal.ansiCyan", "base.cyan"),
+    ("terminal.ansiBrightCyan", "base.cyan.light"),
+    ("terminal.ansiGreen", "base.green"),
+    ("terminal.ansiBrightGreen", "base.green.light"),
+    ("terminal.ansiMagenta", "base.magenta"),
+    ("terminal.ansiBrightMagenta", "base.magenta.light"),
+    ("terminal.ansiRed", "base.red"),
+    ("terminal.ansiBrightRed", "base.red.light"),
+    ("terminal.ansiYellow", "base.yellow"),
+    ("terminal.ansiBrightYellow", "base.yellow.light"),
+];
+
+/// The VS Code `colors` keys mapped to the [`HighlightThemeStyle`](crate::highlighter::HighlightThemeStyle) keys.
+const VSCODE_HIGHLIGHT_COLORS: &[(&str, &str)] = &[
+    ("editor.background", "editor.background"),
+    ("editor.foreground", "editor.foreground"),
+    (
+        "editor.lineHighlightBackground",
+        "editor.active_line.background",
+    ),
+    ("editorLineNumber.foreground", "editor.line_number"),
+    (
+        "editorLineNumber.activeForeground",
+        "editor.active_line_number",
+    ),
+    ("editorError.foreground", "error"),
+    ("editorWarning.foreground", "warning"),
+    ("editorInfo.foreground", "info"),
+    ("editorHint.foreground", "hint"),
+];
+
+/// The TextMate global settings mapped to the [`ThemeConfigColors`](crate::ThemeConfigColors) keys.
+const TM_COLORS: &[(&str, &str)] = &[
+    ("background", "background"),
+    ("foreground", "foreground"),
+    ("caret", "caret"),
+    ("selection", "selection.background"),
+];
+
+/// The TextMate global settings mapped to the [`HighlightThemeStyle`](crate::highlighter::HighlightThemeStyle) keys.
+const TM_HIGHLIGHT_COLORS: &[(&str, &str)] = &[
+    ("background", "editor.background"),
+    ("foreground", "editor.foreground"),
+    ("lineHighlight", "editor.active_line.background"),
+    ("gutterForeground", "editor.line_number"),
+];
+
+/// The tree-sitter capture names of [`SyntaxColors`](crate::highlighter::SyntaxColors),
+/// and the TextMate scopes they are mapped from.
+const SCOPES: &[(&str, &[&str])] = &[
+    ("attribute", &["entity.other.attribute-name"]),
+    ("boolean", &["constant.language.boolean"]),
+    ("comment", &["comment"]),
+    ("comment.doc", &["comment.block.documentation"]),
+    (
+        "constant",
+        &[
+            "constant.language",
+            "constant.other",
+            "variable.other.constant",
+        ],
+    ),
+    ("constructor", &["entity.name.function.constructor"]),
+    ("embedded", &["meta.embedded"]),
+    ("emphasis", &["markup.italic"]),
+    ("emphasis.strong", &["markup.bold"]),
+    ("enum", &["entity.name.type.enum"]),
+    (
+        "function",
+        &[
+            "entity.name.function",
+            "support.function",
+            "meta.function-call",
+        ],
+    ),
+    ("keyword", &["keyword", "storage.type", "storage.modifier"]),
+    ("label", &["entity.name.label"]),
+    ("link_text", &["string.other.link"]),
+    ("link_uri", &["markup.underline.link"]),
+    ("number", &["constant.numeric"]),
+    ("operator", &["keyword.operator"]),
+    (
+        "preproc",
+        &["meta.preprocessor", "keyword.control.directive"],
+    ),
+    (
+        "property",
+        &["variable.other.property", "support.type.property-name"],
+    ),
+    ("punctuation", &["punctuation"]),
+    ("punctuation.bracket", &["punctuation.section"]),
+    (
+        "punctuation.delimiter",
+        &["punctuation.separator", "punctuation.terminator"],
+    ),
+    ("punctuation.list_marker", &["punctuation.definition.list"]),
+    (
+        "punctuation.special",
+        &["punctuation.definition.template-expression"],
+    ),
+    ("string", &["string"]),
+    ("string.escape", &["constant.character.escape"]),
+    ("string.regex", &["string.regexp"]),
+    ("string.special", &["string.other"]),
+    ("string.special.symbol", &["constant.other.symbol"]),
+    ("tag", &["entity.name.tag"]),
+    ("tag.doctype", &["meta.tag.sgml.doctype"]),
+    ("text.literal", &["markup.inline.raw", "markup.raw"]),
+    ("title", &["markup.heading", "entity.name.section"]),
+    (
+        "type",
+        &[
+            "entity.name.type",
+            "entity.name.class",
+            "entity.other.inherited-class",
+            "support.type",
+            "support.class",
+        ],
+    ),
+    ("variable", &["variable"]),
+    ("variable.special", &["variable.language"]),
+    ("variant", &["variable.other.enummember"]),
+];
+
+/// A theme imported from VS Code or TextMate.
+#[derive(Debug, Clone)]
+pub struct ImportedTheme {
+    /// The theme config, the syntax colors are in the [`ThemeConfig::highlight`].
+    pub config: ThemeConfig,
+    /// The color keys that are not mapped, e.g. `activityBar.background`.
+    pub unmapped_colors: Vec<String>,
+    /// The TextMate scope selectors that are not mapped to any syntax color, e.g. `meta.diff`.
+    pub unmapped_scopes: Vec<String>,
+}
+
+impl ThemeConfig {
+    /// Import a VS Code color theme from the JSON (comments and trailing commas are allowed).
+    ///
+    /// The `colors` are mapped to the theme colors and the editor colors, and the `tokenColors`
+    /// are mapped from the TextMate scopes to the tree-sitter capture names.
+    ///
+    /// The keys can't be mapped are reported in the [`ImportedTheme`], so the result can be fine-tuned.
+    pub fn from_vscode_theme(json: &str) -> Result<ImportedTheme> {
+        let value: Value =
+            serde_json::from_str(&strip_json_comments(json)).context("parse VS Code theme")?;
+        let theme = value
+            .as_object()
+            .ok_or_else(|| anyhow!("VS Code theme must be an object"))?;
+
+        let mut importer = Importer::default();
+        if let Some(colors) = theme.get("colors").and_then(|colors| colors.as_object()) {
+            for (key, color) in colors {
+                importer.color(key, color, VSCODE_COLORS, VSCODE_HIGHLIGHT_COLORS);
+            }
+        }
+        match theme.get("tokenColors") {
+            Some(Value::Array(rules)) => importer.token_colors(rules),
+            Some(_) => importer.unmapped_colors.push("tokenColors".into()),
+            None => {}
+        }
+
+        let mode = match theme.get("type").and_then(|ty| ty.as_str()) {
+            Some("light") | Some("hcLight") => Some(ThemeMode::Light),
+            Some("dark") | Some("hc") | Some("hcDark") => Some(ThemeMode::Dark),
+            _ => None,
+        };
+        let name = theme.get("name").and_then(|name| name.as_str());
+
+        importer.finish(name, mode)
+    }
+
+    /// Import a TextMate (or Sublime Text) color theme from the `.tmTheme` plist.
+    ///
+    /// The global settings are mapped to the theme colors and the editor colors, and the scoped
+    /// settings are mapped from the TextMate scopes to the tree-sitter capture names.
+    ///
+    /// The keys can't be mapped are reported in the [`ImportedTheme`], so the result can be fine-tuned.
+    pub fn from_tm_theme(plist: &str) -> Result<ImportedTheme> {
+        let value = parse_plist(plist).context("parse TextMate theme")?;
+        let theme = value
+            .as_object()
+            .ok_or_else(|| anyhow!("TextMate theme must be a dict"))?;
+        let rules = theme
+            .get("settings")
+            .and_then(|settings| settings.as_array())
+            .ok_or_else(|| anyhow!("TextMate theme has no settings"))?;
+
+        let mut importer = Importer::default();
+        for rule in rules {
+            if rule.get("scope").is_some() {
+                continue;
+            }
+            if let Some(settings) = rule.get("settings").and_then(|s| s.as_object()) {
+                for (key, color) in settings {
+                    importer.color(key, color, TM_COLORS, TM_HIGHLIGHT_COLORS);
+                }
+            }
+        }
+        importer.token_colors(rules);
+
+        let name = theme.get("name").and_then(|name| name.as_str());
+        importer.finish(name, None)
+    }
+}
+
+#[derive(Default)]
+struct Importer {
+    colors: Map<String, Value>,
+    highlight: Map<String, Value>,
+    syntax: Map<String, Value>,
+    unmapped_colors: Vec<String>,
+    unmapped_scopes: Vec<String>,
+}
+
+/// The style of a capture, with the score of the selector it comes from.
+#[derive(Default)]
+struct ScopeStyle {
+    color: Option<(Score, String)>,
+    font: Option<(Score, Option<&'static str>, Option<u16>)>,
+}
+
+/// The specificity of a selector to a scope, a [`Score::Whole`] always wins a [`Score::Partial`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Score {
+    /// The selector is more specific than the scope, only matches a part of it,
+    /// e.g. `keyword.control` for `keyword`, the shorter selector wins.
+    Partial(Reverse<usize>),
+    /// The selector matches the whole scope, e.g. `keyword` for `keyword.operator`,
+    /// the longer selector wins.
+    Whole(usize),
+}
+
+impl Importer {
+    fn color(
+        &mut self,
+        key: &str,
+        color: &Value,
+        colors_map: &[(&str, &str)],
+        highlight_map: &[(&str, &str)],
+    ) {
+        let Some(color) = color
+            .as_str()
+            .filter(|color| try_parse_color(color).is_ok())
+        else {
+            self.unmapped_colors.push(key.to_string());
+            return;
+        };
+
+        let mut mapped = false;
+        for (from, to) in colors_map {
+            if *from == key {
+                self.colors.insert(to.to_string(), color.into());
+                mapped = true;
+            }
+        }
+        for (from, to) in highlight_map {
+            if *from == key {
+                self.highlight.insert(to.to_string(), color.into());
+                mapped = true;
+            }
+        }
+
+        if !mapped {
+            self.unmapped_colors.push(key.to_string());
+        }
+    }
+
+    /// Map the TextMate rules (`{ scope, settings: { foreground, fontStyle } }`) to the syntax colors.
+    ///
+    /// Like TextMate, the most specific selector wins, and the later rule wins if same specific.
+    /// A selector more specific than the mapped scopes is only used if no selector matches them.
+    fn token_colors(&mut self, rules: &[Value]) {
+        let mut styles: Vec<ScopeStyle> = SCOPES.iter().map(|_| ScopeStyle::default()).collect();
+
+        for rule in rules {
+            let selectors: Vec<&str> = match rule.get("scope") {
+                Some(Value::String(scope)) => scope.split(',').collect(),
+                Some(Value::Array(scopes)) => scopes.iter().filter_map(|s| s.as_str()).collect(),
+                _ => continue,
+            };
+            let Some(settings) = rule.get("settings").and_then(|s| s.as_object()) else {
+                continue;
+            };
+
+            let color = settings
+                .get("foreground")
+                .and_then(|color| color.as_str())
+                .filter(|color| try_parse_color(color).is_ok());
+            let font = settings
+                .get("fontStyle")
+                .and_then(|font| font.as_str())
+                .map(parse_font_style);
+
+            for selector in selectors {
+                let selector = selector.trim();
+                // Only the last scope of the descendant selector is used, e.g. `meta.function variable`,
+                // and the excluded scopes are ignored, e.g. `string -string.quoted`.
+                let scope = selector
+                    .split(" -")
+                    .next()
+                    .and_then(|s| s.split_whitespace().last())
+                    .unwrap_or_default();
+
+                let mut mapped = false;
+                for ((_, scopes), style) in SCOPES.iter().zip(styles.iter_mut()) {
+                    let Some(score) = scopes
+                        .iter()
+                        .filter_map(|target| selector_score(scope, target))
+                        .max()
+                    else {
+                        continue;
+                    };
+
+                    mapped = true;
+                    if let Some(color) = color {
+                        if style.color.as_ref().map_or(true, |(s, _)| score >= *s) {
+                            style.color = Some((score, color.to_string()));
+                        }
+                    }
+                    if let Some((font_style, font_weight)) = font {
+                        if style.font.as_ref().map_or(true, |(s, _, _)| score >= *s) {
+                            style.font = Some((score, font_style, font_weight));
+                        }
+                    }
+                }
+
+                if !mapped && !selector.is_empty() {
+                    self.unmapped_scopes.push(selector.to_string());
+                }
+            }
+        }
+
+        for ((name, _), style) in SCOPES.iter().zip(styles) {
+            let mut value = Map::new();
+            if let Some((_, color)) = style.color {
+                value.insert("color".into(), color.into());
+            }
+            if let Some((_, font_style, font_weight)) = style.font {
+                if let Some(font_style) = font_style {
+                    value.insert("font_style".into(), font_style.into());
+                }
+                if let Some(font_weight) = font_weight {
+                    value.insert("font_weight".into(), font_weight.into());
+                }
+            }
+            if !value.is_empty() {
+                self.syntax.insert(name.to_string(), value.into());
+            }
+        }
+    }
+
+    fn finish(mut self, name: Option<&str>, mode: Option<ThemeMode>) -> Result<ImportedTheme> {
+        let mode = mode.unwrap_or_else(|| {
+            let background = self
+                .highlight
+                .get("editor.background")
+                .and_then(|color| color.as_str())
+                .and_then(|color| try_parse_color(color).ok());
+            match background {
+                Some(background) if background.l < 0.5 => ThemeMode::Dark,
+                _ => ThemeMode::Light,
+            }
+        });
+
+        self.highlight.insert("syntax".into(), self.syntax.into());
+        let config = serde_json::from_value(json!({
+            "name": name.unwrap_or("Untitled"),
+            "mode": mode,
+            "colors": self.colors,
+            "highlight": self.highlight,
+        }))
+        .context("build theme config")?;
+
+        Ok(ImportedTheme {
+            config,
+            unmapped_colors: self.unmapped_colors,
+            unmapped_scopes: self.unmapped_scopes,
+        })
+    }
+}
+
+/// Returns the specificity if the `selector` matches the `scope`,
+/// e.g. `keyword` matches `keyword` and `keyword.operator`.
+///
+/// The `selector` more specific than the `scope` also matches with a lower score,
+/// e.g. `keyword.control` matches `keyword`, as the themes rarely style the general scopes.
+fn selector_score(selector: &str, scope: &str) -> Option<Score> {
+    fn is_prefix(prefix: &str, scope: &str) -> bool {
+        scope
+            .strip_prefix(prefix)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with('.'))
+    }
+
+    if selector.is_empty() {
+        None
+    } else if is_prefix(selector, scope) {
+        Some(Score::Whole(selector.len()))
+    } else if is_prefix(scope, selector) {
+        Some(Score::Partial(Reverse(selector.len())))
+    } else {
+        None
+    }
+}
+
+/// Parse the TextMate `fontStyle`, e.g. `italic bold`, returns the font style and weight.
+///
+/// An empty `fontStyle` resets the font style to normal.
+fn parse_font_style(font_style: &str) -> (Option<&'static str>, Option<u16>) {
+    let mut style = Some("normal");
+    let mut weight = None;
+    for part in font_style.split_whitespace() {
+        match part {
+            "italic" => style = Some("italic"),
+            "underline" if style != Some("italic") => style = Some("underline"),
+            "bold" => weight = Some(700),
+            _ => {}
+        }
+    }
+    (style, weight)
+}
+
+/// Remove the comments and the trailing commas in the JSON, which are allowed in VS Code themes.
+fn strip_json_comments(json: &str) -> String {
+    let mut output = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        output.push(next);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|c| *c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                while let Some(c) = chars.next() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '}' | ']' => {
+                let trimmed = output.trim_end().len();
+                if output[..trimmed].ends_with(',') {
+                    output.truncate(trimmed - 1);
+                }
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[derive(Debug, PartialEq)]
+enum PlistToken {
+    Open(String),
+    Close(String),
+    Empty(String),
+    Text(String),
+}
+
+fn tokenize_plist(xml: &str) -> Result<Vec<PlistToken>> {
+    let mut tokens = vec![];
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment
+                .find("-->")
+                .ok_or_else(|| anyhow!("unclosed comment"))?;
+            rest = &comment[end + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            let end = rest
+                .find('>')
+                .ok_or_else(|| anyhow!("unclosed declaration"))?;
+            rest = &rest[end + 1..];
+        } else if let Some(tag) = rest.strip_prefix('<') {
+            let end = tag.find('>').ok_or_else(|| anyhow!("unclosed tag"))?;
+            let content = tag[..end].trim();
+            let name = |s: &str| s.split_whitespace().next().unwrap_or_default().to_string();
+            if let Some(close) = content.strip_prefix('/') {
+                tokens.push(PlistToken::Close(name(close)));
+            } else if let Some(empty) = content.strip_suffix('/') {
+                tokens.push(PlistToken::Empty(name(empty)));
+            } else {
+                tokens.push(PlistToken::Open(name(content)));
+            }
+            rest = &tag[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push(PlistToken::Text(decode_xml_entities(&rest[..end])));
+            rest = &rest[end..];
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn decode_xml_entities(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+
+        let decoded = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                output.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Parse the plist XML to JSON value, the `<dict>` is an object and the `<array>` is an array,
+/// other values are strings, except `<true/>` and `<false/>`.
+fn parse_plist(xml: &str) -> Result<Value> {
+    let tokens = tokenize_plist(xml)?;
+    let mut tokens = tokens
+        .into_iter()
+        .filter(|token| !matches!(token, PlistToken::Text(text) if text.trim().is_empty()))
+        .peekable();
+
+    match tokens.next() {
+        Some(PlistToken::Open(name)) if name == "plist" => {}
+        _ => return Err(anyhow!("expected <plist>")),
+    }
+    parse_plist_value(&mut tokens)
+}
+
+fn parse_plist_value(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = PlistToken>>,
+) -> Result<Value> {
+    let tag = match tokens.next() {
+        Some(PlistToken::Open(tag)) => tag,
+        Some(PlistToken::Empty(tag)) => {
+            return Ok(match tag.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "dict" => Value::Object(Map::new()),
+                "array" => Value::Array(vec![]),
+                _ => Value::String(String::new()),
+            });
+        }
+        token => return Err(anyhow!("expected a value, got {:?}", token)),
+    };
+
+    let value = match tag.as_str() {
+        "dict" => {
+            let mut map = Map::new();
+            loop {
+                match tokens.next() {
+                    Some(PlistToken::Close(close)) if close == "dict" => return Ok(map.into()),
+                    Some(PlistToken::Open(key)) if key == "key" => {}
+                    token => return Err(anyhow!("expected <key>, got {:?}", token)),
+                }
+                let key = match tokens.next() {
+                    Some(PlistToken::Text(key)) => {
+                        tokens.next();
+                        key
+                    }
+                    _ => String::new(),
+                };
+                map.insert(key, parse_plist_value(tokens)?);
+            }
+        }
+        "array" => {
+            let mut items = vec![];
+            loop {
+                if matches!(tokens.peek(), Some(PlistToken::Close(close)) if close == "array") {
+                    tokens.next();
+                    return Ok(items.into());
+                }
+                items.push(parse_plist_value(tokens)?);
+            }
+        }
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => {
+            let text = match tokens.peek() {
+                Some(PlistToken::Text(_)) => match tokens.next() {
+                    Some(PlistToken::Text(text)) => text,
+                    _ => unreachable!(),
+                },
+                _ => String::new(),
+            };
+            Value::String(text)
+        }
+    };
+
+    match tokens.next() {
+        Some(PlistToken::Close(close)) if close == tag => Ok(value),
+        token => Err(anyhow!("expected </{}>, got {:?}", tag, token)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::HighlightStyle;
+
+    use std::cmp::Reverse;
+
+    use super::{Score, parse_plist, selector_score, strip_json_comments};
+    use crate::{ThemeConfig, ThemeMode, try_parse_color};
+
+    #[test]
+    fn test_selector_score() {
+        assert_eq!(selector_score("keyword", "keyword"), Some(Score::Whole(7)));
+        assert_eq!(
+            selector_score("keyword", "keyword.operator"),
+            Some(Score::Whole(7))
+        );
+        assert_eq!(
+            selector_score("keyword.control", "keyword"),
+            Some(Score::Partial(Reverse(15)))
+        );
+        assert_eq!(selector_score("key", "keyword"), None);
+        assert_eq!(selector_score("keyword", "keywords"), None);
+        assert_eq!(selector_score("", "keyword"), None);
+
+        // A selector matching the whole scope always wins.
+        assert!(
+            selector_score("string", "string") > selector_score("string.quoted.double", "string")
+        );
+        assert!(
+            selector_score("string.quoted", "string")
+                > selector_score("string.quoted.double", "string")
+        );
+    }
+
+    #[test]
+    fn test_strip_json_comments() {
+        let json = r#"{
+            // comment
+            "a": "http://foo", /* block */
+            "b": [1, 2,],
+        }"#;
+        let value: serde_json::Value = serde_json::from_str(&strip_json_comments(json)).unwrap();
+        assert_eq!(value["a"], "http://foo");
+        assert_eq!(value["b"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_from_vscode_theme() {
+        let json = r##"{
+            "name": "My Theme",
+            "type": "dark",
+            "colors": {
+                "editor.background": "#1e1e1e",
+                "editor.foreground": "#d4d4d4",
+                "activityBar.background": "#333333",
+            },
+            "tokenColors": [
+                { "scope": "keyword", "settings": { "foreground": "#569cd6" } },
+                { "scope": ["keyword.operator", "meta.diff"], "settings": { "foreground": "#d4d4d4" } },
+                { "scope": "comment", "settings": { "foreground": "#6a9955", "fontStyle": "italic" } },
+                { "scope": "keyword.control", "settings": { "foreground": "#c586c0" } },
+                { "scope": "entity.name.function.method", "settings": { "foreground": "#dcdcaa" } },
+            ],
+        }"##;
+
+        let imported = ThemeConfig::from_vscode_theme(json).unwrap();
+        let config = imported.config;
+        assert_eq!(config.name.as_ref(), "My Theme");
+        assert_eq!(config.mode, ThemeMode::Dark);
+        assert_eq!(config.colors.background.as_deref(), Some("#1e1e1e"));
+        assert_eq!(imported.unmapped_colors, vec!["activityBar.background"]);
+        assert_eq!(imported.unmapped_scopes, vec!["meta.diff"]);
+
+        let highlight = config.highlight.unwrap();
+        let color = |hex: &str| Some(try_parse_color(hex).unwrap());
+        assert_eq!(highlight.editor_background, color("#1e1e1e"));
+        assert_eq!(
+            highlight.syntax.style("keyword").and_then(|s| s.color),
+            color("#569cd6")
+        );
+        assert_eq!(
+            highlight.syntax.style("operator").and_then(|s| s.color),
+            color("#d4d4d4")
+        );
+        // The more specific selector is used if no selector matches the whole scope.
+        assert_eq!(
+            highlight.syntax.style("function").and_then(|s| s.color),
+            color("#dcdcaa")
+        );
+        assert_eq!(
+            highlight.syntax.style("comment"),
+            Some(HighlightStyle {
+                color: color("#6a9955"),
+                font_style: Some(gpui::FontStyle::Italic),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_tm_theme() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>name</key>
+    <string>Light &amp; Clean</string>
+    <key>settings</key>
+    <array>
+        <dict>
+            <key>settings</key>
+            <dict>
+                <key>background</key>
+                <string>#FFFFFF</string>
+                <key>invisibles</key>
+                <string>#E0E0E0</string>
+            </dict>
+        </dict>
+        <dict>
+            <key>scope</key>
+            <string>string, source.rust</string>
+            <key>settings</key>
+            <dict>
+                <key>foreground</key>
+                <string>#A31515</string>
+            </dict>
+        </dict>
+    </array>
+</dict>
+</plist>"#;
+
+        let value = parse_plist(plist).unwrap();
+        assert_eq!(value["name"], "Light & Clean");
+
+        let imported = ThemeConfig::from_tm_theme(plist).unwrap();
+        assert_eq!(imported.config.name.as_ref(), "Light & Clean");
+        assert_eq!(imported.config.mode, ThemeMode::Light);
+        assert_eq!(imported.unmapped_colors, vec!["invisibles"]);
+        assert_eq!(imported.unmapped_scopes, vec!["source.rust"]);
+        let highlight = imported.config.highlight.unwrap();
+        assert_eq!(
+            highlight.syntax.style("string").and_then(|s| s.color),
+            Some(try_parse_color("#A31515").unwrap())
+        );
+    }
+}